@@ -0,0 +1,19 @@
+#![no_main]
+
+use std::sync::LazyLock;
+
+use dectalk_bot_core::audio::get_wav_duration;
+use libfuzzer_sys::fuzz_target;
+use tokio::runtime::Runtime;
+
+static RUNTIME: LazyLock<Runtime> = LazyLock::new(|| {
+    tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap()
+});
+
+fuzz_target!(|data: &[u8]| {
+    if let Some(duration) = RUNTIME.block_on(get_wav_duration(data)) {
+        assert!(duration.is_finite() && duration >= 0.0);
+    }
+});
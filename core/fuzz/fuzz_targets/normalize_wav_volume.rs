@@ -0,0 +1,8 @@
+#![no_main]
+
+use dectalk_bot_core::audio::normalize_wav_volume;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = normalize_wav_volume(data);
+});
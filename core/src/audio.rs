@@ -0,0 +1,130 @@
+use std::{error::Error, io::Cursor};
+
+use tokio::io::AsyncReadExt;
+use tracing::instrument;
+
+// The part of the fmt chunk every PCM WAV has, anything after it is an extension
+const FMT_CHUNK_SIZE: usize = 16;
+
+/// Reads the duration from a PCM WAV's headers, or None if they don't make sense. Doesn't
+/// trust any sizes in the file, so it's safe on truncated or hostile input.
+pub async fn get_wav_duration(wav_bytes: &[u8]) -> Option<f64> {
+    let mut cursor = Cursor::new(wav_bytes);
+
+    let mut riff_header = [0; 12];
+    cursor.read_exact(&mut riff_header).await.ok()?;
+
+    if &riff_header[0..4] != b"RIFF" || &riff_header[8..12] != b"WAVE" {
+        return None;
+    }
+
+    let mut fmt_chunk_header = [0; 8];
+    cursor.read_exact(&mut fmt_chunk_header).await.ok()?;
+
+    if &fmt_chunk_header[0..4] != b"fmt " {
+        return None;
+    }
+
+    let fmt_chunk_size = u32::from_le_bytes(fmt_chunk_header[4..8].try_into().ok()?) as usize;
+    if fmt_chunk_size < FMT_CHUNK_SIZE || fmt_chunk_size > wav_bytes.len() {
+        return None;
+    }
+
+    let mut fmt_chunk_data = vec![0; fmt_chunk_size];
+    cursor.read_exact(&mut fmt_chunk_data).await.ok()?;
+    skip_padding(&mut cursor, fmt_chunk_size as u64);
+
+    let audio_format = u16::from_le_bytes(fmt_chunk_data[0..2].try_into().ok()?);
+    // let num_channels = u16::from_le_bytes(fmt_chunk_data[2..4].try_into().ok()?);
+    let sample_rate = u32::from_le_bytes(fmt_chunk_data[4..8].try_into().ok()?);
+    // let byte_rate = u32::from_le_bytes(fmt_chunk_data[8..12].try_into().ok()?);
+    let block_align = u16::from_le_bytes(fmt_chunk_data[12..14].try_into().ok()?);
+    // let bits_per_sample = u16::from_le_bytes(fmt_chunk_data[14..16].try_into().ok()?);
+
+    if audio_format != 1 || sample_rate == 0 || block_align == 0 {
+        return None;
+    }
+
+    let mut data_chunk_header = [0; 8];
+    cursor.read_exact(&mut data_chunk_header).await.ok()?;
+
+    while &data_chunk_header[0..4] != b"data" {
+        let chunk_size = u32::from_le_bytes(data_chunk_header[4..8].try_into().ok()?);
+        cursor.set_position(cursor.position() + chunk_size as u64);
+        skip_padding(&mut cursor, chunk_size as u64);
+        cursor.read_exact(&mut data_chunk_header).await.ok()?;
+    }
+
+    // A truncated file only has as much audio as is actually there
+    let data_chunk_size = u32::from_le_bytes(data_chunk_header[4..8].try_into().ok()?)
+        .min((wav_bytes.len() as u64 - cursor.position()) as u32);
+
+    let num_samples = data_chunk_size as f64 / block_align as f64;
+    let duration = num_samples / sample_rate as f64;
+
+    Some(duration)
+}
+
+/// Chunks are padded to an even length.
+fn skip_padding(cursor: &mut Cursor<&[u8]>, chunk_size: u64) {
+    if chunk_size % 2 == 1 {
+        cursor.set_position(cursor.position() + 1);
+    }
+}
+
+#[instrument(skip_all)]
+pub fn concat_wavs(wavs: &[Vec<u8>]) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+    let first = match wavs {
+        [] => return Err("No audio to join".into()),
+        [wav] => return Ok(wav.clone()),
+        [first, ..] => first,
+    };
+
+    let spec = check_spec(hound::WavReader::new(Cursor::new(first))?.spec())?;
+    let mut buf = Vec::new();
+    let mut writer = hound::WavWriter::new(Cursor::new(&mut buf), spec)?;
+    for wav in wavs {
+        let mut reader = hound::WavReader::new(Cursor::new(wav))?;
+        for sample in reader.samples::<i16>() {
+            writer.write_sample(sample?)?;
+        }
+    }
+    writer.finalize()?;
+    Ok(buf)
+}
+
+#[instrument(skip_all)]
+pub fn normalize_wav_volume(wav_file: &[u8]) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+    let mut reader = hound::WavReader::new(Cursor::new(wav_file))?;
+    let spec = check_spec(reader.spec())?;
+    // A truncated file keeps erroring for every sample its header promised, so stop at the first
+    let samples: Vec<i16> = reader.samples::<i16>().map_while(Result::ok).collect();
+    let max_sample = samples.iter().cloned().fold(0, i16::max);
+    let min_sample = samples.iter().cloned().fold(0, i16::min);
+    let max_amplitude = i16::MAX;
+    let min_amplitude = i16::MIN;
+    let mut normalized_samples = Vec::with_capacity(samples.len());
+    for sample in samples {
+        let normalized_sample = if sample > 0 {
+            sample as f64 / max_sample as f64 * max_amplitude as f64
+        } else {
+            sample as f64 / min_sample as f64 * min_amplitude as f64
+        };
+        normalized_samples.push(normalized_sample as i16);
+    }
+    let mut buf = Vec::new();
+    let mut writer = hound::WavWriter::new(Cursor::new(&mut buf), spec)?;
+    for sample in normalized_samples {
+        writer.write_sample(sample)?;
+    }
+    writer.finalize()?;
+    Ok(buf)
+}
+
+/// hound reads headers it can't write back, and panics on some of them.
+fn check_spec(spec: hound::WavSpec) -> Result<hound::WavSpec, Box<dyn Error + Send + Sync>> {
+    if spec.sample_rate == 0 {
+        return Err("WAV has no sample rate".into());
+    }
+    Ok(spec)
+}
@@ -0,0 +1,63 @@
+use std::{error::Error, path::PathBuf};
+
+use async_trait::async_trait;
+use dectalk_rs::Say;
+
+use crate::dectalk::{DectalkVoice, Language};
+
+/// Turns text into WAV audio. The bot only ever talks to DECtalk through this, so tests and
+/// other frontends can swap in their own engine.
+#[async_trait]
+pub trait TtsEngine: Send + Sync {
+    async fn synthesize(
+        &self,
+        text: &str,
+        voice: &DectalkVoice,
+        language: Language,
+    ) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>>;
+
+    /// The audio along with the phonemes it was spoken as. Engines that can't say leave the
+    /// phonemes empty.
+    async fn synthesize_with_phonemes(
+        &self,
+        text: &str,
+        voice: &DectalkVoice,
+        language: Language,
+    ) -> Result<(Vec<u8>, String), Box<dyn Error + Send + Sync>> {
+        Ok((self.synthesize(text, voice, language).await?, String::new()))
+    }
+}
+
+/// DECtalk's `say` binary, see [`Say`].
+#[derive(Debug, Clone)]
+pub struct SayEngine {
+    pub say_path: PathBuf,
+    pub output_dir: PathBuf,
+}
+
+#[async_trait]
+impl TtsEngine for SayEngine {
+    async fn synthesize(
+        &self,
+        text: &str,
+        voice: &DectalkVoice,
+        language: Language,
+    ) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        Say::new(&self.say_path)
+            .output_dir(&self.output_dir)
+            .synthesize(text, voice, language)
+            .await
+    }
+
+    async fn synthesize_with_phonemes(
+        &self,
+        text: &str,
+        voice: &DectalkVoice,
+        language: Language,
+    ) -> Result<(Vec<u8>, String), Box<dyn Error + Send + Sync>> {
+        Say::new(&self.say_path)
+            .output_dir(&self.output_dir)
+            .synthesize_with_phonemes(text, voice, language)
+            .await
+    }
+}
@@ -0,0 +1,14 @@
+//! Everything between a chat message and the audio the bot plays: per-user DECtalk voices, text
+//! preprocessing, the TTS engine and WAV handling. Nothing here knows about Discord, so other
+//! frontends and tests can use it directly.
+
+pub mod audio;
+pub mod engine;
+pub mod morse;
+pub mod preprocess;
+pub mod profanity;
+pub mod pronunciation;
+pub mod slang;
+pub mod verbalize;
+
+pub use dectalk_rs as dectalk;
@@ -0,0 +1,105 @@
+/// Milliseconds in a dot, about 20 words per minute.
+const UNIT: u32 = 60;
+const FREQUENCY: u32 = 600;
+
+const CODE: &[(char, &str)] = &[
+    ('a', ".-"),
+    ('b', "-..."),
+    ('c', "-.-."),
+    ('d', "-.."),
+    ('e', "."),
+    ('f', "..-."),
+    ('g', "--."),
+    ('h', "...."),
+    ('i', ".."),
+    ('j', ".---"),
+    ('k', "-.-"),
+    ('l', ".-.."),
+    ('m', "--"),
+    ('n', "-."),
+    ('o', "---"),
+    ('p', ".--."),
+    ('q', "--.-"),
+    ('r', ".-."),
+    ('s', "..."),
+    ('t', "-"),
+    ('u', "..-"),
+    ('v', "...-"),
+    ('w', ".--"),
+    ('x', "-..-"),
+    ('y', "-.--"),
+    ('z', "--.."),
+    ('0', "-----"),
+    ('1', ".----"),
+    ('2', "..---"),
+    ('3', "...--"),
+    ('4', "....-"),
+    ('5', "....."),
+    ('6', "-...."),
+    ('7', "--..."),
+    ('8', "---.."),
+    ('9', "----."),
+    ('.', ".-.-.-"),
+    (',', "--..--"),
+    ('?', "..--.."),
+    ('\'', ".----."),
+    ('!', "-.-.--"),
+    ('/', "-..-."),
+    ('(', "-.--."),
+    (')', "-.--.-"),
+    ('&', ".-..."),
+    (':', "---..."),
+    (';', "-.-.-."),
+    ('=', "-...-"),
+    ('+', ".-.-."),
+    ('-', "-....-"),
+    ('"', ".-..-."),
+    ('@', ".--.-."),
+];
+
+fn rest(units: u32) -> String {
+    format!("[_<{}>]", units * UNIT)
+}
+
+/// Spells `text` out as DECtalk tones, skipping characters Morse code has no symbol for. Returns
+/// `None` when nothing is left to play.
+pub fn to_tones(text: &str) -> Option<String> {
+    let words = text
+        .split_whitespace()
+        .map(|word| {
+            word.chars()
+                .filter_map(|c| {
+                    let c = c.to_ascii_lowercase();
+                    CODE.iter()
+                        .find(|(letter, _)| *letter == c)
+                        .map(|(_, code)| *code)
+                })
+                .collect::<Vec<_>>()
+        })
+        .filter(|codes| !codes.is_empty())
+        .collect::<Vec<_>>();
+    if words.is_empty() {
+        return None;
+    }
+
+    let tones = words
+        .iter()
+        .map(|codes| {
+            codes
+                .iter()
+                .map(|code| {
+                    code.chars()
+                        .map(|symbol| {
+                            let units = if symbol == '-' { 3 } else { 1 };
+                            format!("[:tone {} {}]", FREQUENCY, units * UNIT)
+                        })
+                        .collect::<Vec<_>>()
+                        .join(&rest(1))
+                })
+                .collect::<Vec<_>>()
+                .join(&rest(3))
+        })
+        .collect::<Vec<_>>()
+        .join(&rest(7));
+    Some(tones)
+}
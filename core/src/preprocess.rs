@@ -0,0 +1,224 @@
+use std::{collections::HashMap, sync::LazyLock};
+
+use regex::Regex;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{dectalk::Language, slang, verbalize};
+
+pub const BUILTIN_VOICES: [(&str, &str); 10] = [
+    ("paul", "p"),
+    ("harry", "h"),
+    ("frank", "f"),
+    ("dennis", "d"),
+    ("betty", "b"),
+    ("ursula", "u"),
+    ("wendy", "w"),
+    ("rita", "r"),
+    ("kit", "k"),
+    ("val", "v"),
+];
+
+static LINK: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"https?://[^\s/$.?#].[^\s]*").unwrap());
+static LINK_DOMAIN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"https?://(?:www\.)?([^\s/$.?#:][^\s/?#:]*)[^\s]*").unwrap());
+static DISCORD_EMOJI: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<a?:(\w+):\d+>").unwrap());
+static ROLL: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\[:roll\s*(\d+)\s*\]").unwrap());
+static VOICE_TAG: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)^\s*\[:(?:name\s*(\w+)|n(\w))\s*\]").unwrap());
+
+/// The per-server settings that change how a message is read.
+#[derive(Debug, Clone, Copy)]
+pub struct PreprocessOptions<'a> {
+    pub read_link_domains: bool,
+    pub expand_slang: bool,
+    /// Server-specific additions to the built-in slang list.
+    pub slang: &'a HashMap<String, String>,
+    /// How many Unicode emoji to read by name, 0 to drop them all.
+    pub emoji_limit: usize,
+}
+
+/// Turns a raw chat message into something DECtalk can read naturally.
+pub fn process_message(text: &str, options: &PreprocessOptions) -> String {
+    let text = if options.read_link_domains {
+        replace_links_with_domains(text)
+    } else {
+        remove_links(text)
+    };
+    let text = replace_discord_emojis(&text);
+    let text = collapse_repetition(&text);
+    let text = if options.expand_slang {
+        slang::expand_slang(&text, options.slang)
+    } else {
+        text
+    };
+    let text = verbalize::verbalize(&text);
+    let text = replace_unicode_emojis(&text, options.emoji_limit);
+    text.trim().to_string()
+}
+
+pub fn remove_links(text: &str) -> String {
+    LINK.replace_all(text, "").to_string()
+}
+
+pub fn replace_links_with_domains(text: &str) -> String {
+    LINK_DOMAIN
+        .replace_all(text, |caps: &regex::Captures| {
+            let domain = caps.get(1).unwrap().as_str().trim_end_matches('.');
+            format!(" link to {} ", domain.replace('.', " dot "))
+        })
+        .to_string()
+}
+
+pub fn replace_discord_emojis(text: &str) -> String {
+    let result = DISCORD_EMOJI.replace_all(text, |caps: &regex::Captures| {
+        let emoji_name = caps.get(1).unwrap().as_str().to_string();
+        emoji_name
+    });
+
+    result.to_string()
+}
+
+pub fn collapse_repetition(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut previous = None;
+    let mut run = 0;
+    for c in text.chars() {
+        if Some(c) == previous {
+            run += 1;
+        } else {
+            previous = Some(c);
+            run = 1;
+        }
+
+        // Digits are left alone so numbers keep their value
+        let max_run = if c.is_alphabetic() {
+            2
+        } else if c.is_ascii_punctuation() {
+            if c == '.' {
+                3
+            } else {
+                1
+            }
+        } else {
+            usize::MAX
+        };
+
+        if run <= max_run {
+            result.push(c);
+        }
+    }
+    result
+}
+
+pub fn replace_unicode_emojis(text: &str, limit: usize) -> String {
+    let mut count = 0;
+    text.graphemes(true)
+        .map(|grapheme| match emojis::get(grapheme) {
+            Some(emoji) => {
+                count += 1;
+                if count > limit {
+                    return String::new();
+                }
+
+                let name = emoji.shortcode().unwrap_or(emoji.name());
+                format!(" {} ", name.replace('_', " "))
+            }
+            None => grapheme.to_string(),
+        })
+        .collect()
+}
+
+pub fn truncate(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((index, _)) => text[..index].to_string(),
+        None => text.to_string(),
+    }
+}
+
+pub fn chunk_text(text: &str, max_len: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut chunk = String::new();
+    for word in text.split_whitespace() {
+        if !chunk.is_empty() && chunk.len() + word.len() + 1 > max_len {
+            chunks.push(std::mem::take(&mut chunk));
+        }
+        if !chunk.is_empty() {
+            chunk.push(' ');
+        }
+        chunk.push_str(word);
+    }
+    if !chunk.is_empty() {
+        chunks.push(chunk);
+    }
+    chunks
+}
+
+pub fn estimate_duration(text: &str, words_per_minute: f64) -> f64 {
+    // Long words take longer to say, so count roughly every six letters as a word
+    let words = text
+        .split_whitespace()
+        .map(|word| (word.chars().count() as f64 / 6.0).ceil())
+        .sum::<f64>();
+    words * 60.0 / words_per_minute
+}
+
+pub fn get_requested_roll(content: &str) -> Option<u64> {
+    let caps = ROLL.captures(content)?;
+    let roll = caps.get(1)?.as_str().parse::<u64>().ok()?;
+    Some(roll)
+}
+
+pub fn take_voice_tag(content: &str) -> (Option<&'static str>, &str) {
+    let caps = match VOICE_TAG.captures(content) {
+        Some(caps) => caps,
+        None => return (None, content),
+    };
+
+    let requested = caps
+        .get(1)
+        .or(caps.get(2))
+        .map(|m| m.as_str().to_lowercase())
+        .unwrap_or_default();
+    let voice = BUILTIN_VOICES
+        .iter()
+        .find(|(name, short)| *name == requested || *short == requested)
+        .map(|(name, _)| *name);
+    match voice {
+        Some(voice) => (Some(voice), &content[caps.get(0).unwrap().end()..]),
+        None => (None, content),
+    }
+}
+
+pub fn remove_requested_roll(content: &str) -> String {
+    ROLL.replace_all(content, "").to_string()
+}
+
+pub fn detect_foreign_language(text: &str) -> Option<whatlang::Lang> {
+    let info = whatlang::detect(text)?;
+    if !info.is_reliable() || info.lang() == whatlang::Lang::Eng {
+        return None;
+    }
+    Some(info.lang())
+}
+
+pub fn to_dectalk_language(lang: whatlang::Lang) -> Option<Language> {
+    match lang {
+        whatlang::Lang::Spa => Some(Language::Spanish),
+        whatlang::Lang::Deu => Some(Language::German),
+        whatlang::Lang::Fra => Some(Language::French),
+        _ => None,
+    }
+}
+
+pub fn spell_out(text: &str) -> String {
+    text.split_whitespace()
+        .map(|word| {
+            word.chars()
+                .filter(|c| c.is_alphanumeric())
+                .map(|c| c.to_string())
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
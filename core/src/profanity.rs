@@ -0,0 +1,78 @@
+use std::{fmt, str::FromStr};
+
+use regex::{NoExpand, Regex};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProfanityAction {
+    Skip,
+    Bleep,
+    Replace,
+}
+
+impl fmt::Display for ProfanityAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            ProfanityAction::Skip => "skip",
+            ProfanityAction::Bleep => "bleep",
+            ProfanityAction::Replace => "replace",
+        })
+    }
+}
+
+impl FromStr for ProfanityAction {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "skip" => Ok(ProfanityAction::Skip),
+            "bleep" => Ok(ProfanityAction::Bleep),
+            "replace" => Ok(ProfanityAction::Replace),
+            _ => Err(format!("Expected skip, bleep or replace, got `{}`", value)),
+        }
+    }
+}
+
+/// Compiles the words once, so filtering a message doesn't have to.
+pub fn compile_words(words: &[String]) -> Vec<Regex> {
+    words
+        .iter()
+        // An empty word would match between every pair of letters
+        .filter(|word| !word.is_empty())
+        .filter_map(
+            |word| match Regex::new(&format!(r"(?i)\b{}\b", regex::escape(word))) {
+                Ok(re) => Some(re),
+                Err(e) => {
+                    warn!(error = ?e, "Invalid profanity word {}", word);
+                    None
+                }
+            },
+        )
+        .collect()
+}
+
+/// Returns `None` when the message should be skipped entirely. `words` come from
+/// [`compile_words`].
+pub fn apply_profanity_filter(
+    text: &str,
+    words: &[Regex],
+    action: ProfanityAction,
+    replacement: &str,
+) -> Option<String> {
+    let mut text = text.to_string();
+    for re in words {
+        if !re.is_match(&text) {
+            continue;
+        }
+
+        let replacement = match action {
+            ProfanityAction::Skip => return None,
+            ProfanityAction::Bleep => " [:tone 1000 300] ",
+            ProfanityAction::Replace => replacement,
+        };
+        text = re.replace_all(&text, NoExpand(replacement)).to_string();
+    }
+    Some(text)
+}
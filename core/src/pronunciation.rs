@@ -0,0 +1,42 @@
+use std::{collections::HashMap, error::Error, path::Path};
+
+use regex::{NoExpand, Regex};
+use tokio::fs;
+use tracing::debug;
+
+#[derive(Default)]
+pub struct PronunciationMap {
+    rules: Vec<(Regex, String)>,
+}
+
+impl PronunciationMap {
+    pub fn new() -> Self {
+        PronunciationMap { rules: Vec::new() }
+    }
+
+    /// Reads a JSON object of words and how to say them.
+    pub async fn load(path: &Path) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        debug!("Loading pronunciations...");
+        let pronunciations_string = fs::read_to_string(path).await?;
+        let pronunciations: HashMap<String, String> = serde_json::from_str(&pronunciations_string)?;
+
+        let mut rules = Vec::with_capacity(pronunciations.len());
+        for (word, replacement) in pronunciations {
+            let re = Regex::new(&format!(r"(?i)\b{}\b", regex::escape(&word)))?;
+            rules.push((re, replacement));
+        }
+
+        // Longer entries first, so multi-word phrases win over their individual words
+        rules.sort_by_key(|(re, _)| std::cmp::Reverse(re.as_str().len()));
+
+        Ok(PronunciationMap { rules })
+    }
+
+    pub fn apply(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for (re, replacement) in &self.rules {
+            text = re.replace_all(&text, NoExpand(replacement)).to_string();
+        }
+        text
+    }
+}
@@ -0,0 +1,70 @@
+use std::{collections::HashMap, sync::LazyLock};
+
+use regex::{Captures, Regex};
+
+static WORD: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\b\w+\b").unwrap());
+
+const SLANG: &[(&str, &str)] = &[
+    ("afaik", "as far as I know"),
+    ("afk", "away from keyboard"),
+    ("bc", "because"),
+    ("brb", "be right back"),
+    ("btw", "by the way"),
+    ("fr", "for real"),
+    ("ftw", "for the win"),
+    ("gg", "good game"),
+    ("gl", "good luck"),
+    ("hf", "have fun"),
+    ("idc", "I don't care"),
+    ("idk", "I don't know"),
+    ("ikr", "I know right"),
+    ("imho", "in my humble opinion"),
+    ("imo", "in my opinion"),
+    ("irl", "in real life"),
+    ("jk", "just kidding"),
+    ("lmk", "let me know"),
+    ("ngl", "not gonna lie"),
+    ("np", "no problem"),
+    ("nvm", "never mind"),
+    ("omw", "on my way"),
+    ("rn", "right now"),
+    ("smh", "shaking my head"),
+    ("tbh", "to be honest"),
+    ("thx", "thanks"),
+    ("ty", "thank you"),
+    ("wdym", "what do you mean"),
+    ("wyd", "what are you doing"),
+    ("yw", "you're welcome"),
+];
+
+pub fn expand_slang(text: &str, custom: &HashMap<String, String>) -> String {
+    WORD.replace_all(text, |caps: &Captures| {
+        let word = caps[0].to_lowercase();
+        if let Some(expansion) = custom.get(&word) {
+            return expansion.clone();
+        }
+
+        match SLANG.iter().find(|(abbreviation, _)| *abbreviation == word) {
+            Some((_, expansion)) => expansion.to_string(),
+            None => caps[0].to_string(),
+        }
+    })
+    .to_string()
+}
+
+/// Replaces whole word triggers with their phrases, ignoring case. Earlier tables win, so a
+/// user's own macros can shadow the server's.
+pub fn expand_macros(text: &str, tables: &[&HashMap<String, String>]) -> String {
+    if tables.iter().all(|table| table.is_empty()) {
+        return text.to_string();
+    }
+
+    WORD.replace_all(text, |caps: &Captures| {
+        let word = caps[0].to_lowercase();
+        match tables.iter().find_map(|table| table.get(&word)) {
+            Some(phrase) => phrase.clone(),
+            None => caps[0].to_string(),
+        }
+    })
+    .to_string()
+}
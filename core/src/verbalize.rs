@@ -0,0 +1,296 @@
+use std::sync::LazyLock;
+
+use regex::{Captures, Regex};
+
+static DATE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b(\d{4})-(\d{2})-(\d{2})\b").unwrap());
+static TIME: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)\b(\d{1,2}):(\d{2})(?:\s?([ap])\.?m\b\.?)?").unwrap());
+static CURRENCY: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"([$€£])(\d{1,3}(?:,\d{3})+|\d+)(?:\.(\d+))?(k|K|M|B|bn)?\b").unwrap()
+});
+static SUFFIXED_NUMBER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b(\d+)(?:\.(\d+))?(k|K|M|B|bn)\b").unwrap());
+
+const ONES: [&str; 20] = [
+    "zero",
+    "one",
+    "two",
+    "three",
+    "four",
+    "five",
+    "six",
+    "seven",
+    "eight",
+    "nine",
+    "ten",
+    "eleven",
+    "twelve",
+    "thirteen",
+    "fourteen",
+    "fifteen",
+    "sixteen",
+    "seventeen",
+    "eighteen",
+    "nineteen",
+];
+const TENS: [&str; 10] = [
+    "", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+];
+const SCALES: [&str; 7] = [
+    "",
+    "thousand",
+    "million",
+    "billion",
+    "trillion",
+    "quadrillion",
+    "quintillion",
+];
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+pub fn verbalize(text: &str) -> String {
+    let text = verbalize_dates(text);
+    let text = verbalize_times(&text);
+    let text = verbalize_currency(&text);
+    verbalize_suffixed_numbers(&text)
+}
+
+pub fn number_to_words(n: u64) -> String {
+    if n < 20 {
+        return ONES[n as usize].to_string();
+    }
+
+    if n < 100 {
+        return match n % 10 {
+            0 => TENS[(n / 10) as usize].to_string(),
+            ones => format!("{} {}", TENS[(n / 10) as usize], ONES[ones as usize]),
+        };
+    }
+
+    if n < 1000 {
+        return match n % 100 {
+            0 => format!("{} hundred", ONES[(n / 100) as usize]),
+            rest => format!(
+                "{} hundred {}",
+                ONES[(n / 100) as usize],
+                number_to_words(rest)
+            ),
+        };
+    }
+
+    let mut groups = Vec::new();
+    let mut remaining = n;
+    let mut scale = 0;
+    while remaining > 0 {
+        let group = remaining % 1000;
+        if group > 0 {
+            let words = number_to_words(group);
+            groups.push(match SCALES[scale] {
+                "" => words,
+                scale => format!("{} {}", words, scale),
+            });
+        }
+        remaining /= 1000;
+        scale += 1;
+    }
+    groups.reverse();
+    groups.join(" ")
+}
+
+pub fn ordinal_to_words(n: u64) -> String {
+    let words = number_to_words(n);
+    let (head, last) = match words.rsplit_once(' ') {
+        Some((head, last)) => (format!("{} ", head), last),
+        None => (String::new(), words.as_str()),
+    };
+
+    let last = match last {
+        "one" => "first".to_string(),
+        "two" => "second".to_string(),
+        "three" => "third".to_string(),
+        "five" => "fifth".to_string(),
+        "eight" => "eighth".to_string(),
+        "nine" => "ninth".to_string(),
+        "twelve" => "twelfth".to_string(),
+        last if last.ends_with('y') => format!("{}ieth", &last[..last.len() - 1]),
+        last => format!("{}th", last),
+    };
+    head + &last
+}
+
+fn year_to_words(year: u64) -> String {
+    // Years are read in pairs ("nineteen ninety nine") except around the turn of a millennium
+    match (year / 100, year % 100) {
+        (_, 0) if year.is_multiple_of(1000) => number_to_words(year),
+        (century, 0) => format!("{} hundred", number_to_words(century)),
+        (century, rest) if century.is_multiple_of(10) && rest < 10 => number_to_words(year),
+        (century, rest) if rest < 10 => {
+            format!("{} oh {}", number_to_words(century), number_to_words(rest))
+        }
+        (century, rest) => format!("{} {}", number_to_words(century), number_to_words(rest)),
+    }
+}
+
+fn decimal_to_words(integer: &str, fraction: Option<&str>) -> Option<String> {
+    let integer = number_to_words(integer.replace(',', "").parse::<u64>().ok()?);
+    match fraction {
+        Some(fraction) if !fraction.is_empty() => {
+            let digits = fraction
+                .chars()
+                .filter_map(|c| c.to_digit(10))
+                .map(|d| ONES[d as usize])
+                .collect::<Vec<_>>()
+                .join(" ");
+            Some(format!("{} point {}", integer, digits))
+        }
+        _ => Some(integer),
+    }
+}
+
+fn scale_suffix_to_words(suffix: &str) -> Option<&'static str> {
+    match suffix {
+        "k" | "K" => Some("thousand"),
+        "M" => Some("million"),
+        "B" | "bn" => Some("billion"),
+        _ => None,
+    }
+}
+
+fn verbalize_dates(text: &str) -> String {
+    DATE.replace_all(text, |caps: &Captures| {
+        let year = caps[1].parse::<u64>().unwrap_or(0);
+        let month = caps[2].parse::<usize>().unwrap_or(0);
+        let day = caps[3].parse::<u64>().unwrap_or(0);
+        if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+            return caps[0].to_string();
+        }
+
+        format!(
+            "{} {}, {}",
+            MONTHS[month - 1],
+            ordinal_to_words(day),
+            year_to_words(year)
+        )
+    })
+    .to_string()
+}
+
+fn verbalize_times(text: &str) -> String {
+    TIME.replace_all(text, |caps: &Captures| {
+        let hour = caps[1].parse::<u64>().unwrap_or(0);
+        let minute = caps[2].parse::<u64>().unwrap_or(0);
+        let meridiem = caps.get(3).map(|m| m.as_str().to_uppercase());
+        if hour > 23 || minute > 59 || (meridiem.is_some() && !(1..=12).contains(&hour)) {
+            return caps[0].to_string();
+        }
+
+        // "15:00" and "03:00" are 24-hour times, read the military way, but "3:00" isn't
+        let leading_zero = caps[1].len() == 2 && caps[1].starts_with('0');
+        let mut words = number_to_words(hour);
+        if leading_zero && hour > 0 {
+            words.insert_str(0, "oh ");
+        }
+        match minute {
+            0 if meridiem.is_none() && (hour >= 13 || leading_zero) => words.push_str(" hundred"),
+            0 if meridiem.is_none() => words.push_str(" o'clock"),
+            0 => {}
+            1..=9 => words.push_str(&format!(" oh {}", number_to_words(minute))),
+            _ => words.push_str(&format!(" {}", number_to_words(minute))),
+        }
+        if let Some(meridiem) = meridiem {
+            words.push_str(&format!(" {} M", meridiem));
+        }
+        words
+    })
+    .to_string()
+}
+
+fn verbalize_currency(text: &str) -> String {
+    CURRENCY
+        .replace_all(text, |caps: &Captures| {
+            let (singular, plural, cent_singular, cent_plural) = match &caps[1] {
+                "$" => ("dollar", "dollars", "cent", "cents"),
+                "€" => ("euro", "euros", "cent", "cents"),
+                _ => ("pound", "pounds", "penny", "pence"),
+            };
+
+            let fraction = caps.get(3).map(|m| m.as_str());
+            if let Some(scale) = caps.get(4).and_then(|m| scale_suffix_to_words(m.as_str())) {
+                return match decimal_to_words(&caps[2], fraction) {
+                    Some(amount) => format!("{} {} {}", amount, scale, plural),
+                    None => caps[0].to_string(),
+                };
+            }
+
+            let whole = match caps[2].replace(',', "").parse::<u64>() {
+                Ok(whole) => whole,
+                Err(_) => return caps[0].to_string(),
+            };
+            let cents = match fraction {
+                Some(fraction) if fraction.len() <= 2 => {
+                    format!("{:0<2}", fraction).parse::<u64>().unwrap_or(0)
+                }
+                Some(_) => return caps[0].to_string(),
+                None => 0,
+            };
+
+            let whole_words = format!(
+                "{} {}",
+                number_to_words(whole),
+                if whole == 1 { singular } else { plural }
+            );
+            let cent_words = format!(
+                "{} {}",
+                number_to_words(cents),
+                if cents == 1 {
+                    cent_singular
+                } else {
+                    cent_plural
+                }
+            );
+            match (whole, cents) {
+                (0, cents) if cents > 0 => cent_words,
+                (_, 0) => whole_words,
+                _ => format!("{} and {}", whole_words, cent_words),
+            }
+        })
+        .to_string()
+}
+
+fn verbalize_suffixed_numbers(text: &str) -> String {
+    SUFFIXED_NUMBER
+        .replace_all(text, |caps: &Captures| {
+            let scale = scale_suffix_to_words(&caps[3]).unwrap_or_default();
+            match decimal_to_words(&caps[1], caps.get(2).map(|m| m.as_str())) {
+                Some(amount) => format!("{} {}", amount, scale),
+                None => caps[0].to_string(),
+            }
+        })
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn times() {
+        assert_eq!(verbalize("3:00"), "three o'clock");
+        assert_eq!(verbalize("03:00"), "oh three hundred");
+        assert_eq!(verbalize("15:00"), "fifteen hundred");
+        assert_eq!(verbalize("12:05 pm"), "twelve oh five P M");
+    }
+}
@@ -0,0 +1,119 @@
+//! Golden checks for what the corpus in `tests/golden/corpus.json` would hand to DECtalk: each
+//! voice's prelude, and the full `say` arguments once the text has been through preprocessing
+//! with a server's default settings. Run with `UPDATE_GOLDEN=1` to accept the current output
+//! after an intentional change.
+
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use dectalk_bot_core::{
+    dectalk::{DectalkVoice, Language, Say, PAUL_VOICE},
+    preprocess::{process_message, PreprocessOptions},
+};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct Case {
+    name: String,
+    text: String,
+    /// Without a player the case uses Paul.
+    player: Option<u64>,
+    #[serde(default)]
+    seed: u64,
+    #[serde(default = "default_language")]
+    language: String,
+}
+
+fn default_language() -> String {
+    Language::English.code().to_string()
+}
+
+impl Case {
+    fn voice(&self) -> DectalkVoice {
+        match self.player {
+            Some(player) => DectalkVoice::generate(player, self.seed),
+            None => PAUL_VOICE,
+        }
+    }
+
+    fn language(&self) -> Language {
+        [
+            Language::English,
+            Language::Spanish,
+            Language::German,
+            Language::French,
+        ]
+        .into_iter()
+        .find(|language| language.code() == self.language)
+        .unwrap_or_else(|| panic!("Unknown language {} in {}", self.language, self.name))
+    }
+}
+
+fn golden_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden")
+}
+
+fn updating() -> bool {
+    env::var_os("UPDATE_GOLDEN").is_some()
+}
+
+fn corpus() -> Vec<Case> {
+    let corpus = fs::read_to_string(golden_dir().join("corpus.json")).unwrap();
+    serde_json::from_str(&corpus).unwrap()
+}
+
+/// Checks each case's output against `tests/golden/<kind>/<name>.txt`, or rewrites the files
+/// when updating.
+fn check(kind: &str, output: impl Fn(&Case) -> String) {
+    let dir = golden_dir().join(kind);
+    let mut mismatches = Vec::new();
+    for case in corpus() {
+        let path = dir.join(format!("{}.txt", case.name));
+        let actual = output(&case);
+        if updating() {
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(&path, &actual).unwrap();
+            continue;
+        }
+        match fs::read_to_string(&path) {
+            Ok(expected) if expected == actual => {}
+            Ok(expected) => mismatches.push(format!(
+                "{}:\nexpected {}\n     got {}",
+                case.name, expected, actual
+            )),
+            Err(_) => mismatches.push(format!("{}: no golden {}", case.name, kind)),
+        }
+    }
+    assert!(
+        mismatches.is_empty(),
+        "{} changed, rerun with UPDATE_GOLDEN=1 if that's intended\n{}",
+        kind,
+        mismatches.join("\n")
+    );
+}
+
+#[test]
+fn preludes_match_golden() {
+    check("preludes", |case| case.voice().prelude());
+}
+
+#[test]
+fn say_scripts_match_golden() {
+    let slang = HashMap::new();
+    // The same as a server that hasn't changed any settings
+    let options = PreprocessOptions {
+        read_link_domains: false,
+        expand_slang: false,
+        slang: &slang,
+        emoji_limit: 3,
+    };
+    check("scripts", |case| {
+        let text = process_message(&case.text, &options);
+        let mut script = Say::args(&text, &case.voice().prelude(), case.language()).join("\n");
+        script.push('\n');
+        script
+    });
+}
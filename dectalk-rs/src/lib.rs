@@ -0,0 +1,20 @@
+//! Drive the DECtalk speech synthesizer from Rust: build voices from DECtalk's `[:dv ...]`
+//! parameters and run its `say` binary to get WAV audio back.
+//!
+//! ```no_run
+//! use dectalk_rs::{DectalkVoice, Language, Say};
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+//! let voice = DectalkVoice::builder().head_size(90).build()?;
+//! let wav = Say::new("dectalk/say")
+//!     .synthesize("Hello there", &voice, Language::English)
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+mod say;
+mod voice;
+
+pub use say::{Say, SayError};
+pub use voice::{ranges, DectalkVoice, Language, OutOfRange, Sex, VoiceBuilder, PAUL_VOICE};
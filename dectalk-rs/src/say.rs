@@ -0,0 +1,119 @@
+use std::{error::Error, fmt, path::PathBuf};
+
+use tokio::{fs, process::Command};
+use uuid::Uuid;
+
+use crate::{DectalkVoice, Language};
+
+/// `say` exiting unsuccessfully, with everything needed to reproduce it.
+#[derive(Debug, Clone)]
+pub struct SayError {
+    /// The full command line, starting with the binary.
+    pub args: Vec<String>,
+    /// None when `say` was killed by a signal.
+    pub exit_code: Option<i32>,
+    pub stderr: String,
+}
+
+impl fmt::Display for SayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Failed to run say: {}", self.stderr)
+    }
+}
+
+impl Error for SayError {}
+
+/// Runs DECtalk's `say` binary, which can only write to a file, and reads the WAV back.
+#[derive(Debug, Clone)]
+pub struct Say {
+    path: PathBuf,
+    output_dir: PathBuf,
+}
+
+impl Say {
+    /// Writes temporary files next to the binary unless told otherwise.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let output_dir = path
+            .parent()
+            .map(|parent| parent.to_path_buf())
+            .unwrap_or_default();
+        Say { path, output_dir }
+    }
+
+    pub fn output_dir(mut self, output_dir: impl Into<PathBuf>) -> Self {
+        self.output_dir = output_dir.into();
+        self
+    }
+
+    pub async fn synthesize(
+        &self,
+        text: &str,
+        voice: &DectalkVoice,
+        language: Language,
+    ) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        let (wav, _) = self.run(text, &voice.prelude(), language).await?;
+        Ok(wav)
+    }
+
+    /// Like [`Say::synthesize`], but also returns the phonemes DECtalk spoke, which `say`
+    /// prints with `[:log phonemes on]`.
+    pub async fn synthesize_with_phonemes(
+        &self,
+        text: &str,
+        voice: &DectalkVoice,
+        language: Language,
+    ) -> Result<(Vec<u8>, String), Box<dyn Error + Send + Sync>> {
+        let prelude = format!("{}[:log phonemes on]", voice.prelude());
+        let (wav, stdout) = self.run(text, &prelude, language).await?;
+        Ok((wav, String::from_utf8_lossy(&stdout).trim().to_string()))
+    }
+
+    /// Everything `say` is given for `text` apart from the file to write.
+    pub fn args(text: &str, prelude: &str, language: Language) -> Vec<String> {
+        let mut args = Vec::new();
+        if language != Language::English {
+            args.extend(["-l".to_string(), language.code().to_string()]);
+        }
+        args.extend([
+            "-a".to_string(),
+            text.to_string(),
+            "-pre".to_string(),
+            prelude.to_string(),
+        ]);
+        args
+    }
+
+    /// Returns the WAV and whatever `say` printed.
+    async fn run(
+        &self,
+        text: &str,
+        prelude: &str,
+        language: Language,
+    ) -> Result<(Vec<u8>, Vec<u8>), Box<dyn Error + Send + Sync>> {
+        let filename = self.output_dir.join(format!("{}.wav", Uuid::new_v4()));
+
+        let mut cmd = Command::new(&self.path);
+        cmd.args(Say::args(text, prelude, language));
+        cmd.arg("-fo").arg(&filename);
+
+        let output = cmd.output().await?;
+        if !output.status.success() {
+            let command = cmd.as_std();
+            let args = std::iter::once(command.get_program())
+                .chain(command.get_args())
+                .map(|arg| arg.to_string_lossy().to_string())
+                .collect();
+            return Err(SayError {
+                args,
+                exit_code: output.status.code(),
+                stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+            }
+            .into());
+        }
+
+        let wav = fs::read(&filename).await;
+        fs::remove_file(&filename).await?;
+        Ok((wav?, output.stdout))
+    }
+}
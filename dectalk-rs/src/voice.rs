@@ -0,0 +1,411 @@
+use std::{error::Error, fmt, ops::RangeInclusive};
+
+use tiny_keccak::keccakf;
+
+/// The values DECtalk accepts for each `[:dv ...]` parameter.
+pub mod ranges {
+    use std::ops::RangeInclusive;
+
+    pub const HEAD_SIZE: RangeInclusive<u16> = 65..=145;
+    pub const FOURTH_FORMANT: RangeInclusive<u16> = 2000..=4650;
+    pub const FIFTH_FORMANT: RangeInclusive<u16> = 2500..=4950;
+    pub const FOURTH_BANDWIDTH: RangeInclusive<u16> = 100..=2048;
+    pub const FIFTH_BANDWIDTH: RangeInclusive<u16> = 100..=2048;
+    pub const BREATHINESS: RangeInclusive<u16> = 0..=72;
+    pub const LAX_BREATHINESS: RangeInclusive<u16> = 0..=100;
+    pub const SMOOTHNESS: RangeInclusive<u16> = 0..=100;
+    pub const RICHNESS: RangeInclusive<u16> = 0..=100;
+    pub const FIXED_SAMPLINGS: RangeInclusive<u16> = 0..=100;
+    pub const LARYNGEALIZATION: RangeInclusive<u16> = 0..=100;
+    pub const BASELINE_FALL: RangeInclusive<u16> = 0..=40;
+    pub const HAT_RISE: RangeInclusive<u16> = 2..=100;
+    pub const STRESS_RISE: RangeInclusive<u16> = 1..=100;
+    pub const ASSERTIVENESS: RangeInclusive<u16> = 0..=100;
+    pub const QUICKNESS: RangeInclusive<u16> = 0..=100;
+    pub const AVERAGE_PITCH: RangeInclusive<u16> = 50..=350;
+    pub const PITCH_RANGE: RangeInclusive<u16> = 0..=250;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sex {
+    Female,
+    Male,
+}
+
+#[derive(Debug, Clone)]
+pub struct DectalkVoice {
+    sx: u8,   // --     Set sex to female (0) or male (1)
+    hs: u16,  // %      Head size
+    f4: u16,  // Hz     Fourth formant frequency
+    f5: u16,  // Hz     Fifth formant frequency
+    b4: u16,  // Hz     Fourth formant bandwidth
+    b5: u16,  // Hz     Fifth formant bandwidth
+    br: u16,  // dB     Breathiness
+    lx: u16,  // %      Lax breathiness
+    sm: u16,  // %      Smoothness (high frequency attenuation)
+    ri: u16,  // %      Richness
+    nf: u16,  // --     Number of fixed samplings of glottal pulse open phase
+    la: u16,  // %      Laryngealization
+    bf: u16,  // Hz     Baseline fall
+    hr: u16,  // Hz     Hat rise
+    sr: u16,  // Hz     Stress rise
+    as_: u16, // %      Assertiveness
+    qu: u16,  // %      Quickness
+    ap: u16,  // Hz     Average pitch
+    pr: u16,  // %      Pitch range
+              // gv: u16,  // dB     Gain of voicing source
+              // gh: u16,  // dB     Gain of aspiration source
+              // gn: u16,  // dB     Gain of frication source
+              // gf: u16,  // bB     Gain of nasalization
+              // g1: u16,  // dB     Gain of first formant resonator
+              // g2: u16,  // dB     Gain of second formant resonator
+              // g3: u16,  // dB     Gain of third formant resonator
+              // g4: u16,  // dB     Gain of fourth formant resonator
+              // g5: u16,  // dB     Gain of fifth formant resonator (replaces lo)
+}
+
+pub const PAUL_VOICE: DectalkVoice = DectalkVoice {
+    sx: 1,
+    hs: 100,
+    f4: 3300,
+    f5: 3650,
+    b4: 260,
+    b5: 330,
+    br: 0,
+    lx: 0,
+    sm: 3,
+    ri: 70,
+    nf: 0,
+    la: 0,
+    bf: 18,
+    hr: 18,
+    sr: 32,
+    as_: 100,
+    qu: 40,
+    ap: 112,
+    pr: 100,
+    // gv: 65,
+    // gh: 70,
+    // gn: 74,
+    // gf: 70,
+    // g1: 68,
+    // g2: 60,
+    // g3: 48,
+    // g4: 64,
+    // g5: 86,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Language {
+    English,
+    Spanish,
+    German,
+    French,
+}
+
+impl Language {
+    pub const fn code(&self) -> &'static str {
+        match self {
+            Language::English => "us",
+            Language::Spanish => "sp",
+            Language::German => "gr",
+            Language::French => "fr",
+        }
+    }
+}
+
+#[inline]
+fn u64_to_u16_loop(range: RangeInclusive<u16>, value: u64) -> u16 {
+    let (min, max) = range.into_inner();
+    (min as u64 + (value % (max - min + 1) as u64)) as u16
+}
+
+impl DectalkVoice {
+    /// Starts from Perfect Paul, DECtalk's default voice.
+    pub fn builder() -> VoiceBuilder {
+        VoiceBuilder { voice: PAUL_VOICE }
+    }
+
+    /// Starts from this voice, to change some of its parameters.
+    pub fn to_builder(&self) -> VoiceBuilder {
+        VoiceBuilder {
+            voice: self.clone(),
+        }
+    }
+
+    /// Picks every parameter from its range using a hash of the two numbers, so the same
+    /// player and seed always sound the same. Odd seeds are male, even seeds female.
+    pub fn generate(player_id: u64, seed: u64) -> Self {
+        let mut random = [player_id ^ seed; 25];
+        let sx = (seed % 2) as u8;
+        keccakf(&mut random);
+        let hs = u64_to_u16_loop(ranges::HEAD_SIZE, random[0]);
+        keccakf(&mut random);
+        let f4 = u64_to_u16_loop(ranges::FOURTH_FORMANT, random[0]);
+        keccakf(&mut random);
+        let f5 = u64_to_u16_loop(ranges::FIFTH_FORMANT, random[0]);
+        keccakf(&mut random);
+        let b4 = u64_to_u16_loop(ranges::FOURTH_BANDWIDTH, random[0]);
+        keccakf(&mut random);
+        let b5 = u64_to_u16_loop(ranges::FIFTH_BANDWIDTH, random[0]);
+        keccakf(&mut random);
+        let br = u64_to_u16_loop(ranges::BREATHINESS, random[0]);
+        keccakf(&mut random);
+        let lx = u64_to_u16_loop(ranges::LAX_BREATHINESS, random[0]);
+        keccakf(&mut random);
+        let sm = u64_to_u16_loop(ranges::SMOOTHNESS, random[0]);
+        keccakf(&mut random);
+        let ri = u64_to_u16_loop(ranges::RICHNESS, random[0]);
+        keccakf(&mut random);
+        let nf = u64_to_u16_loop(ranges::FIXED_SAMPLINGS, random[0]);
+        keccakf(&mut random);
+        let la = u64_to_u16_loop(ranges::LARYNGEALIZATION, random[0]);
+        keccakf(&mut random);
+        let bf = u64_to_u16_loop(ranges::BASELINE_FALL, random[0]);
+        keccakf(&mut random);
+        let hr = u64_to_u16_loop(ranges::HAT_RISE, random[0]);
+        keccakf(&mut random);
+        let sr = u64_to_u16_loop(ranges::STRESS_RISE, random[0]);
+        keccakf(&mut random);
+        let as_ = u64_to_u16_loop(ranges::ASSERTIVENESS, random[0]);
+        keccakf(&mut random);
+        let qu = u64_to_u16_loop(ranges::QUICKNESS, random[0]);
+        keccakf(&mut random);
+        let ap = u64_to_u16_loop(ranges::AVERAGE_PITCH, random[0]);
+        keccakf(&mut random);
+        let pr = u64_to_u16_loop(ranges::PITCH_RANGE, random[0]);
+        // keccakf(&mut random);
+        // let gv = u64_to_u16_loop(0, 86, random[0]);
+        // keccakf(&mut random);
+        // let gh = u64_to_u16_loop(0, 86, random[0]);
+        // keccakf(&mut random);
+        // let gn = u64_to_u16_loop(0, 86, random[0]);
+        // keccakf(&mut random);
+        // let gf = u64_to_u16_loop(0, 86, random[0]);
+        // keccakf(&mut random);
+        // let g1 = u64_to_u16_loop(0, 86, random[0]);
+        // keccakf(&mut random);
+        // let g2 = u64_to_u16_loop(0, 86, random[0]);
+        // keccakf(&mut random);
+        // let g3 = u64_to_u16_loop(0, 86, random[0]);
+        // keccakf(&mut random);
+        // let g4 = u64_to_u16_loop(0, 86, random[0]);
+        // keccakf(&mut random);
+        // let g5 = u64_to_u16_loop(0, 86, random[0]);
+
+        Self {
+            sx,
+            hs,
+            f4,
+            f5,
+            b4,
+            b5,
+            br,
+            lx,
+            sm,
+            ri,
+            nf,
+            la,
+            bf,
+            hr,
+            sr,
+            as_,
+            qu,
+            ap,
+            pr,
+            // gv,
+            // gh,
+            // gn,
+            // gf,
+            // g1,
+            // g2,
+            // g3,
+            // g4,
+            // g5,
+        }
+    }
+
+    /// The `[:dv ...]` commands that switch DECtalk to this voice, sent before the text.
+    pub fn prelude(&self) -> String {
+        format!(
+            "[:phoneme on][:nv]
+        [:dv sx {}][:dv hs {}]
+        [:dv f4 {}][:dv f5 {}]
+        [:dv b4 {}][:dv b5 {}]
+        [:dv br {}][:dv lx {}]
+        [:dv sm {}][:dv ri {}]
+        [:dv nf {}][:dv la {}]
+        [:dv bf {}][:dv hr {}]
+        [:dv sr {}][:dv as {}]
+        [:dv qu {}][:dv ap {}]
+        [:dv pr {}]", // [:dv gv {}]
+            // [:dv gh {}][:dv gn {}]
+            // [:dv gf {}][:dv g1 {}]
+            // [:dv g2 {}][:dv g3 {}]
+            // [:dv g4 {}][:dv g5 {}]",
+            self.sx,
+            self.hs,
+            self.f4,
+            self.f5,
+            self.b4,
+            self.b5,
+            self.br,
+            self.lx,
+            self.sm,
+            self.ri,
+            self.nf,
+            self.la,
+            self.bf,
+            self.hr,
+            self.sr,
+            self.as_,
+            self.qu,
+            self.ap,
+            self.pr,
+            // self.gv,
+            // self.gh,
+            // self.gn,
+            // self.gf,
+            // self.g1,
+            // self.g2,
+            // self.g3,
+            // self.g4,
+            // self.g5
+        )
+    }
+}
+
+/// Sets individual voice parameters, checking each against its range when built.
+///
+/// ```
+/// use dectalk_rs::{DectalkVoice, Sex};
+///
+/// let voice = DectalkVoice::builder()
+///     .sex(Sex::Female)
+///     .head_size(90)
+///     .average_pitch(220)
+///     .build()
+///     .unwrap();
+/// assert!(voice.prelude().contains("[:dv hs 90]"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct VoiceBuilder {
+    voice: DectalkVoice,
+}
+
+macro_rules! setters {
+    ($($(#[$doc:meta])* $name:ident => $field:ident),* $(,)?) => {
+        $(
+            $(#[$doc])*
+            pub fn $name(mut self, value: u16) -> Self {
+                self.voice.$field = value;
+                self
+            }
+        )*
+    };
+}
+
+impl VoiceBuilder {
+    pub fn sex(mut self, sex: Sex) -> Self {
+        self.voice.sx = match sex {
+            Sex::Female => 0,
+            Sex::Male => 1,
+        };
+        self
+    }
+
+    setters! {
+        /// Percent, smaller heads sound younger.
+        head_size => hs,
+        /// Hz
+        fourth_formant => f4,
+        /// Hz
+        fifth_formant => f5,
+        /// Hz
+        fourth_bandwidth => b4,
+        /// Hz
+        fifth_bandwidth => b5,
+        /// dB
+        breathiness => br,
+        /// Percent
+        lax_breathiness => lx,
+        /// Percent of high frequency attenuation.
+        smoothness => sm,
+        /// Percent
+        richness => ri,
+        /// Fixed samplings of the glottal pulse open phase.
+        fixed_samplings => nf,
+        /// Percent
+        laryngealization => la,
+        /// Hz
+        baseline_fall => bf,
+        /// Hz
+        hat_rise => hr,
+        /// Hz
+        stress_rise => sr,
+        /// Percent
+        assertiveness => as_,
+        /// Percent
+        quickness => qu,
+        /// Hz
+        average_pitch => ap,
+        /// Percent
+        pitch_range => pr,
+    }
+
+    pub fn build(self) -> Result<DectalkVoice, OutOfRange> {
+        let voice = self.voice;
+        let checks = [
+            ("head_size", voice.hs, ranges::HEAD_SIZE),
+            ("fourth_formant", voice.f4, ranges::FOURTH_FORMANT),
+            ("fifth_formant", voice.f5, ranges::FIFTH_FORMANT),
+            ("fourth_bandwidth", voice.b4, ranges::FOURTH_BANDWIDTH),
+            ("fifth_bandwidth", voice.b5, ranges::FIFTH_BANDWIDTH),
+            ("breathiness", voice.br, ranges::BREATHINESS),
+            ("lax_breathiness", voice.lx, ranges::LAX_BREATHINESS),
+            ("smoothness", voice.sm, ranges::SMOOTHNESS),
+            ("richness", voice.ri, ranges::RICHNESS),
+            ("fixed_samplings", voice.nf, ranges::FIXED_SAMPLINGS),
+            ("laryngealization", voice.la, ranges::LARYNGEALIZATION),
+            ("baseline_fall", voice.bf, ranges::BASELINE_FALL),
+            ("hat_rise", voice.hr, ranges::HAT_RISE),
+            ("stress_rise", voice.sr, ranges::STRESS_RISE),
+            ("assertiveness", voice.as_, ranges::ASSERTIVENESS),
+            ("quickness", voice.qu, ranges::QUICKNESS),
+            ("average_pitch", voice.ap, ranges::AVERAGE_PITCH),
+            ("pitch_range", voice.pr, ranges::PITCH_RANGE),
+        ];
+        for (parameter, value, range) in checks {
+            if !range.contains(&value) {
+                return Err(OutOfRange {
+                    parameter,
+                    value,
+                    range,
+                });
+            }
+        }
+        Ok(voice)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OutOfRange {
+    pub parameter: &'static str,
+    pub value: u16,
+    pub range: RangeInclusive<u16>,
+}
+
+impl fmt::Display for OutOfRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} must be between {} and {}, got {}",
+            self.parameter,
+            self.range.start(),
+            self.range.end(),
+            self.value
+        )
+    }
+}
+
+impl Error for OutOfRange {}
@@ -0,0 +1,142 @@
+use axum::{
+    extract::{Path, Request, State},
+    http::StatusCode,
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{get, post, put},
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use serenity::all::GuildId;
+
+use crate::{
+    config,
+    events::leave_guild,
+    http::{self, HttpState},
+    state::BotState,
+};
+
+type ApiResult<T> = Result<T, (StatusCode, String)>;
+
+#[derive(Deserialize)]
+struct SetRoll {
+    roll: u64,
+}
+
+/// Operator endpoints for scripts, all of which need `http.admin_token`.
+pub fn router() -> Router<HttpState> {
+    Router::new()
+        .route("/guilds", get(list_guilds))
+        .route(
+            "/guilds/{guild_id}/queue",
+            get(get_queue).delete(clear_queue),
+        )
+        .route("/guilds/{guild_id}/leave", post(leave))
+        .route("/rolls/{user_id}", put(set_roll))
+        .route("/config", get(dump_config))
+        .route_layer(middleware::from_fn(require_token))
+}
+
+async fn require_token(request: Request, next: Next) -> Response {
+    let admin_token = match &config::get().http.admin_token {
+        Some(admin_token) if !admin_token.is_empty() => admin_token.clone(),
+        _ => return StatusCode::NOT_FOUND.into_response(),
+    };
+    if !http::token_matches(http::bearer_token(request.headers()), &admin_token) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    next.run(request).await
+}
+
+async fn list_guilds(State(state): State<HttpState>) -> Json<Value> {
+    let mut guilds = Vec::new();
+    for guild_id in state.cache.guilds() {
+        let name = state.cache.guild(guild_id).map(|guild| guild.name.clone());
+        let (voice_channel_id, queued) = match state.songbird.get(guild_id) {
+            Some(call) => {
+                let call = call.lock().await;
+                (
+                    call.current_channel().map(|channel_id| channel_id.0.get()),
+                    call.queue().len(),
+                )
+            }
+            None => (None, 0),
+        };
+        guilds.push(json!({
+            "id": guild_id.get(),
+            "name": name,
+            "voice_channel_id": voice_channel_id,
+            "queued": queued,
+        }));
+    }
+    Json(Value::Array(guilds))
+}
+
+async fn get_queue(
+    State(state): State<HttpState>,
+    Path(guild_id): Path<u64>,
+) -> ApiResult<Json<Value>> {
+    let call = state
+        .songbird
+        .get(GuildId::new(guild_id))
+        .ok_or((StatusCode::NOT_FOUND, "Not in a call".to_string()))?;
+    let call = call.lock().await;
+    let mut tracks = Vec::new();
+    for track in call.queue().current_queue() {
+        let info = track.get_info().await.ok();
+        tracks.push(json!({
+            "playing": info.as_ref().map(|info| format!("{:?}", info.playing)),
+            "position": info.map(|info| info.position.as_secs_f64()),
+        }));
+    }
+    Ok(Json(json!({ "length": tracks.len(), "tracks": tracks })))
+}
+
+async fn clear_queue(
+    State(state): State<HttpState>,
+    Path(guild_id): Path<u64>,
+) -> ApiResult<StatusCode> {
+    let call = state
+        .songbird
+        .get(GuildId::new(guild_id))
+        .ok_or((StatusCode::NOT_FOUND, "Not in a call".to_string()))?;
+    call.lock().await.queue().stop();
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn leave(State(state): State<HttpState>, Path(guild_id): Path<u64>) -> StatusCode {
+    leave_guild(&state.data, &state.songbird, GuildId::new(guild_id)).await;
+    StatusCode::NO_CONTENT
+}
+
+async fn set_roll(
+    State(state): State<HttpState>,
+    Path(user_id): Path<u64>,
+    Json(body): Json<SetRoll>,
+) -> ApiResult<StatusCode> {
+    let bot = BotState::get(&state.data).await;
+    bot.voice_manager.set_roll(user_id, body.roll).await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn dump_config() -> ApiResult<Json<Value>> {
+    let mut config = serde_json::to_value(&*config::get())
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    config["token"] = json!("<redacted>");
+    if let Some(helper_tokens) = config["helper_tokens"].as_array_mut() {
+        helper_tokens.fill(json!("<redacted>"));
+    }
+    config["http"]["admin_token"] = json!("<redacted>");
+    config["http"]["speak_token"] = json!("<redacted>");
+    if config["mqtt"].is_object() {
+        config["mqtt"]["password"] = json!("<redacted>");
+    }
+    if config["matrix"].is_object() {
+        config["matrix"]["password"] = json!("<redacted>");
+    }
+    if config["telegram"].is_object() {
+        config["telegram"]["token"] = json!("<redacted>");
+    }
+    Ok(Json(config))
+}
@@ -0,0 +1,96 @@
+use std::sync::Arc;
+
+use serenity::{
+    all::{ChannelId, CreateAllowedMentions, CreateMessage, GuildId},
+    cache::Cache,
+    http::Http,
+    prelude::{RwLock, TypeMap},
+};
+use songbird::Songbird;
+use tracing::{debug, error, warn};
+
+use dectalk_bot_core::dectalk::{DectalkVoice, PAUL_VOICE};
+
+use crate::{
+    audio::speak_in_guild,
+    cron::{self, Schedule},
+    state::BotState,
+};
+
+pub const MAX_ANNOUNCEMENTS: usize = 10;
+
+/// Checks every guild's announcements at the start of each minute, posting the due ones and
+/// reading them in the channel the bot was bound to with `/join`, or whatever call it's in.
+pub async fn run_announcements(
+    http: Arc<Http>,
+    cache: Arc<Cache>,
+    data: Arc<RwLock<TypeMap>>,
+    songbird: Arc<Songbird>,
+) {
+    let state = BotState::get(&data).await;
+
+    loop {
+        let minute = cron::wait_for_next_minute().await;
+
+        let due = state
+            .guild_configs
+            .configs
+            .lock()
+            .await
+            .iter()
+            .flat_map(|(guild_id, config)| {
+                config
+                    .announcements
+                    .iter()
+                    .filter(|announcement| {
+                        Schedule::parse(&announcement.schedule)
+                            .is_ok_and(|schedule| schedule.matches(&minute))
+                    })
+                    .map(|announcement| (*guild_id, announcement.clone()))
+            })
+            .collect::<Vec<_>>();
+
+        for (guild_id, announcement) in due {
+            let guild_id = GuildId::new(guild_id);
+            let message = CreateMessage::new()
+                .content(format!("📢 {}", announcement.text))
+                .allowed_mentions(CreateAllowedMentions::new());
+            if let Err(e) = ChannelId::new(announcement.channel)
+                .send_message(&http, message)
+                .await
+            {
+                warn!(error = ?e, "Failed to post announcement");
+            }
+
+            let voice_channel_id = state
+                .bindings
+                .lock()
+                .await
+                .get(&guild_id)
+                .map(|binding| binding.voice_channel_id);
+            if voice_channel_id.is_none() && songbird.get(guild_id).is_none() {
+                continue;
+            }
+            let voice = match announcement.voice {
+                Some(roll) => DectalkVoice::generate(guild_id.get(), roll),
+                None => PAUL_VOICE,
+            };
+            match speak_in_guild(
+                &data,
+                &cache,
+                &songbird,
+                guild_id,
+                voice_channel_id,
+                "Announcement",
+                &announcement.text,
+                &voice,
+            )
+            .await
+            {
+                Ok(()) => {}
+                Err(e) if e.is_expected() => debug!(error = ?e, "Not reading announcement"),
+                Err(e) => error!(error = ?e, "Failed to read announcement"),
+            }
+        }
+    }
+}
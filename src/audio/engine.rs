@@ -0,0 +1,122 @@
+use std::{error::Error, fmt::Write, path::Path};
+
+use dectalk_bot_core::{
+    dectalk::{DectalkVoice, Language, SayError},
+    engine::TtsEngine,
+};
+use serenity::async_trait;
+use tokio::{fs, time::Instant};
+use tracing::{debug, error, instrument, warn};
+use uuid::Uuid;
+
+use crate::{config, error::BotError, metrics, reminders::unix_now};
+
+/// The engine from `config.engine`, looked up on every call so reloads take effect.
+pub struct ConfiguredEngine;
+
+#[async_trait]
+impl TtsEngine for ConfiguredEngine {
+    async fn synthesize(
+        &self,
+        text: &str,
+        voice: &DectalkVoice,
+        language: Language,
+    ) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        let (tts_bytes, _) = run(text, voice, language, false).await?;
+        Ok(tts_bytes)
+    }
+
+    async fn synthesize_with_phonemes(
+        &self,
+        text: &str,
+        voice: &DectalkVoice,
+        language: Language,
+    ) -> Result<(Vec<u8>, String), Box<dyn Error + Send + Sync>> {
+        run(text, voice, language, true).await
+    }
+}
+
+#[instrument(skip_all, fields(chars = text.len(), ?language))]
+async fn run(
+    text: &str,
+    voice: &DectalkVoice,
+    language: Language,
+    with_phonemes: bool,
+) -> Result<(Vec<u8>, String), Box<dyn Error + Send + Sync>> {
+    let started = Instant::now();
+    let engine = &config::get().engine;
+    let say = engine.say_engine();
+    let result = if with_phonemes {
+        say.synthesize_with_phonemes(text, voice, language).await
+    } else {
+        say.synthesize(text, voice, language)
+            .await
+            .map(|tts_bytes| (tts_bytes, String::new()))
+    };
+    let output = match result {
+        Ok(output) => output,
+        Err(e) => {
+            metrics::SAY_FAILURES.inc();
+            if let Some(debug_dir) = &engine.debug_dir {
+                capture_failure(debug_dir, text, voice, language, &*e).await;
+            }
+            return Err(e);
+        }
+    };
+    metrics::SYNTHESIS_SECONDS.observe(started.elapsed().as_secs_f64());
+    metrics::LAST_SYNTHESIS_SECONDS.set(started.elapsed().as_secs_f64());
+    debug!(elapsed = ?started.elapsed(), "Synthesized");
+    Ok(output)
+}
+
+/// Writes everything needed to rerun a failed synthesis to `debug_dir` and reports it to the
+/// ops channel.
+async fn capture_failure(
+    debug_dir: &Path,
+    text: &str,
+    voice: &DectalkVoice,
+    language: Language,
+    e: &(dyn Error + Send + Sync + 'static),
+) {
+    let mut capture = format!("language: {:?}\n", language);
+    let exit_code = match e.downcast_ref::<SayError>() {
+        Some(say_error) => {
+            let _ = writeln!(capture, "argv: {:?}", say_error.args);
+            let _ = writeln!(capture, "exit code: {:?}", say_error.exit_code);
+            let _ = writeln!(capture, "stderr:\n{}", say_error.stderr);
+            say_error.exit_code
+        }
+        None => {
+            let _ = writeln!(capture, "error: {}", e);
+            None
+        }
+    };
+    let _ = write!(capture, "prelude:\n{}\ntext:\n{}\n", voice.prelude(), text);
+
+    let path = debug_dir.join(format!("say-{}-{}.txt", unix_now(), Uuid::new_v4()));
+    let written = match fs::create_dir_all(debug_dir).await {
+        Ok(()) => fs::write(&path, capture).await,
+        Err(e) => Err(e),
+    };
+    match written {
+        Ok(()) => error!(
+            capture = %path.display(),
+            ?exit_code,
+            chars = text.len(),
+            "say failed: {}",
+            e
+        ),
+        Err(write_error) => warn!(error = ?write_error, "Failed to save say failure"),
+    }
+}
+
+pub async fn synthesize(
+    text: &str,
+    voice: &DectalkVoice,
+    language: Language,
+) -> Result<Vec<u8>, BotError> {
+    ConfiguredEngine
+        .synthesize(text, voice, language)
+        .await
+        .map_err(BotError::Synthesis)
+}
@@ -0,0 +1,5 @@
+mod engine;
+mod speak;
+
+pub use engine::{synthesize, ConfiguredEngine};
+pub use speak::{record_clip, speak_in_guild, THROUGHPUT_PERIOD};
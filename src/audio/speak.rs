@@ -0,0 +1,179 @@
+use std::{sync::Arc, time::Duration};
+
+use dectalk_bot_core::{
+    audio::{get_wav_duration, normalize_wav_volume},
+    dectalk::{DectalkVoice, Language},
+};
+use serenity::{
+    all::{ChannelId, GuildId, MessageId},
+    cache::Cache,
+    prelude::{RwLock, TypeMap},
+};
+use songbird::{input::Input, tracks::Track, Songbird};
+use tokio::time::Instant;
+use tracing::{info_span, instrument, Instrument};
+
+use super::synthesize;
+use crate::{
+    config,
+    error::BotError,
+    events::sync_guild_users,
+    idle, metrics,
+    preprocess::{estimate_duration, process_message, ESTIMATE_MARGIN},
+    reconnect, shutdown,
+    state::BotState,
+    transcript,
+};
+
+pub const THROUGHPUT_PERIOD: Duration = Duration::from_secs(60);
+
+/// Reads text from outside Discord in a guild's voice channel, with the guild's preprocessing
+/// and limits. Joins `channel_id` when given and the guild allows it, unless the bot is busy in
+/// another call. Otherwise the bot has to be in a call already.
+#[allow(clippy::too_many_arguments)]
+#[instrument(skip_all, fields(%guild_id, speaker = %speaker))]
+pub async fn speak_in_guild(
+    data: &RwLock<TypeMap>,
+    cache: &Cache,
+    manager: &Arc<Songbird>,
+    guild_id: GuildId,
+    channel_id: Option<ChannelId>,
+    speaker: &str,
+    text: &str,
+    voice: &DectalkVoice,
+) -> Result<(), BotError> {
+    if shutdown::is_shutting_down() {
+        return Err(BotError::ShuttingDown);
+    }
+    if shutdown::is_panicked() {
+        return Err(BotError::Panicked);
+    }
+
+    let state = BotState::get(data).await;
+    let config = state.guild_configs.get_config(guild_id.get()).await;
+    if !config.enabled {
+        return Err(BotError::Disabled);
+    }
+
+    let limits = config.limits_for(&[]);
+    if text.len() > limits.max_message_length {
+        return Err(BotError::TooLong);
+    }
+    let content = match config.apply_profanity_filter(&process_message(text, &config)) {
+        Some(content) => state.pronunciations.apply(&content),
+        None => return Err(BotError::Filtered),
+    };
+    if content.is_empty() {
+        return Err(BotError::Empty);
+    }
+    if estimate_duration(&content) > limits.max_duration * ESTIMATE_MARGIN {
+        return Err(BotError::TooLong);
+    }
+
+    let synthesis_budget = config::get().limits.guild_synthesis_seconds;
+    if synthesis_budget > 0.0
+        && !state.guild_throughput.lock().await.has_tokens(
+            guild_id,
+            synthesis_budget,
+            THROUGHPUT_PERIOD,
+        )
+    {
+        metrics::MESSAGES_THROTTLED.inc();
+        return Err(BotError::Throttled);
+    }
+
+    let (handler_lock, channel_id) = match channel_id {
+        Some(channel_id) => {
+            if !config.voice_channels.permits(channel_id.get()) {
+                return Err(BotError::ChannelBlocked);
+            }
+            let handler_lock = reconnect::get_or_insert_call(manager, guild_id).await;
+            let mut handler = handler_lock.lock().await;
+            match handler.current_channel() {
+                Some(current) if current == channel_id.into() => {}
+                Some(_) => return Err(BotError::Busy),
+                None => {
+                    handler.join(channel_id).await?;
+                    sync_guild_users(cache, data, guild_id, Some((channel_id, &mut *handler)))
+                        .await;
+                }
+            }
+            drop(handler);
+            (handler_lock, channel_id)
+        }
+        None => {
+            let handler_lock = manager.get(guild_id).ok_or(BotError::NotConnected)?;
+            let current_channel = handler_lock.lock().await.current_channel();
+            match current_channel {
+                Some(channel_id) => (handler_lock, ChannelId::new(channel_id.0.get())),
+                None => return Err(BotError::NotConnected),
+            }
+        }
+    };
+
+    let synthesis_started = Instant::now();
+    let tts_bytes = synthesize(&content, voice, Language::English).await;
+    if synthesis_budget > 0.0 {
+        state.guild_throughput.lock().await.charge(
+            guild_id,
+            synthesis_started.elapsed().as_secs_f64(),
+            synthesis_budget,
+            THROUGHPUT_PERIOD,
+        );
+    }
+    let tts_bytes = tts_bytes?;
+    match get_wav_duration(&tts_bytes).await {
+        Some(duration) if duration > limits.max_duration => return Err(BotError::TooLong),
+        Some(_) => {}
+        None => return Err(BotError::Audio("Failed to get duration".into())),
+    }
+    let normalized_tts_bytes = normalize_wav_volume(&tts_bytes).map_err(BotError::Audio)?;
+
+    idle::mark_played(data, guild_id).await;
+    record_clip(
+        data,
+        guild_id,
+        channel_id,
+        None,
+        speaker,
+        &content,
+        &normalized_tts_bytes,
+    )
+    .await;
+    let track = handler_lock
+        .lock()
+        .await
+        .enqueue(Track::from(Input::from(normalized_tts_bytes)).volume(config::get().engine.volume))
+        .instrument(info_span!("play"))
+        .await;
+    transcript::follow_track(
+        &track,
+        transcript::TranscriptEvent::Speaking {
+            guild_id: guild_id.get(),
+            user_id: 0,
+            user: speaker.to_string(),
+            text: content,
+        },
+    );
+    metrics::MESSAGES_SPOKEN.inc();
+    Ok(())
+}
+
+pub async fn record_clip(
+    data: &RwLock<TypeMap>,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    message_id: Option<MessageId>,
+    user: &str,
+    text: &str,
+    wav: &[u8],
+) {
+    BotState::get(data).await.clips.lock().await.record(
+        guild_id,
+        channel_id,
+        message_id,
+        user.to_string(),
+        text.to_string(),
+        wav.to_vec(),
+    );
+}
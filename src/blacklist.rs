@@ -0,0 +1,58 @@
+use std::{collections::HashSet, sync::Arc};
+
+use tokio::sync::Mutex;
+use tracing::debug;
+
+use crate::{
+    config,
+    error::{self, BotError},
+};
+
+/// Users operators have banned from every server. Servers keep their own list in
+/// `GuildConfig::blacklist`.
+pub struct Blacklist {
+    pub users: Arc<Mutex<HashSet<u64>>>,
+}
+
+impl Blacklist {
+    pub fn new() -> Self {
+        Blacklist {
+            users: Arc::new(Mutex::new(HashSet::new())),
+        }
+    }
+
+    pub async fn contains(&self, id: u64) -> bool {
+        self.users.lock().await.contains(&id)
+    }
+
+    /// Returns false if the user was already on the list.
+    pub async fn add(&self, id: u64) -> Result<bool, BotError> {
+        let added = self.users.lock().await.insert(id);
+        if added {
+            self.save_blacklist().await?;
+        }
+        Ok(added)
+    }
+
+    /// Returns false if the user wasn't on the list.
+    pub async fn remove(&self, id: u64) -> Result<bool, BotError> {
+        let removed = self.users.lock().await.remove(&id);
+        if removed {
+            self.save_blacklist().await?;
+        }
+        Ok(removed)
+    }
+
+    pub async fn load_blacklist(&self) -> Result<(), BotError> {
+        debug!("Loading blacklist...");
+        let users = error::read_json(config::get().data_path("blacklist.json")).await?;
+        *self.users.lock().await = users;
+        Ok(())
+    }
+
+    pub async fn save_blacklist(&self) -> Result<(), BotError> {
+        debug!("Saving blacklist...");
+        let users = self.users.lock().await;
+        error::write_json(config::get().data_path("blacklist.json"), &*users).await
+    }
+}
@@ -0,0 +1,59 @@
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+
+#[cfg(target_os = "macos")]
+const DEFAULT_PLAYER: &str = "afplay";
+#[cfg(not(target_os = "macos"))]
+const DEFAULT_PLAYER: &str = "aplay -q";
+
+#[derive(Debug, Parser)]
+#[command(
+    version,
+    about = "A Discord bot that reads messages aloud with DECtalk"
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<CliCommand>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CliCommand {
+    /// Connect to Discord and start reading messages (the default)
+    Run,
+    /// Synthesize text to a WAV file, for trying out voices without Discord
+    Say {
+        text: String,
+        #[arg(short, long, default_value = "say.wav")]
+        output: PathBuf,
+        /// Use this user's voice instead of Paul
+        #[arg(short, long)]
+        user: Option<u64>,
+        /// Override the user's saved roll
+        #[arg(short, long, requires = "user")]
+        roll: Option<u64>,
+    },
+    /// Read lines from stdin aloud on this machine, for trying out voices and preprocessing
+    /// without a live bot
+    Local {
+        /// Read lines from this file or named pipe instead of stdin
+        #[arg(short, long)]
+        input: Option<PathBuf>,
+        /// Write each line to a numbered WAV here instead of playing it
+        #[arg(short, long)]
+        output_dir: Option<PathBuf>,
+        /// The command that plays a WAV file, given its path
+        #[arg(short, long, default_value = DEFAULT_PLAYER)]
+        player: String,
+        /// Use this user's voice instead of Paul
+        #[arg(short, long)]
+        user: Option<u64>,
+        /// Override the user's saved roll
+        #[arg(short, long, requires = "user")]
+        roll: Option<u64>,
+    },
+    /// Print the voice parameters generated for a user
+    Voice { user_id: u64, roll: Option<u64> },
+    /// Upgrade the data files to the current format, which `run` also does on startup
+    Migrate,
+}
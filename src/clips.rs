@@ -0,0 +1,79 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+    time::Duration,
+};
+
+use serenity::all::{ChannelId, GuildId, MessageId};
+use tokio::time::Instant;
+
+pub const MAX_CLIPS: usize = 5;
+const CLIP_RETENTION: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Debug, Clone)]
+pub struct Clip {
+    pub user: String,
+    pub text: String,
+    pub wav: Arc<Vec<u8>>,
+    message_id: Option<MessageId>,
+    spoken_at: Instant,
+}
+
+/// The audio of the last few messages each voice channel heard, so `/clip` can share them.
+pub struct ClipBuffer {
+    clips: HashMap<(GuildId, ChannelId), VecDeque<Clip>>,
+}
+
+impl ClipBuffer {
+    pub fn new() -> Self {
+        ClipBuffer {
+            clips: HashMap::new(),
+        }
+    }
+
+    /// `message_id` is the message that was read out, if any, so deleting it drops the clip too.
+    pub fn record(
+        &mut self,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        message_id: Option<MessageId>,
+        user: String,
+        text: String,
+        wav: Vec<u8>,
+    ) {
+        let clips = self.clips.entry((guild_id, channel_id)).or_default();
+        if clips.len() == MAX_CLIPS {
+            clips.pop_back();
+        }
+        clips.push_front(Clip {
+            user,
+            text,
+            wav: Arc::new(wav),
+            message_id,
+            spoken_at: Instant::now(),
+        });
+    }
+
+    /// The clip `ago` messages back in a voice channel, 0 being the latest.
+    pub fn get(&self, guild_id: GuildId, channel_id: ChannelId, ago: usize) -> Option<Clip> {
+        self.clips
+            .get(&(guild_id, channel_id))?
+            .get(ago)
+            .filter(|clip| clip.spoken_at.elapsed() < CLIP_RETENTION)
+            .cloned()
+    }
+
+    pub fn remove_message(&mut self, message_id: MessageId) {
+        for clips in self.clips.values_mut() {
+            clips.retain(|clip| clip.message_id != Some(message_id));
+        }
+        self.clips.retain(|_, clips| !clips.is_empty());
+    }
+
+    pub fn prune(&mut self) {
+        for clips in self.clips.values_mut() {
+            clips.retain(|clip| clip.spoken_at.elapsed() < CLIP_RETENTION);
+        }
+        self.clips.retain(|_, clips| !clips.is_empty());
+    }
+}
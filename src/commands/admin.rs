@@ -0,0 +1,416 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use dectalk_bot_core::preprocess;
+use serenity::all::{
+    ChannelId, CommandInteraction, Context, CreateAllowedMentions, CreateEmbed, CreateMessage,
+    ResolvedOption,
+};
+use tokio::time::Instant;
+use tracing::error;
+
+use super::{get_string_option, get_subcommand, get_user_option, is_admin};
+use crate::{config, guild_config::AssignedVoice, leaderboard, metrics, shutdown, state::BotState};
+
+// Reports go to the ops channel or the owner's DMs, which shouldn't be floodable
+const REPORT_COOLDOWN: Duration = Duration::from_secs(10 * 60);
+
+pub async fn admin(ctx: &Context, command: &CommandInteraction) -> String {
+    let is_operator = config::get().is_operator(command.user.id.get());
+    let options = command.data.options();
+    let (subcommand, sub_options) = match get_subcommand(&options) {
+        Some(subcommand) => subcommand,
+        None => return "Unknown subcommand.".to_string(),
+    };
+
+    match subcommand {
+        "blacklist" | "unblacklist" if is_operator => {
+            global_blacklist(ctx, subcommand, sub_options).await
+        }
+        "blacklist" | "unblacklist" if is_admin(ctx, command).await => {
+            guild_blacklist(ctx, command, subcommand, sub_options).await
+        }
+        "blacklist" | "unblacklist" => {
+            "You need the Manage Server permission or this server's admin role for that."
+                .to_string()
+        }
+        "setvoice" if is_operator || is_admin(ctx, command).await => {
+            set_voice(ctx, command, sub_options, is_operator).await
+        }
+        "setvoice" => "You need the Manage Server permission or this server's admin role for that."
+            .to_string(),
+        _ if !is_operator => "Only bot operators can do that.".to_string(),
+        "reload" => match config::reload() {
+            Ok(()) => "Reloaded the config.".to_string(),
+            Err(e) => {
+                error!(error = ?e, "Failed to reload config");
+                format!("Failed to reload the config: {}", e)
+            }
+        },
+        _ => "Unknown subcommand.".to_string(),
+    }
+}
+
+pub async fn panic(ctx: &Context, command: &CommandInteraction) -> String {
+    if !config::get().is_operator(command.user.id.get()) {
+        return "Only bot operators can do that.".to_string();
+    }
+
+    // Helper bots stop too
+    let state = BotState::get(&ctx.data).await;
+    let mut left = 0;
+    for manager in state.coordinator.songbirds() {
+        left += shutdown::panic(manager).await;
+    }
+    format!(
+        "Stopped playback and left {} calls. Nothing will be read until /unpanic.",
+        left
+    )
+}
+
+pub fn unpanic(command: &CommandInteraction) -> String {
+    if !config::get().is_operator(command.user.id.get()) {
+        return "Only bot operators can do that.".to_string();
+    }
+
+    match shutdown::unpanic() {
+        true => "Reading again, use /join to bring the bot back into calls.".to_string(),
+        false => "The bot isn't stopped.".to_string(),
+    }
+}
+
+/// Assigns a built-in voice like `betty`, or a numbered voice like `/voice` picks. Operators
+/// change the user's own voice, server admins only what they sound like in their server.
+async fn set_voice(
+    ctx: &Context,
+    command: &CommandInteraction,
+    options: &[ResolvedOption<'_>],
+    is_operator: bool,
+) -> String {
+    let user = match get_user_option(options, "user") {
+        Some(user) => user,
+        None => return "Pick a user.".to_string(),
+    };
+    let requested = get_string_option(options, "voice")
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase();
+
+    let preset = preprocess::BUILTIN_VOICES
+        .iter()
+        .find(|(name, short)| *name == requested || *short == requested);
+    let assigned = match (preset, requested.parse::<u64>()) {
+        (Some((name, _)), _) => AssignedVoice::Preset(name.to_string()),
+        (None, Ok(roll)) => AssignedVoice::Roll(roll),
+        (None, Err(_)) => {
+            return format!(
+                "Pick a voice number or one of {}.",
+                preprocess::BUILTIN_VOICES
+                    .iter()
+                    .map(|(name, _)| *name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        }
+    };
+    let reply = match &assigned {
+        AssignedVoice::Preset(name) => format!("<@{}> now speaks as {}", user, name),
+        AssignedVoice::Roll(roll) => format!("<@{}> now uses voice {}", user, roll),
+    };
+
+    let state = BotState::get(&ctx.data).await;
+    if is_operator {
+        match &assigned {
+            AssignedVoice::Preset(name) => state.voice_manager.set_preset(user.get(), name).await,
+            AssignedVoice::Roll(roll) => state.voice_manager.set_roll(user.get(), *roll).await,
+        }
+        return reply;
+    }
+
+    let guild_id = match command.guild_id {
+        Some(guild_id) => guild_id,
+        None => return "This command only works in servers.".to_string(),
+    };
+    match state
+        .guild_configs
+        .update_config(guild_id.get(), |config| {
+            config.assigned_voices.insert(user.get(), assigned);
+        })
+        .await
+    {
+        Ok(()) => format!("{} in this server", reply),
+        Err(e) => {
+            error!(error = ?e, "Failed to save guild configs");
+            "Failed to save the voice.".to_string()
+        }
+    }
+}
+
+async fn global_blacklist(
+    ctx: &Context,
+    subcommand: &str,
+    options: &[ResolvedOption<'_>],
+) -> String {
+    let user = match get_user_option(options, "user") {
+        Some(user) => user,
+        None => return "Pick a user.".to_string(),
+    };
+    let state = BotState::get(&ctx.data).await;
+    let result = if subcommand == "blacklist" {
+        state.blacklist.add(user.get()).await
+    } else {
+        state.blacklist.remove(user.get()).await
+    };
+    match (subcommand, result) {
+        ("blacklist", Ok(true)) => format!("Ignoring <@{}> in every server.", user),
+        ("blacklist", Ok(false)) => format!("<@{}> is already blacklisted.", user),
+        (_, Ok(true)) => format!("Reading <@{}> again.", user),
+        (_, Ok(false)) => format!("<@{}> isn't blacklisted.", user),
+        (_, Err(e)) => {
+            error!(error = ?e, "Failed to save blacklist");
+            "Failed to save the blacklist.".to_string()
+        }
+    }
+}
+
+async fn guild_blacklist(
+    ctx: &Context,
+    command: &CommandInteraction,
+    subcommand: &str,
+    options: &[ResolvedOption<'_>],
+) -> String {
+    let guild_id = match command.guild_id {
+        Some(guild_id) => guild_id,
+        None => return "This command only works in servers.".to_string(),
+    };
+    let user = match get_user_option(options, "user") {
+        Some(user) => user.get(),
+        None => return "Pick a user.".to_string(),
+    };
+    let state = BotState::get(&ctx.data).await;
+    let result = state
+        .guild_configs
+        .update_config(guild_id.get(), |config| {
+            let listed = config.blacklist.contains(&user);
+            if subcommand == "blacklist" && !listed {
+                config.blacklist.push(user);
+            } else if subcommand == "unblacklist" {
+                config.blacklist.retain(|id| *id != user);
+            }
+            listed
+        })
+        .await;
+    match (subcommand, result) {
+        ("blacklist", Ok(false)) => format!("Ignoring <@{}> in this server.", user),
+        ("blacklist", Ok(true)) => format!("<@{}> is already blacklisted.", user),
+        (_, Ok(true)) => format!("Reading <@{}> again.", user),
+        (_, Ok(false)) => format!("<@{}> isn't blacklisted.", user),
+        (_, Err(e)) => {
+            error!(error = ?e, "Failed to save guild configs");
+            "Failed to save the blacklist.".to_string()
+        }
+    }
+}
+
+/// Sends the last messages the bot read from a user to the ops channel, or to the server owner
+/// when there isn't one, since the audio itself is gone by the time anyone reports it.
+pub async fn report(ctx: &Context, command: &CommandInteraction) -> String {
+    let guild_id = match command.guild_id {
+        Some(guild_id) => guild_id,
+        None => return "This command only works in servers.".to_string(),
+    };
+
+    let options = command.data.options();
+    let user_id = match get_user_option(&options, "user") {
+        Some(user_id) => user_id,
+        None => return "Pick someone to report.".to_string(),
+    };
+
+    let state = BotState::get(&ctx.data).await;
+    let messages = state.read_history.lock().await.recent(guild_id, user_id);
+    if messages.is_empty() {
+        return format!("I haven't read anything from <@{}> recently.", user_id);
+    }
+    if !config::get().is_operator(command.user.id.get()) {
+        let mut reports_sent = state.reports_sent.lock().await;
+        reports_sent.retain(|_, sent_at| sent_at.elapsed() < REPORT_COOLDOWN);
+        if let Some(sent_at) = reports_sent.get(&command.user.id) {
+            return format!(
+                "You sent a report recently, try again in {} minutes.",
+                (REPORT_COOLDOWN - sent_at.elapsed()).as_secs() / 60 + 1
+            );
+        }
+        reports_sent.insert(command.user.id, Instant::now());
+    }
+
+    let (guild_name, owner_id) = match ctx.cache.guild(guild_id) {
+        Some(guild) => (guild.name.clone(), guild.owner_id),
+        None => {
+            error!("Failed to get guild");
+            return "Something went wrong.".to_string();
+        }
+    };
+
+    let mut content = format!(
+        "**Report** from <@{}> in {} ({}) about <@{}>:",
+        command.user.id, guild_name, guild_id, user_id
+    );
+    for message in messages {
+        let text = message.text.chars().take(200).collect::<String>();
+        content.push_str(&format!(
+            "\n<t:{}:f> {}: {}",
+            message.sent_at.unix_timestamp(),
+            message.message_id.link(message.channel_id, Some(guild_id)),
+            text
+        ));
+    }
+    let content = content.chars().take(1900).collect::<String>();
+    let message = CreateMessage::new()
+        .content(content)
+        .allowed_mentions(CreateAllowedMentions::new());
+
+    let sent = match config::get().ops_channel {
+        Some(channel_id) => ChannelId::new(channel_id)
+            .send_message(&ctx.http, message)
+            .await
+            .map(|_| ()),
+        None => owner_id
+            .direct_message(&ctx.http, message)
+            .await
+            .map(|_| ()),
+    };
+    match sent {
+        Ok(()) => "Sent your report, thanks.".to_string(),
+        Err(e) => {
+            error!(error = ?e, "Failed to send report");
+            "Failed to send your report.".to_string()
+        }
+    }
+}
+
+pub async fn stats(ctx: &Context, command: &CommandInteraction) -> String {
+    let state = BotState::get(&ctx.data).await;
+    let format_counts = |counts: BTreeMap<String, u64>| {
+        if counts.is_empty() {
+            return "Nothing yet.".to_string();
+        }
+        let mut counts = counts.into_iter().collect::<Vec<_>>();
+        counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        counts
+            .iter()
+            .map(|(feature, count)| format!("`{}`: {}", feature, count))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let mut sections = Vec::new();
+    if let Some(guild_id) = command.guild_id {
+        sections.push(format!(
+            "**This server**\n{}",
+            format_counts(state.stats.guild_counts(guild_id.get()).await)
+        ));
+    }
+    if config::get().is_operator(command.user.id.get()) {
+        sections.push(format!(
+            "**All servers**\n{}",
+            format_counts(state.stats.total_counts().await)
+        ));
+    }
+    if sections.is_empty() {
+        return "This command only works in servers.".to_string();
+    }
+    sections.join("\n\n")
+}
+
+pub async fn leaderboard(ctx: &Context, command: &CommandInteraction) -> String {
+    let guild_id = match command.guild_id {
+        Some(guild_id) => guild_id,
+        None => return "This command only works in servers.".to_string(),
+    };
+
+    let options = command.data.options();
+    let (period, days) = match get_string_option(&options, "period") {
+        Some("month") => ("month", leaderboard::MAX_DAYS),
+        _ => ("week", 7),
+    };
+    let state = BotState::get(&ctx.data).await;
+    let top = state.leaderboard.top(guild_id.get(), days, 10).await;
+    if top.is_empty() {
+        return format!("Nobody has been read aloud this {}.", period);
+    }
+    let lines = top
+        .iter()
+        .enumerate()
+        .map(|(index, (user_id, tally))| {
+            format!(
+                "{}. <@{}>: {:.0} seconds over {} messages",
+                index + 1,
+                user_id,
+                tally.seconds,
+                tally.messages
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    format!("**Top talkers this {}**\n{}", period, lines)
+}
+
+pub async fn status(ctx: &Context, command: &CommandInteraction) -> Result<CreateEmbed, String> {
+    if !config::get().is_operator(command.user.id.get()) {
+        return Err("Only bot operators can do that.".to_string());
+    }
+
+    let state = BotState::get(&ctx.data).await;
+    let manager = match songbird::get(ctx).await {
+        Some(manager) => manager,
+        None => {
+            error!("Failed to get songbird manager");
+            return Err("Failed to read the bot's state.".to_string());
+        }
+    };
+
+    let mut calls = Vec::new();
+    for (guild_id, call) in manager.iter() {
+        let call = call.lock().await;
+        if call.current_connection().is_some() {
+            calls.push(format!("{}: {} queued", guild_id, call.queue().len()));
+        }
+    }
+    let calls = if calls.is_empty() {
+        "None".to_string()
+    } else {
+        // Embed fields hold at most 1024 characters
+        preprocess::truncate(&calls.join("\n"), 1000)
+    };
+
+    let uptime = state.started.elapsed().as_secs();
+    let uptime = format!(
+        "{}d {}h {}m",
+        uptime / 86400,
+        uptime / 3600 % 24,
+        uptime / 60 % 60
+    );
+    let latency = match metrics::LAST_SYNTHESIS_SECONDS.get() {
+        0.0 => "Nothing synthesized yet".to_string(),
+        seconds => format!("{:.0} ms", seconds * 1000.0),
+    };
+    let caches = format!(
+        "{} users, {} voices, {} guild configs",
+        ctx.cache.user_count(),
+        state.voice_manager.voices.lock().await.len(),
+        state.guild_configs.configs.lock().await.len()
+    );
+    let data_dir = &config::get().data_dir;
+    let storage = match config::check_writable(data_dir).await {
+        Ok(()) => format!("{} is writable", data_dir.display()),
+        Err(e) => format!("{} isn't writable: {}", data_dir.display(), e),
+    };
+
+    Ok(CreateEmbed::new()
+        .title("Status")
+        .field("Uptime", uptime, true)
+        .field("Guilds", ctx.cache.guild_count().to_string(), true)
+        .field("Last synthesis", latency, true)
+        .field("Voice connections", calls, false)
+        .field("Caches", caches, false)
+        .field("Data", storage, false))
+}
@@ -0,0 +1,208 @@
+use std::time::Duration;
+
+use rand::seq::IndexedRandom;
+use serenity::all::{
+    CommandInteraction, Context, CreateAllowedMentions, CreateMessage, Message, ReactionType,
+    ResolvedValue, UserId,
+};
+use tokio::time::Instant;
+use tracing::{error, warn};
+
+use super::{
+    get_user_option,
+    playback::{play_clip, render_script},
+};
+use crate::{config, reminders::unix_now, state::BotState};
+
+const VOTING_TIME: Duration = Duration::from_secs(60);
+// Each duel pings the opponent, so nobody can start them back to back
+const DUEL_COOLDOWN: Duration = Duration::from_secs(5 * 60);
+const FIRST: char = '🔴';
+const SECOND: char = '🔵';
+const SENTENCES: &[&str] = &[
+    "The quick brown fox jumps over the lazy dog.",
+    "I am the very model of a modern major general.",
+    "She sells sea shells by the sea shore.",
+    "Peter Piper picked a peck of pickled peppers.",
+];
+
+pub async fn voiceduel(ctx: &Context, command: &CommandInteraction) -> String {
+    let guild_id = match command.guild_id {
+        Some(guild_id) => guild_id,
+        None => return "This command only works in servers.".to_string(),
+    };
+
+    let options = command.data.options();
+    let opponent = match get_user_option(&options, "user") {
+        Some(opponent) => opponent,
+        None => return show_records(ctx, guild_id.get()).await,
+    };
+    if opponent == command.user.id {
+        return "Pick someone else to duel.".to_string();
+    }
+    if options.iter().any(|option| match option.value {
+        ResolvedValue::User(user, _) => user.bot,
+        _ => false,
+    }) {
+        return "Bots don't duel.".to_string();
+    }
+
+    let state = BotState::get(&ctx.data).await;
+    if !config::get().is_operator(command.user.id.get()) {
+        let mut started = state.duels_started.lock().await;
+        started.retain(|_, started_at| started_at.elapsed() < DUEL_COOLDOWN);
+        if started.iter().any(|((guild, _), started_at)| {
+            *guild == guild_id && started_at.elapsed() < VOTING_TIME
+        }) {
+            return "There's already a duel being voted on.".to_string();
+        }
+        if let Some(started_at) = started.get(&(guild_id, command.user.id)) {
+            return format!(
+                "You started a duel recently, try again in {} seconds.",
+                (DUEL_COOLDOWN - started_at.elapsed()).as_secs() + 1
+            );
+        }
+        started.insert((guild_id, command.user.id), Instant::now());
+    }
+
+    let sentence = SENTENCES
+        .choose(&mut rand::rng())
+        .copied()
+        .unwrap_or(SENTENCES[0]);
+    let mut clips = Vec::new();
+    for (number, user_id) in [("one", command.user.id), ("two", opponent)] {
+        let script = format!("Voice {}. {}", number, sentence);
+        match render_script(ctx, command, guild_id, user_id, &script).await {
+            Ok(clip) => clips.push(clip),
+            Err(content) => return content,
+        }
+    }
+    for clip in clips {
+        if let Err(content) = play_clip(ctx, command, guild_id, clip).await {
+            return content;
+        }
+    }
+
+    let message = CreateMessage::new()
+        .content(format!(
+            "🎤 Voice duel: <@{}> vs <@{}>! React {} for voice one or {} for voice two, voting \
+             closes <t:{}:R>.",
+            command.user.id,
+            opponent,
+            FIRST,
+            SECOND,
+            unix_now() + VOTING_TIME.as_secs()
+        ))
+        // Naming the opponent mustn't ping them, or anyone could be pinged on demand
+        .allowed_mentions(CreateAllowedMentions::new());
+    let message = match command.channel_id.send_message(&ctx.http, message).await {
+        Ok(message) => message,
+        Err(e) => {
+            warn!(error = ?e, "Failed to post duel");
+            return "Failed to start the vote, can I post in this channel?".to_string();
+        }
+    };
+    for reaction in [FIRST, SECOND] {
+        if let Err(e) = message.react(&ctx.http, reaction).await {
+            warn!(error = ?e, "Failed to add duel reaction");
+        }
+    }
+
+    tokio::spawn(finish_duel(ctx.clone(), message, command.user.id, opponent));
+    "Let the duel begin!".to_string()
+}
+
+async fn finish_duel(ctx: Context, message: Message, challenger: UserId, opponent: UserId) {
+    tokio::time::sleep(VOTING_TIME).await;
+
+    let message = match message.channel_id.message(&ctx.http, message.id).await {
+        Ok(message) => message,
+        Err(e) => {
+            warn!(error = ?e, "Failed to fetch duel votes");
+            return;
+        }
+    };
+    let votes = |emoji: char| {
+        message
+            .reactions
+            .iter()
+            .find(|reaction| reaction.reaction_type == ReactionType::Unicode(emoji.to_string()))
+            .map(|reaction| reaction.count - reaction.me as u64)
+            .unwrap_or_default()
+    };
+    let (first_votes, second_votes) = (votes(FIRST), votes(SECOND));
+
+    let guild_id = match message.guild_id {
+        Some(guild_id) => guild_id,
+        None => return,
+    };
+    let (winner, loser) = match first_votes.cmp(&second_votes) {
+        std::cmp::Ordering::Greater => (challenger, opponent),
+        std::cmp::Ordering::Less => (opponent, challenger),
+        std::cmp::Ordering::Equal => {
+            post_result(
+                &ctx,
+                &message,
+                format!("It's a tie, {} votes each!", first_votes),
+            )
+            .await;
+            return;
+        }
+    };
+
+    let state = BotState::get(&ctx.data).await;
+    let content = match state
+        .duels
+        .record_result(guild_id.get(), winner.get(), loser.get())
+        .await
+    {
+        Ok((winner_record, loser_record)) => format!(
+            "<@{}> wins {} to {}! They're now {}-{}, <@{}> is {}-{}.",
+            winner,
+            first_votes.max(second_votes),
+            first_votes.min(second_votes),
+            winner_record.wins,
+            winner_record.losses,
+            loser,
+            loser_record.wins,
+            loser_record.losses
+        ),
+        Err(e) => {
+            error!(error = ?e, "Failed to save duel records");
+            format!("<@{}> wins!", winner)
+        }
+    };
+    post_result(&ctx, &message, content).await;
+}
+
+async fn post_result(ctx: &Context, message: &Message, content: String) {
+    let result = CreateMessage::new()
+        .content(content)
+        .reference_message(message)
+        .allowed_mentions(CreateAllowedMentions::new());
+    if let Err(e) = message.channel_id.send_message(&ctx.http, result).await {
+        warn!(error = ?e, "Failed to post duel result");
+    }
+}
+
+async fn show_records(ctx: &Context, guild_id: u64) -> String {
+    let state = BotState::get(&ctx.data).await;
+    let records = state.duels.top(guild_id, 10).await;
+    if records.is_empty() {
+        return "No duels yet, challenge someone with `/voiceduel user`.".to_string();
+    }
+    records
+        .iter()
+        .enumerate()
+        .map(|(index, (user_id, record))| {
+            format!(
+                "{}. <@{}>: {} wins, {} losses",
+                index + 1,
+                user_id,
+                record.wins,
+                record.losses
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,948 @@
+use serenity::all::{
+    ChannelType, CommandInteraction, CommandOptionType, Context, CreateAllowedMentions,
+    CreateAttachment, CreateAutocompleteResponse, CreateCommand, CreateCommandOption,
+    CreateInteractionResponse, EditInteractionResponse, ResolvedOption, ResolvedValue, UserId,
+};
+use tracing::error;
+
+use crate::{clips, config, guild_config::SETTINGS, shutdown, songs, state::BotState, stats};
+
+mod admin;
+mod duel;
+mod playback;
+mod settings;
+mod utility;
+mod voice;
+
+const ADMIN_COMMANDS: &[&str] = &[
+    "config",
+    "slang",
+    "filter",
+    "profanity",
+    "limits",
+    "tts",
+    "forceroll",
+    "transcript",
+    "feed",
+    "announce",
+];
+
+// Server moderators can control playback as well as admins
+const PLAYBACK_COMMANDS: &[&str] = &["skip", "stop", "clear", "leave"];
+
+// Anything that makes the bot talk or play in a call, which blacklisted users can't do
+const SPEAKING_COMMANDS: &[&str] = &[
+    "sound",
+    "sing",
+    "morse",
+    "remindme",
+    "time",
+    "timer",
+    "roll-dice",
+    "flip",
+    "voiceduel",
+];
+
+pub fn register() -> Vec<CreateCommand> {
+    vec![
+        CreateCommand::new("config")
+            .description("Configure the bot for this server")
+            .dm_permission(false)
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "show",
+                "Show the current settings",
+            ))
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "set", "Change a setting")
+                    .add_sub_option(
+                        CreateCommandOption::new(
+                            CommandOptionType::String,
+                            "setting",
+                            "The setting to change",
+                        )
+                        .required(true)
+                        .set_autocomplete(true),
+                    )
+                    .add_sub_option(
+                        CreateCommandOption::new(
+                            CommandOptionType::String,
+                            "value",
+                            "The new value",
+                        )
+                        .required(true),
+                    ),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommandGroup,
+                    "channels",
+                    "Choose which channels get served",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::SubCommand,
+                        "allow",
+                        "Only serve allowed channels of this kind",
+                    )
+                    .add_sub_option(channel_option("The channel to allow")),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::SubCommand,
+                        "block",
+                        "Never serve a channel",
+                    )
+                    .add_sub_option(channel_option("The channel to block")),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::SubCommand,
+                        "remove",
+                        "Remove a channel from the lists",
+                    )
+                    .add_sub_option(channel_option("The channel to remove")),
+                )
+                .add_sub_option(CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "list",
+                    "List the allowed and blocked channels",
+                )),
+            ),
+        CreateCommand::new("slang")
+            .description("Manage this server's abbreviation expansions")
+            .dm_permission(false)
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "add",
+                    "Add or replace an expansion",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::String,
+                        "abbreviation",
+                        "The abbreviation, e.g. gg",
+                    )
+                    .required(true),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::String,
+                        "expansion",
+                        "What to say instead, e.g. good game",
+                    )
+                    .required(true),
+                ),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "remove",
+                    "Remove an expansion",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::String,
+                        "abbreviation",
+                        "The abbreviation to remove",
+                    )
+                    .required(true),
+                ),
+            )
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "list",
+                "List this server's expansions",
+            )),
+        CreateCommand::new("macro")
+            .description("Manage short triggers that are read as longer phrases")
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "add",
+                    "Add or replace a macro",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::String,
+                        "trigger",
+                        "A single word, e.g. gg",
+                    )
+                    .required(true),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::String,
+                        "phrase",
+                        "What to say instead, e.g. good game everyone",
+                    )
+                    .required(true),
+                )
+                .add_sub_option(scope_option()),
+            )
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "remove", "Remove a macro")
+                    .add_sub_option(
+                        CreateCommandOption::new(
+                            CommandOptionType::String,
+                            "trigger",
+                            "The trigger to remove",
+                        )
+                        .required(true),
+                    )
+                    .add_sub_option(scope_option()),
+            )
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "list", "List macros")
+                    .add_sub_option(scope_option()),
+            ),
+        CreateCommand::new("filter")
+            .description("Manage which messages get read aloud")
+            .dm_permission(false)
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "add", "Add a filter")
+                    .add_sub_option(
+                        CreateCommandOption::new(
+                            CommandOptionType::String,
+                            "kind",
+                            "Whether matching messages are allowed or denied",
+                        )
+                        .required(true)
+                        .add_string_choice("allow", "allow")
+                        .add_string_choice("deny", "deny"),
+                    )
+                    .add_sub_option(
+                        CreateCommandOption::new(
+                            CommandOptionType::String,
+                            "pattern",
+                            "A regular expression, e.g. ^!",
+                        )
+                        .required(true),
+                    ),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "remove",
+                    "Remove a filter",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::String,
+                        "pattern",
+                        "The pattern to remove",
+                    )
+                    .required(true),
+                ),
+            )
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "list",
+                "List this server's filters",
+            )),
+        CreateCommand::new("profanity")
+            .description("Manage this server's filtered words")
+            .dm_permission(false)
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "add", "Filter a word")
+                    .add_sub_option(
+                        CreateCommandOption::new(
+                            CommandOptionType::String,
+                            "word",
+                            "The word or phrase to filter",
+                        )
+                        .required(true),
+                    ),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "remove",
+                    "Stop filtering a word",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::String,
+                        "word",
+                        "The word or phrase to remove",
+                    )
+                    .required(true),
+                ),
+            )
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "list",
+                "List this server's filtered words",
+            )),
+        CreateCommand::new("limits")
+            .description("Manage per-role message limits")
+            .dm_permission(false)
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "set",
+                    "Set the limits for a role",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::Role, "role", "The role")
+                        .required(true),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::Integer,
+                        "length",
+                        "Maximum message length in characters",
+                    )
+                    .required(true)
+                    .min_int_value(1),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::Number,
+                        "duration",
+                        "Maximum spoken duration in seconds",
+                    )
+                    .required(true)
+                    .min_number_value(1.0),
+                ),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "remove",
+                    "Remove the limits for a role",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::Role, "role", "The role")
+                        .required(true),
+                ),
+            )
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "list",
+                "List this server's limits",
+            )),
+        CreateCommand::new("tts")
+            .description("Turn reading messages aloud on or off for this server")
+            .dm_permission(false)
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "enable",
+                "Start reading messages aloud",
+            ))
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "disable",
+                "Stop reading messages aloud",
+            )),
+        CreateCommand::new("preview")
+            .description("Hear how your voice reads some text")
+            .dm_permission(true)
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::String, "text", "What to say")
+                    .required(true)
+                    .max_length(256),
+            ),
+        CreateCommand::new("voice")
+            .description("Show or change your voice")
+            .dm_permission(true)
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Integer,
+                    "roll",
+                    "Pick a new voice by number",
+                )
+                .min_int_value(0),
+            ),
+        CreateCommand::new("transcription")
+            .description("Choose whether what you say in calls can be transcribed")
+            .dm_permission(true)
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Boolean,
+                    "allow",
+                    "Whether servers that turned on transcription can transcribe you",
+                ),
+            ),
+        CreateCommand::new("sound")
+            .description("Play and manage this server's sound clips")
+            .dm_permission(false)
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "play", "Play a clip")
+                    .add_sub_option(
+                        CreateCommandOption::new(
+                            CommandOptionType::String,
+                            "name",
+                            "The clip to play",
+                        )
+                        .required(true),
+                    ),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "add",
+                    "Add or replace a clip (requires Manage Server)",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::String,
+                        "name",
+                        "Letters, numbers, - and _",
+                    )
+                    .required(true),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::Attachment,
+                        "clip",
+                        "A short WAV file",
+                    )
+                    .required(true),
+                ),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "remove",
+                    "Remove a clip (requires Manage Server)",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::String,
+                        "name",
+                        "The clip to remove",
+                    )
+                    .required(true),
+                ),
+            )
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "list",
+                "List this server's clips",
+            )),
+        CreateCommand::new("sing")
+            .description("Sing a song in your voice channel")
+            .dm_permission(false)
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::String, "title", "The song to sing")
+                    .required(true)
+                    .set_autocomplete(true),
+            ),
+        CreateCommand::new("morse")
+            .description("Beep some text in Morse code in your voice channel")
+            .dm_permission(false)
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::String, "text", "What to spell")
+                    .required(true)
+                    .max_length(64),
+            ),
+        CreateCommand::new("remindme")
+            .description("Remind you of something later, out loud if you're in a call with me")
+            .dm_permission(false)
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::String, "in", "e.g. 10m or 1h30m")
+                    .required(true),
+            )
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::String, "text", "What to remind you")
+                    .required(true)
+                    .max_length(256),
+            ),
+        CreateCommand::new("time")
+            .description("Read the current time in this server's timezone")
+            .dm_permission(false),
+        CreateCommand::new("timer")
+            .description("Say \"timer done\" in the call when the time is up")
+            .dm_permission(false)
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::String, "duration", "e.g. 5m or 1h30m")
+                    .required(true),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "label",
+                    "What the timer is for",
+                )
+                .max_length(64),
+            ),
+        CreateCommand::new("roll-dice")
+            .description("Roll dice and read the result aloud")
+            .dm_permission(false)
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::String, "dice", "e.g. 2d6 or d20")
+                    .required(true)
+                    .max_length(16),
+            ),
+        CreateCommand::new("flip")
+            .description("Flip a coin and read the result aloud")
+            .dm_permission(false),
+        CreateCommand::new("voiceduel")
+            .description("Pit your voice against someone else's, or show the duel records")
+            .dm_permission(false)
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::User,
+                "user",
+                "Who to duel",
+            )),
+        CreateCommand::new("lottery")
+            .description("The daily lottery for a golden voice")
+            .dm_permission(false)
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "join",
+                "Enter the daily draw",
+            ))
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "leave",
+                "Stop entering the daily draw",
+            ))
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "status",
+                "Show when the lottery is drawn and who won",
+            )),
+        CreateCommand::new("forceroll")
+            .description("Change someone else's voice")
+            .dm_permission(false)
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::User, "user", "Whose voice to change")
+                    .required(true),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Integer,
+                    "roll",
+                    "The new voice number",
+                )
+                .required(true)
+                .min_int_value(0),
+            ),
+        CreateCommand::new("admin")
+            .description("Bot operator tools")
+            .dm_permission(true)
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "reload",
+                "Reload config.toml without restarting",
+            ))
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "blacklist",
+                    "Stop reading a user's messages, everywhere if you're a bot operator",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::User, "user", "Who to ignore")
+                        .required(true),
+                ),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "unblacklist",
+                    "Read a blacklisted user's messages again",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::User, "user", "Who to read")
+                        .required(true),
+                ),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "setvoice",
+                    "Assign someone a voice in this server until they pick another (requires Manage Server)",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::User, "user", "Whose voice to set")
+                        .required(true),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::String,
+                        "voice",
+                        "A voice number, or a built-in voice like betty",
+                    )
+                    .required(true),
+                ),
+            ),
+        CreateCommand::new("panic")
+            .description("Bot operators only: stop playback, leave every call and stop reading")
+            .dm_permission(true),
+        CreateCommand::new("unpanic")
+            .description("Bot operators only: start reading again after /panic")
+            .dm_permission(true),
+        CreateCommand::new("transcript")
+            .description("Share what the bot says outside the voice channel")
+            .dm_permission(false)
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "token",
+                "Create a new token for caption overlays, replacing the old one",
+            )),
+        CreateCommand::new("feed")
+            .description("Read new posts from RSS or Atom feeds")
+            .dm_permission(false)
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "add",
+                    "Follow a feed, posting links to new items and reading their titles",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::String, "url", "The feed's URL")
+                        .required(true),
+                )
+                .add_sub_option(channel_option("Where to post links")),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "remove",
+                    "Stop following a feed",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::String, "url", "The feed's URL")
+                        .required(true),
+                ),
+            )
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "list",
+                "Show the feeds this server follows",
+            )),
+        CreateCommand::new("announce")
+            .description("Read and post messages on a schedule")
+            .dm_permission(false)
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "add",
+                    "Schedule an announcement in this channel",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::String,
+                        "schedule",
+                        "A cron expression in UTC, e.g. 0 20 * * FRI",
+                    )
+                    .required(true),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::String, "text", "What to say")
+                        .required(true)
+                        .max_length(256),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::Integer,
+                        "voice",
+                        "The voice number to read it in",
+                    )
+                    .min_int_value(0),
+                ),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "remove",
+                    "Remove an announcement",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::Integer,
+                        "number",
+                        "The announcement's number in /announce list",
+                    )
+                    .required(true)
+                    .min_int_value(1),
+                ),
+            )
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "list",
+                "Show this server's announcements",
+            )),
+        CreateCommand::new("clip")
+            .description("Share what the bot just said")
+            .dm_permission(false)
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "last",
+                    "Post the audio of a recent message in this channel",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::Integer,
+                        "ago",
+                        "How many messages back, 1 for the latest",
+                    )
+                    .min_int_value(1)
+                    .max_int_value(clips::MAX_CLIPS as u64),
+                ),
+            ),
+        CreateCommand::new("report")
+            .description("Send what the bot recently read from someone to the bot's staff")
+            .dm_permission(false)
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::User, "user", "Who to report")
+                    .required(true),
+            ),
+        CreateCommand::new("stats")
+            .description("Show which commands and features get used here")
+            .dm_permission(true),
+        CreateCommand::new("leaderboard")
+            .description("Show who had the most read aloud here lately")
+            .dm_permission(false)
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::String, "period", "Defaults to week")
+                    .add_string_choice("week", "week")
+                    .add_string_choice("month", "month"),
+            ),
+        CreateCommand::new("status")
+            .description("Show the bot's runtime diagnostics")
+            .dm_permission(true),
+        CreateCommand::new("join")
+            .description("Join your voice channel and read this text channel")
+            .dm_permission(false),
+        CreateCommand::new("skip")
+            .description("Skip the message being read")
+            .dm_permission(false),
+        CreateCommand::new("stop")
+            .description("Stop reading and drop every queued message")
+            .dm_permission(false),
+        CreateCommand::new("clear")
+            .description("Drop queued messages but finish the current one")
+            .dm_permission(false),
+        CreateCommand::new("leave")
+            .description("Leave the voice channel")
+            .dm_permission(false),
+    ]
+}
+
+pub async fn run(ctx: &Context, command: &CommandInteraction) {
+    // Joining a voice channel can take longer than Discord's three second response window
+    if let Err(e) = command.defer_ephemeral(&ctx.http).await {
+        error!(error = ?e, "Failed to defer command");
+        return;
+    }
+
+    if let Some(guild_id) = command.guild_id {
+        let feature = format!("/{}", command.data.name);
+        stats::record(&ctx.data, guild_id.get(), &feature).await;
+    }
+
+    let mut response =
+        EditInteractionResponse::new().allowed_mentions(CreateAllowedMentions::new());
+    match command.data.name.as_str() {
+        _ if shutdown::is_shutting_down() => {
+            response = response.content("Shutting down, try again in a minute.")
+        }
+        name if shutdown::is_panicked() && name != "unpanic" => {
+            response = response.content("Stopped by an operator until they use /unpanic.")
+        }
+        "status" => match admin::status(ctx, command).await {
+            Ok(embed) => response = response.embed(embed),
+            Err(content) => response = response.content(content),
+        },
+        name => {
+            let (content, audio) = match name {
+                "preview" => voice::preview(ctx, command).await,
+                "voice" => voice::voice(ctx, command).await,
+                name => (run_text_command(ctx, command, name).await, None),
+            };
+            response = response.content(content);
+            if let Some(audio) = audio {
+                response = response.new_attachment(CreateAttachment::bytes(audio, "voice.wav"));
+            }
+        }
+    }
+    if let Err(e) = command.edit_response(&ctx.http, response).await {
+        error!(error = ?e, "Failed to respond to command");
+    }
+}
+
+async fn run_text_command(ctx: &Context, command: &CommandInteraction, name: &str) -> String {
+    if ADMIN_COMMANDS.contains(&name) && !is_admin(ctx, command).await {
+        return "You need the Manage Server permission or this server's admin role for that."
+            .to_string();
+    }
+
+    if PLAYBACK_COMMANDS.contains(&name) && !can_control_playback(ctx, command).await {
+        return "You need the Mute Members or Manage Server permission, or this server's admin \
+                role, for that."
+            .to_string();
+    }
+
+    if SPEAKING_COMMANDS.contains(&name) && is_blacklisted(ctx, command).await {
+        return "You can't make the bot speak here.".to_string();
+    }
+
+    match name {
+        "config" => settings::config(ctx, command).await,
+        "slang" => settings::slang(ctx, command).await,
+        "macro" => settings::macros(ctx, command).await,
+        "filter" => settings::filter(ctx, command).await,
+        "profanity" => settings::profanity(ctx, command).await,
+        "limits" => settings::limits(ctx, command).await,
+        "tts" => settings::tts(ctx, command).await,
+        "sound" => playback::sound(ctx, command).await,
+        "sing" => playback::sing(ctx, command).await,
+        "morse" => playback::morse(ctx, command).await,
+        "remindme" => utility::remindme(ctx, command).await,
+        "time" => utility::time(ctx, command).await,
+        "timer" => utility::timer(ctx, command).await,
+        "transcription" => utility::transcription(ctx, command).await,
+        "roll-dice" => utility::roll_dice(ctx, command).await,
+        "flip" => utility::flip(ctx, command).await,
+        "voiceduel" => duel::voiceduel(ctx, command).await,
+        "lottery" => utility::lottery(ctx, command).await,
+        "forceroll" => voice::forceroll(ctx, command).await,
+        "transcript" => settings::transcript(ctx, command).await,
+        "feed" => settings::feed(ctx, command).await,
+        "announce" => settings::announce(ctx, command).await,
+        "clip" => playback::clip(ctx, command).await,
+        "report" => admin::report(ctx, command).await,
+        "stats" => admin::stats(ctx, command).await,
+        "leaderboard" => admin::leaderboard(ctx, command).await,
+        "admin" => admin::admin(ctx, command).await,
+        "panic" => admin::panic(ctx, command).await,
+        "unpanic" => admin::unpanic(command),
+        "join" => playback::join(ctx, command).await,
+        "leave" => playback::leave(ctx, command).await,
+        "skip" | "stop" | "clear" => playback::playback(ctx, command, name).await,
+        _ => "Unknown command.".to_string(),
+    }
+}
+
+pub async fn autocomplete(ctx: &Context, command: &CommandInteraction) {
+    let partial = match command.data.autocomplete() {
+        Some(option) => option.value.to_lowercase(),
+        None => return,
+    };
+
+    let choices = match command.data.name.as_str() {
+        "sing" => songs::list_songs().await,
+        _ => SETTINGS.iter().map(|setting| setting.to_string()).collect(),
+    };
+    let mut response = CreateAutocompleteResponse::new();
+    for choice in choices.iter().filter(|c| c.contains(&partial)).take(25) {
+        response = response.add_string_choice(choice, choice);
+    }
+
+    if let Err(e) = command
+        .create_response(&ctx.http, CreateInteractionResponse::Autocomplete(response))
+        .await
+    {
+        error!(error = ?e, "Failed to respond to autocomplete");
+    }
+}
+
+/// Operators, members with Manage Server and members with the guild's admin role.
+pub async fn is_admin(ctx: &Context, command: &CommandInteraction) -> bool {
+    if config::get().is_operator(command.user.id.get()) {
+        return true;
+    }
+
+    let (guild_id, member) = match (command.guild_id, command.member.as_ref()) {
+        (Some(guild_id), Some(member)) => (guild_id, member),
+        _ => return false,
+    };
+    if member
+        .permissions
+        .is_some_and(|permissions| permissions.manage_guild())
+    {
+        return true;
+    }
+
+    // Interactions carry the member's roles, so this never needs to hit the API
+    let state = BotState::get(&ctx.data).await;
+    let config = state.guild_configs.get_config(guild_id.get()).await;
+    config.admin_role.is_some_and(|role| {
+        member
+            .roles
+            .iter()
+            .any(|member_role| member_role.get() == role)
+    })
+}
+
+/// On the operators' list or this server's.
+async fn is_blacklisted(ctx: &Context, command: &CommandInteraction) -> bool {
+    let user_id = command.user.id.get();
+    let state = BotState::get(&ctx.data).await;
+    if state.blacklist.contains(user_id).await {
+        return true;
+    }
+    match command.guild_id {
+        Some(guild_id) => state
+            .guild_configs
+            .get_config(guild_id.get())
+            .await
+            .blacklist
+            .contains(&user_id),
+        None => false,
+    }
+}
+
+async fn can_control_playback(ctx: &Context, command: &CommandInteraction) -> bool {
+    let can_mute = command.member.as_ref().is_some_and(|member| {
+        member
+            .permissions
+            .is_some_and(|permissions| permissions.mute_members())
+    });
+    can_mute || is_admin(ctx, command).await
+}
+
+fn get_subcommand<'a, 'b>(
+    options: &'b [ResolvedOption<'a>],
+) -> Option<(&'a str, &'b [ResolvedOption<'a>])> {
+    match options.first() {
+        Some(ResolvedOption {
+            name,
+            value:
+                ResolvedValue::SubCommand(sub_options) | ResolvedValue::SubCommandGroup(sub_options),
+            ..
+        }) => Some((name, sub_options)),
+        _ => None,
+    }
+}
+
+fn channel_option(description: &str) -> CreateCommandOption {
+    CreateCommandOption::new(CommandOptionType::Channel, "channel", description)
+        .required(true)
+        .channel_types(vec![
+            ChannelType::Text,
+            ChannelType::News,
+            ChannelType::Voice,
+            ChannelType::Stage,
+        ])
+}
+
+fn scope_option() -> CreateCommandOption {
+    CreateCommandOption::new(
+        CommandOptionType::String,
+        "scope",
+        "Your own macros or the whole server's, yours by default",
+    )
+    .add_string_choice("me", "me")
+    .add_string_choice("server", "server")
+}
+
+fn get_string_option<'a>(options: &[ResolvedOption<'a>], name: &str) -> Option<&'a str> {
+    options.iter().find_map(|option| match option.value {
+        ResolvedValue::String(value) if option.name == name => Some(value),
+        _ => None,
+    })
+}
+
+fn get_user_option(options: &[ResolvedOption<'_>], name: &str) -> Option<UserId> {
+    options.iter().find_map(|option| match option.value {
+        ResolvedValue::User(user, _) if option.name == name => Some(user.id),
+        _ => None,
+    })
+}
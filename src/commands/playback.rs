@@ -0,0 +1,529 @@
+use std::time::Duration;
+
+use dectalk_bot_core::{audio, dectalk::Language, morse, preprocess};
+use serenity::all::{
+    ChannelId, CommandInteraction, Context, CreateAllowedMentions, CreateAttachment, CreateMessage,
+    GuildId, ResolvedValue, UserId,
+};
+use songbird::{input::Input, tracks::Track};
+use tokio::time::Instant;
+use tracing::{error, warn};
+
+use super::{get_string_option, get_subcommand, is_admin};
+use crate::{
+    audio::{synthesize, THROUGHPUT_PERIOD},
+    config,
+    events::{leave_guild, sync_guild_users},
+    idle, metrics, reconnect, songs, soundboard,
+    state::{Binding, BotState},
+};
+
+pub async fn sound(ctx: &Context, command: &CommandInteraction) -> String {
+    let guild_id = match command.guild_id {
+        Some(guild_id) => guild_id,
+        None => return "This command only works in servers.".to_string(),
+    };
+
+    let options = command.data.options();
+    let (subcommand, sub_options) = match get_subcommand(&options) {
+        Some(subcommand) => subcommand,
+        None => return "Unknown subcommand.".to_string(),
+    };
+
+    let name = get_string_option(sub_options, "name")
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase();
+    let can_manage = is_admin(ctx, command).await;
+    match subcommand {
+        "play" => {
+            let clip = match soundboard::load_sound(guild_id, &name).await {
+                Some(clip) => clip,
+                None => return format!("There's no clip called `{}`.", name),
+            };
+            match play_clip(ctx, command, guild_id, clip).await {
+                Ok(()) => "Playing.".to_string(),
+                Err(content) => content,
+            }
+        }
+        "add" if can_manage => {
+            if !soundboard::is_valid_name(&name) {
+                return "Clip names can only use letters, numbers, - and _.".to_string();
+            }
+
+            let attachment = match sub_options.iter().find_map(|option| match option.value {
+                ResolvedValue::Attachment(attachment) => Some(attachment),
+                _ => None,
+            }) {
+                Some(attachment) => attachment,
+                None => return "Attach a WAV file.".to_string(),
+            };
+            let limits = &config::get().limits;
+            if attachment.size > limits.max_sound_size {
+                return format!("Clips can be at most {} KiB.", limits.max_sound_size / 1024);
+            }
+
+            let clip = match attachment.download().await {
+                Ok(clip) => clip,
+                Err(e) => {
+                    error!(error = ?e, "Failed to download attachment");
+                    return "Failed to download the clip.".to_string();
+                }
+            };
+            match audio::get_wav_duration(&clip).await {
+                Some(duration) if duration <= limits.max_sound_duration => {}
+                Some(_) => {
+                    return format!(
+                        "Clips can be at most {} seconds long.",
+                        limits.max_sound_duration
+                    )
+                }
+                None => return "Clips have to be uncompressed WAV files.".to_string(),
+            }
+
+            match soundboard::save_sound(guild_id, &name, &clip).await {
+                Ok(()) => format!("Added `{}`", name),
+                Err(e) => {
+                    error!(error = ?e, "Failed to save sound");
+                    "Failed to save the clip.".to_string()
+                }
+            }
+        }
+        "remove" if can_manage => match soundboard::remove_sound(guild_id, &name).await {
+            Ok(true) => format!("Removed `{}`", name),
+            Ok(false) => format!("There's no clip called `{}`.", name),
+            Err(e) => {
+                error!(error = ?e, "Failed to remove sound");
+                "Failed to remove the clip.".to_string()
+            }
+        },
+        "add" | "remove" => {
+            "You need the Manage Server permission or this server's admin role for that."
+                .to_string()
+        }
+        "list" => {
+            let names = soundboard::list_sounds(guild_id).await;
+            if names.is_empty() {
+                return "No clips yet.".to_string();
+            }
+            names
+                .iter()
+                .map(|name| format!("`{}`", name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        }
+        _ => "Unknown subcommand.".to_string(),
+    }
+}
+
+pub async fn sing(ctx: &Context, command: &CommandInteraction) -> String {
+    let guild_id = match command.guild_id {
+        Some(guild_id) => guild_id,
+        None => return "This command only works in servers.".to_string(),
+    };
+
+    let options = command.data.options();
+    let title = get_string_option(&options, "title")
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase();
+    let script = match songs::load_song(&title).await {
+        Some(script) => script,
+        None => return format!("I don't know a song called `{}`.", title),
+    };
+
+    let state = BotState::get(&ctx.data).await;
+    let is_operator = config::get().is_operator(command.user.id.get());
+    let cooldown = Duration::from_secs(config::get().limits.song_cooldown);
+    if !is_operator {
+        if let Some(sung_at) = state.sung.lock().await.get(&guild_id) {
+            if sung_at.elapsed() < cooldown {
+                return format!(
+                    "I sang recently, try again in {} seconds.",
+                    (cooldown - sung_at.elapsed()).as_secs() + 1
+                );
+            }
+        }
+    }
+
+    let song = match render_script(ctx, command, guild_id, command.user.id, &script).await {
+        Ok(song) => song,
+        Err(content) => return content,
+    };
+
+    state.sung.lock().await.insert(guild_id, Instant::now());
+    match play_clip(ctx, command, guild_id, song).await {
+        Ok(()) => format!("Singing `{}`.", title),
+        Err(content) => content,
+    }
+}
+
+pub async fn morse(ctx: &Context, command: &CommandInteraction) -> String {
+    let guild_id = match command.guild_id {
+        Some(guild_id) => guild_id,
+        None => return "This command only works in servers.".to_string(),
+    };
+
+    let options = command.data.options();
+    let text = get_string_option(&options, "text").unwrap_or_default();
+    let script = match morse::to_tones(text) {
+        Some(script) => script,
+        None => return "There's nothing in that Morse code can spell.".to_string(),
+    };
+
+    let beeps = match render_script(ctx, command, guild_id, command.user.id, &script).await {
+        Ok(beeps) => beeps,
+        Err(content) => return content,
+    };
+    match play_clip(ctx, command, guild_id, beeps).await {
+        Ok(()) => "Playing.".to_string(),
+        Err(content) => content,
+    }
+}
+
+/// Synthesizes `script` in `speaker`'s voice, charged to the guild's synthesis budget unless an
+/// operator asked for it.
+pub async fn render_script(
+    ctx: &Context,
+    command: &CommandInteraction,
+    guild_id: GuildId,
+    speaker: UserId,
+    script: &str,
+) -> Result<Vec<u8>, String> {
+    let state = BotState::get(&ctx.data).await;
+    let synthesis_budget = config::get().limits.guild_synthesis_seconds;
+    if !config::get().is_operator(command.user.id.get())
+        && synthesis_budget > 0.0
+        && !state.guild_throughput.lock().await.has_tokens(
+            guild_id,
+            synthesis_budget,
+            THROUGHPUT_PERIOD,
+        )
+    {
+        metrics::MESSAGES_THROTTLED.inc();
+        return Err(
+            "This server has used up its speaking time, try again in a minute.".to_string(),
+        );
+    }
+
+    let voice = state.voice_manager.get_voice(speaker.get()).await;
+    let synthesis_started = Instant::now();
+    let tts_bytes = synthesize(script, &voice, Language::English).await;
+    if synthesis_budget > 0.0 {
+        state.guild_throughput.lock().await.charge(
+            guild_id,
+            synthesis_started.elapsed().as_secs_f64(),
+            synthesis_budget,
+            THROUGHPUT_PERIOD,
+        );
+    }
+    let tts_bytes = match tts_bytes {
+        Ok(tts_bytes) => tts_bytes,
+        Err(e) => {
+            error!(error = ?e, "Failed to synthesize script");
+            return Err("Failed to play that.".to_string());
+        }
+    };
+    match audio::normalize_wav_volume(&tts_bytes) {
+        Ok(wav) => Ok(wav),
+        Err(e) => {
+            error!(error = ?e, "Failed to normalize script volume");
+            Err("Failed to play that.".to_string())
+        }
+    }
+}
+
+pub async fn play_clip(
+    ctx: &Context,
+    command: &CommandInteraction,
+    guild_id: GuildId,
+    clip: Vec<u8>,
+) -> Result<(), String> {
+    let user_channel_id = ctx.cache.guild(guild_id).and_then(|guild| {
+        guild
+            .voice_states
+            .get(&command.user.id)
+            .and_then(|voice_state| voice_state.channel_id)
+    });
+    let channel_id = match user_channel_id {
+        Some(channel_id) => channel_id,
+        None => return Err("Join a voice channel first.".to_string()),
+    };
+    let config = BotState::get(&ctx.data)
+        .await
+        .guild_configs
+        .get_config(guild_id.get())
+        .await;
+    if !config.voice_channels.permits(channel_id.get()) {
+        return Err("I'm not allowed in your voice channel.".to_string());
+    }
+
+    let manager = match songbird::get(ctx).await {
+        Some(manager) => manager,
+        None => {
+            error!("Failed to get songbird manager");
+            return Err("Something went wrong.".to_string());
+        }
+    };
+
+    let handler_lock = reconnect::get_or_insert_call(&manager, guild_id).await;
+    let mut handler = handler_lock.lock().await;
+    match handler.current_channel() {
+        Some(current) if current == channel_id.into() => {}
+        Some(_) => return Err("I'm busy in another voice channel.".to_string()),
+        None => {
+            if let Err(e) = handler.join(channel_id).await {
+                error!(error = ?e, "Failed to join channel");
+                return Err("Failed to join your voice channel.".to_string());
+            }
+            sync_guild_users(
+                &ctx.cache,
+                &ctx.data,
+                guild_id,
+                Some((channel_id, &mut *handler)),
+            )
+            .await;
+        }
+    }
+
+    handler
+        .enqueue(Track::from(Input::from(clip)).volume(config::get().engine.volume))
+        .await;
+    idle::mark_played(&ctx.data, guild_id).await;
+
+    Ok(())
+}
+
+pub async fn join(ctx: &Context, command: &CommandInteraction) -> String {
+    let guild_id = match command.guild_id {
+        Some(guild_id) => guild_id,
+        None => return "This command only works in servers.".to_string(),
+    };
+
+    let channel_id = {
+        let guild = match ctx.cache.guild(guild_id) {
+            Some(guild) => guild,
+            None => {
+                error!("Failed to get guild");
+                return "Something went wrong.".to_string();
+            }
+        };
+
+        match guild
+            .voice_states
+            .get(&command.user.id)
+            .and_then(|voice_state| voice_state.channel_id)
+        {
+            Some(channel_id) => channel_id,
+            None => return "Join a voice channel first.".to_string(),
+        }
+    };
+
+    let manager = match songbird::get(ctx).await {
+        Some(manager) => manager,
+        None => {
+            error!("Failed to get songbird manager");
+            return "Something went wrong.".to_string();
+        }
+    };
+
+    let state = BotState::get(&ctx.data).await;
+    if state
+        .coordinator
+        .owner(guild_id, channel_id)
+        .await
+        .is_some_and(|owner| owner != state.instance)
+    {
+        return "Another bot account is already reading that channel.".to_string();
+    }
+    let config = state.guild_configs.get_config(guild_id.get()).await;
+    if !config.voice_channels.permits(channel_id.get()) {
+        return "I'm not allowed in your voice channel.".to_string();
+    }
+
+    let handler_lock = reconnect::get_or_insert_call(&manager, guild_id).await;
+    let mut handler = handler_lock.lock().await;
+    if let Err(e) = handler.join(channel_id).await {
+        error!(error = ?e, "Failed to join channel");
+        return "Failed to join your voice channel.".to_string();
+    }
+
+    state.bindings.lock().await.insert(
+        guild_id,
+        Binding {
+            voice_channel_id: channel_id,
+            text_channel_id: command.channel_id,
+        },
+    );
+
+    sync_guild_users(
+        &ctx.cache,
+        &ctx.data,
+        guild_id,
+        Some((channel_id, &mut *handler)),
+    )
+    .await;
+    drop(handler);
+    idle::mark_played(&ctx.data, guild_id).await;
+
+    format!(
+        "Joined <#{}>, reading <#{}>",
+        channel_id, command.channel_id
+    )
+}
+
+pub async fn playback(ctx: &Context, command: &CommandInteraction, name: &str) -> String {
+    let guild_id = match command.guild_id {
+        Some(guild_id) => guild_id,
+        None => return "This command only works in servers.".to_string(),
+    };
+
+    let manager = match songbird::get(ctx).await {
+        Some(manager) => manager,
+        None => {
+            error!("Failed to get songbird manager");
+            return "Something went wrong.".to_string();
+        }
+    };
+    let handler_lock = match manager.get(guild_id) {
+        Some(handler_lock) => handler_lock,
+        None => return "I'm not in a voice channel.".to_string(),
+    };
+    let handler = handler_lock.lock().await;
+    let queue = handler.queue();
+    if queue.is_empty() {
+        return "Nothing is being read.".to_string();
+    }
+
+    match name {
+        "skip" => match queue.skip() {
+            Ok(()) => "Skipped.".to_string(),
+            Err(e) => {
+                warn!(error = ?e, "Failed to skip track");
+                "Failed to skip.".to_string()
+            }
+        },
+        "stop" => {
+            queue.stop();
+            "Stopped.".to_string()
+        }
+        _ => {
+            // The front of the queue is the track that's playing
+            let dropped = queue.modify_queue(|tracks| tracks.drain(1..).collect::<Vec<_>>());
+            for track in &dropped {
+                let _ = track.stop();
+            }
+            format!("Dropped {} queued messages.", dropped.len())
+        }
+    }
+}
+
+pub async fn leave(ctx: &Context, command: &CommandInteraction) -> String {
+    let guild_id = match command.guild_id {
+        Some(guild_id) => guild_id,
+        None => return "This command only works in servers.".to_string(),
+    };
+
+    let manager = match songbird::get(ctx).await {
+        Some(manager) => manager,
+        None => {
+            error!("Failed to get songbird manager");
+            return "Something went wrong.".to_string();
+        }
+    };
+
+    if manager.get(guild_id).is_none() {
+        return "I'm not in a voice channel.".to_string();
+    }
+
+    leave_guild(&ctx.data, &manager, guild_id).await;
+
+    "Left the voice channel.".to_string()
+}
+
+pub async fn clip(ctx: &Context, command: &CommandInteraction) -> String {
+    let guild_id = match command.guild_id {
+        Some(guild_id) => guild_id,
+        None => return "This command only works in servers.".to_string(),
+    };
+
+    let options = command.data.options();
+    let ago = match get_subcommand(&options) {
+        Some(("last", sub_options)) => sub_options
+            .iter()
+            .find_map(|option| match option.value {
+                ResolvedValue::Integer(ago) if option.name == "ago" => Some(ago),
+                _ => None,
+            })
+            .unwrap_or(1),
+        _ => return "Unknown subcommand.".to_string(),
+    };
+
+    // Clips only come from the invoker's own voice channel, or the bot's if they can see it
+    let bot_channel_id = match songbird::get(ctx)
+        .await
+        .and_then(|manager| manager.get(guild_id))
+    {
+        Some(handler_lock) => handler_lock
+            .lock()
+            .await
+            .current_channel()
+            .map(|channel_id| ChannelId::new(channel_id.0.get())),
+        None => None,
+    };
+    let channel_id = {
+        let guild = match ctx.cache.guild(guild_id) {
+            Some(guild) => guild,
+            None => {
+                error!("Failed to get guild");
+                return "Something went wrong.".to_string();
+            }
+        };
+        let user_channel_id = guild
+            .voice_states
+            .get(&command.user.id)
+            .and_then(|voice_state| voice_state.channel_id);
+        let can_view = |channel_id: ChannelId| {
+            let member = match &command.member {
+                Some(member) => member,
+                None => return false,
+            };
+            guild
+                .channels
+                .get(&channel_id)
+                .is_some_and(|channel| guild.user_permissions_in(channel, member).view_channel())
+        };
+        match user_channel_id.or(bot_channel_id.filter(|channel_id| can_view(*channel_id))) {
+            Some(channel_id) => channel_id,
+            None => return "Join a voice channel first.".to_string(),
+        }
+    };
+
+    let state = BotState::get(&ctx.data).await;
+    let clip =
+        match state
+            .clips
+            .lock()
+            .await
+            .get(guild_id, channel_id, ago.saturating_sub(1) as usize)
+        {
+            Some(clip) => clip,
+            None => return "I haven't said that much recently.".to_string(),
+        };
+
+    let message = CreateMessage::new()
+        .content(format!(
+            "🔊 {}: {}",
+            clip.user,
+            preprocess::truncate(&clip.text, 1800)
+        ))
+        .allowed_mentions(CreateAllowedMentions::new())
+        .add_file(CreateAttachment::bytes(clip.wav.to_vec(), "dectalk.wav"));
+    match command.channel_id.send_message(&ctx.http, message).await {
+        Ok(_) => "Posted the clip.".to_string(),
+        Err(e) => {
+            warn!(error = ?e, "Failed to post clip");
+            "Failed to post the clip, do I have permission to attach files here?".to_string()
+        }
+    }
+}
@@ -0,0 +1,846 @@
+use regex::RegexBuilder;
+use serenity::all::{
+    Channel, ChannelId, ChannelType, CommandInteraction, Context, GuildId, ResolvedOption,
+    ResolvedValue,
+};
+use tracing::{debug, error};
+use uuid::Uuid;
+
+use super::{get_string_option, get_subcommand, is_admin};
+use crate::{
+    announcements::MAX_ANNOUNCEMENTS,
+    cron::Schedule,
+    feeds,
+    guild_config::{
+        parse_id_list, Announcement, FeedSubscription, GuildConfigManager, Limits,
+        CHANNEL_SETTINGS, SETTINGS,
+    },
+    macros::{MAX_GUILD_MACROS, MAX_PHRASE_LENGTH, MAX_USER_MACROS},
+    state::BotState,
+};
+
+pub async fn config(ctx: &Context, command: &CommandInteraction) -> String {
+    let guild_id = match command.guild_id {
+        Some(guild_id) => guild_id,
+        None => return "This command only works in servers.".to_string(),
+    };
+
+    let state = BotState::get(&ctx.data).await;
+    let options = command.data.options();
+    let (subcommand, sub_options) = match get_subcommand(&options) {
+        Some(subcommand) => subcommand,
+        None => return "Unknown subcommand.".to_string(),
+    };
+
+    match subcommand {
+        "show" => {
+            let config = state.guild_configs.get_config(guild_id.get()).await;
+            SETTINGS
+                .iter()
+                .filter_map(|setting| Some(format!("`{}`: {}", setting, config.get(setting)?)))
+                .collect::<Vec<_>>()
+                .join("\n")
+        }
+        "set" => {
+            let setting = get_string_option(sub_options, "setting").unwrap_or_default();
+            let value = get_string_option(sub_options, "value").unwrap_or_default();
+            if let Err(e) = check_channel_setting(ctx, guild_id, setting, value).await {
+                return e;
+            }
+            match state
+                .guild_configs
+                .update_config(guild_id.get(), |config| config.set(setting, value))
+                .await
+            {
+                Ok(Ok(())) => format!("Set `{}` to {}", setting, value),
+                Ok(Err(e)) => e,
+                Err(e) => {
+                    error!(error = ?e, "Failed to save guild configs");
+                    "Failed to save the setting.".to_string()
+                }
+            }
+        }
+        "channels" => channels(&state.guild_configs, guild_id, sub_options).await,
+        _ => "Unknown subcommand.".to_string(),
+    }
+}
+
+/// The bot can post anywhere it's been added, so channel settings only take this guild's
+/// channels.
+async fn check_channel_setting(
+    ctx: &Context,
+    guild_id: GuildId,
+    setting: &str,
+    value: &str,
+) -> Result<(), String> {
+    if !CHANNEL_SETTINGS.contains(&setting) {
+        return Ok(());
+    }
+    let channel_id = match parse_id_list(value)?.first() {
+        Some(0) => return Err("Pick a channel in this server.".to_string()),
+        Some(channel_id) => ChannelId::new(*channel_id),
+        None => return Ok(()),
+    };
+    match channel_id.to_channel(ctx).await {
+        Ok(Channel::Guild(channel)) if channel.guild_id == guild_id => Ok(()),
+        _ => Err("Pick a channel in this server.".to_string()),
+    }
+}
+
+async fn channels(
+    guild_configs: &GuildConfigManager,
+    guild_id: GuildId,
+    options: &[ResolvedOption<'_>],
+) -> String {
+    let (subcommand, sub_options) = match get_subcommand(options) {
+        Some(subcommand) => subcommand,
+        None => return "Unknown subcommand.".to_string(),
+    };
+
+    let channel = sub_options.iter().find_map(|option| match option.value {
+        ResolvedValue::Channel(channel) => Some(channel),
+        _ => None,
+    });
+    let result = match (subcommand, channel) {
+        ("allow" | "block" | "remove", Some(channel)) => {
+            let is_voice = matches!(channel.kind, ChannelType::Voice | ChannelType::Stage);
+            let id = channel.id.get();
+            guild_configs
+                .update_config(guild_id.get(), |config| {
+                    let list = if is_voice {
+                        &mut config.voice_channels
+                    } else {
+                        &mut config.text_channels
+                    };
+                    list.allowed.retain(|allowed| *allowed != id);
+                    list.blocked.retain(|blocked| *blocked != id);
+                    match subcommand {
+                        "allow" => {
+                            list.allowed.push(id);
+                            format!("Allowed <#{}>", id)
+                        }
+                        "block" => {
+                            list.blocked.push(id);
+                            format!("Blocked <#{}>", id)
+                        }
+                        _ => format!("Removed <#{}>", id),
+                    }
+                })
+                .await
+        }
+        ("list", _) => {
+            let config = guild_configs.get_config(guild_id.get()).await;
+            let format_channels = |ids: &[u64]| match ids {
+                [] => "none".to_string(),
+                ids => ids
+                    .iter()
+                    .map(|id| format!("<#{}>", id))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            };
+            return format!(
+                "Allowed text: {}\nBlocked text: {}\nAllowed voice: {}\nBlocked voice: {}",
+                format_channels(&config.text_channels.allowed),
+                format_channels(&config.text_channels.blocked),
+                format_channels(&config.voice_channels.allowed),
+                format_channels(&config.voice_channels.blocked)
+            );
+        }
+        _ => return "Unknown subcommand.".to_string(),
+    };
+
+    match result {
+        Ok(content) => content,
+        Err(e) => {
+            error!(error = ?e, "Failed to save guild configs");
+            "Failed to save the change.".to_string()
+        }
+    }
+}
+
+pub async fn slang(ctx: &Context, command: &CommandInteraction) -> String {
+    let guild_id = match command.guild_id {
+        Some(guild_id) => guild_id,
+        None => return "This command only works in servers.".to_string(),
+    };
+
+    let state = BotState::get(&ctx.data).await;
+    let options = command.data.options();
+    let (subcommand, sub_options) = match get_subcommand(&options) {
+        Some(subcommand) => subcommand,
+        None => return "Unknown subcommand.".to_string(),
+    };
+
+    let abbreviation = get_string_option(sub_options, "abbreviation")
+        .unwrap_or_default()
+        .to_lowercase();
+    let result = match subcommand {
+        "add" => {
+            let expansion = get_string_option(sub_options, "expansion")
+                .unwrap_or_default()
+                .to_string();
+            state
+                .guild_configs
+                .update_config(guild_id.get(), |config| {
+                    config.slang.insert(abbreviation.clone(), expansion.clone());
+                    format!("`{}` will be read as \"{}\"", abbreviation, expansion)
+                })
+                .await
+        }
+        "remove" => {
+            state
+                .guild_configs
+                .update_config(guild_id.get(), |config| {
+                    match config.slang.remove(&abbreviation) {
+                        Some(_) => format!("Removed `{}`", abbreviation),
+                        None => format!("`{}` isn't in the list", abbreviation),
+                    }
+                })
+                .await
+        }
+        "list" => {
+            let config = state.guild_configs.get_config(guild_id.get()).await;
+            if config.slang.is_empty() {
+                return "No custom expansions yet.".to_string();
+            }
+
+            let mut entries = config
+                .slang
+                .iter()
+                .map(|(abbreviation, expansion)| format!("`{}`: {}", abbreviation, expansion))
+                .collect::<Vec<_>>();
+            entries.sort();
+            return entries.join("\n");
+        }
+        _ => return "Unknown subcommand.".to_string(),
+    };
+
+    match result {
+        Ok(content) => content,
+        Err(e) => {
+            error!(error = ?e, "Failed to save guild configs");
+            "Failed to save the change.".to_string()
+        }
+    }
+}
+
+pub async fn macros(ctx: &Context, command: &CommandInteraction) -> String {
+    let state = BotState::get(&ctx.data).await;
+    let options = command.data.options();
+    let (subcommand, sub_options) = match get_subcommand(&options) {
+        Some(subcommand) => subcommand,
+        None => return "Unknown subcommand.".to_string(),
+    };
+
+    let guild_id = match get_string_option(sub_options, "scope") {
+        Some("server") => match command.guild_id {
+            Some(guild_id) if subcommand == "list" || is_admin(ctx, command).await => {
+                Some(guild_id)
+            }
+            Some(_) => {
+                return "You need the Manage Server permission or this server's admin role for \
+                        server macros."
+                    .to_string()
+            }
+            None => return "Server macros only work in servers.".to_string(),
+        },
+        _ => None,
+    };
+    let user_id = command.user.id.get();
+
+    let trigger = get_string_option(sub_options, "trigger")
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase();
+    let result = match subcommand {
+        "add" => {
+            if trigger.is_empty() || !trigger.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                return "Triggers must be a single word.".to_string();
+            }
+            let phrase = get_string_option(sub_options, "phrase")
+                .unwrap_or_default()
+                .trim()
+                .to_string();
+            if phrase.is_empty() || phrase.len() > MAX_PHRASE_LENGTH {
+                return format!("Phrases must be 1 to {} characters.", MAX_PHRASE_LENGTH);
+            }
+
+            let added = format!("`{}` will be read as \"{}\"", trigger, phrase);
+            match guild_id {
+                Some(guild_id) => {
+                    state
+                        .guild_configs
+                        .update_config(guild_id.get(), |config| {
+                            if config.macros.len() >= MAX_GUILD_MACROS
+                                && !config.macros.contains_key(&trigger)
+                            {
+                                return format!(
+                                    "This server already has {} macros.",
+                                    MAX_GUILD_MACROS
+                                );
+                            }
+                            config.macros.insert(trigger.clone(), phrase.clone());
+                            added.clone()
+                        })
+                        .await
+                }
+                None => state
+                    .macros
+                    .add(user_id, trigger.clone(), phrase.clone())
+                    .await
+                    .map(|ok| match ok {
+                        true => added.clone(),
+                        false => format!("You already have {} macros.", MAX_USER_MACROS),
+                    }),
+            }
+        }
+        "remove" => {
+            let removed = match guild_id {
+                Some(guild_id) => {
+                    state
+                        .guild_configs
+                        .update_config(guild_id.get(), |config| {
+                            config.macros.remove(&trigger).is_some()
+                        })
+                        .await
+                }
+                None => state.macros.remove(user_id, &trigger).await,
+            };
+            removed.map(|removed| match removed {
+                true => format!("Removed `{}`", trigger),
+                false => format!("`{}` isn't a macro", trigger),
+            })
+        }
+        "list" => {
+            let macros = match guild_id {
+                Some(guild_id) => state.guild_configs.get_config(guild_id.get()).await.macros,
+                None => state.macros.get(user_id).await,
+            };
+            if macros.is_empty() {
+                return "No macros yet.".to_string();
+            }
+
+            let mut entries = macros
+                .iter()
+                .map(|(trigger, phrase)| format!("`{}`: {}", trigger, phrase))
+                .collect::<Vec<_>>();
+            entries.sort();
+            return entries.join("\n");
+        }
+        _ => return "Unknown subcommand.".to_string(),
+    };
+
+    match result {
+        Ok(content) => content,
+        Err(e) => {
+            error!(error = ?e, "Failed to save macros");
+            "Failed to save the change.".to_string()
+        }
+    }
+}
+
+pub async fn filter(ctx: &Context, command: &CommandInteraction) -> String {
+    let guild_id = match command.guild_id {
+        Some(guild_id) => guild_id,
+        None => return "This command only works in servers.".to_string(),
+    };
+
+    let state = BotState::get(&ctx.data).await;
+    let options = command.data.options();
+    let (subcommand, sub_options) = match get_subcommand(&options) {
+        Some(subcommand) => subcommand,
+        None => return "Unknown subcommand.".to_string(),
+    };
+
+    let pattern = get_string_option(sub_options, "pattern")
+        .unwrap_or_default()
+        .to_string();
+    let result = match subcommand {
+        "add" => {
+            if let Err(e) = RegexBuilder::new(&pattern).size_limit(1 << 16).build() {
+                return format!("Invalid pattern: {}", e);
+            }
+
+            let allow = get_string_option(sub_options, "kind") == Some("allow");
+            state
+                .guild_configs
+                .update_config(guild_id.get(), |config| {
+                    let filters = if allow {
+                        &mut config.allow_filters
+                    } else {
+                        &mut config.deny_filters
+                    };
+                    if !filters.contains(&pattern) {
+                        filters.push(pattern.clone());
+                    }
+                    format!(
+                        "Messages matching `{}` will be {}",
+                        pattern,
+                        if allow { "allowed" } else { "skipped" }
+                    )
+                })
+                .await
+        }
+        "remove" => {
+            state
+                .guild_configs
+                .update_config(guild_id.get(), |config| {
+                    let count = config.allow_filters.len() + config.deny_filters.len();
+                    config.allow_filters.retain(|filter| *filter != pattern);
+                    config.deny_filters.retain(|filter| *filter != pattern);
+                    if count == config.allow_filters.len() + config.deny_filters.len() {
+                        format!("`{}` isn't a filter", pattern)
+                    } else {
+                        format!("Removed `{}`", pattern)
+                    }
+                })
+                .await
+        }
+        "list" => {
+            let config = state.guild_configs.get_config(guild_id.get()).await;
+            let entries = config
+                .allow_filters
+                .iter()
+                .map(|filter| format!("allow `{}`", filter))
+                .chain(
+                    config
+                        .deny_filters
+                        .iter()
+                        .map(|filter| format!("deny `{}`", filter)),
+                )
+                .collect::<Vec<_>>();
+            if entries.is_empty() {
+                return "No filters yet.".to_string();
+            }
+            return entries.join("\n");
+        }
+        _ => return "Unknown subcommand.".to_string(),
+    };
+
+    match result {
+        Ok(content) => content,
+        Err(e) => {
+            error!(error = ?e, "Failed to save guild configs");
+            "Failed to save the change.".to_string()
+        }
+    }
+}
+
+pub async fn profanity(ctx: &Context, command: &CommandInteraction) -> String {
+    let guild_id = match command.guild_id {
+        Some(guild_id) => guild_id,
+        None => return "This command only works in servers.".to_string(),
+    };
+
+    let state = BotState::get(&ctx.data).await;
+    let options = command.data.options();
+    let (subcommand, sub_options) = match get_subcommand(&options) {
+        Some(subcommand) => subcommand,
+        None => return "Unknown subcommand.".to_string(),
+    };
+
+    let word = get_string_option(sub_options, "word")
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase();
+    let result = match subcommand {
+        "add" => {
+            if word.is_empty() {
+                return "The word can't be empty.".to_string();
+            }
+            state
+                .guild_configs
+                .update_config(guild_id.get(), |config| {
+                    if !config.profanity_words.contains(&word) {
+                        config.profanity_words.push(word.clone());
+                    }
+                    format!("Filtering ||{}||", word)
+                })
+                .await
+        }
+        "remove" => {
+            state
+                .guild_configs
+                .update_config(guild_id.get(), |config| {
+                    let count = config.profanity_words.len();
+                    config.profanity_words.retain(|filtered| *filtered != word);
+                    if count == config.profanity_words.len() {
+                        format!("||{}|| isn't filtered", word)
+                    } else {
+                        format!("No longer filtering ||{}||", word)
+                    }
+                })
+                .await
+        }
+        "list" => {
+            let config = state.guild_configs.get_config(guild_id.get()).await;
+            if config.profanity_words.is_empty() {
+                return "No filtered words yet.".to_string();
+            }
+            return config
+                .profanity_words
+                .iter()
+                .map(|word| format!("||{}||", word))
+                .collect::<Vec<_>>()
+                .join(", ");
+        }
+        _ => return "Unknown subcommand.".to_string(),
+    };
+
+    match result {
+        Ok(content) => content,
+        Err(e) => {
+            error!(error = ?e, "Failed to save guild configs");
+            "Failed to save the change.".to_string()
+        }
+    }
+}
+
+pub async fn limits(ctx: &Context, command: &CommandInteraction) -> String {
+    let guild_id = match command.guild_id {
+        Some(guild_id) => guild_id,
+        None => return "This command only works in servers.".to_string(),
+    };
+
+    let state = BotState::get(&ctx.data).await;
+    let options = command.data.options();
+    let (subcommand, sub_options) = match get_subcommand(&options) {
+        Some(subcommand) => subcommand,
+        None => return "Unknown subcommand.".to_string(),
+    };
+
+    let role = sub_options.iter().find_map(|option| match option.value {
+        ResolvedValue::Role(role) => Some(role.id),
+        _ => None,
+    });
+    let result = match (subcommand, role) {
+        ("set", Some(role)) => {
+            let max_message_length = sub_options
+                .iter()
+                .find_map(|option| match option.value {
+                    ResolvedValue::Integer(length) => Some(length.max(1) as usize),
+                    _ => None,
+                })
+                .unwrap_or_default();
+            let max_duration = sub_options
+                .iter()
+                .find_map(|option| match option.value {
+                    ResolvedValue::Number(duration) => Some(duration.max(1.0)),
+                    _ => None,
+                })
+                .unwrap_or_default();
+            state
+                .guild_configs
+                .update_config(guild_id.get(), |config| {
+                    config.role_limits.insert(
+                        role.get(),
+                        Limits {
+                            max_message_length,
+                            max_duration,
+                        },
+                    );
+                    format!(
+                        "<@&{}> can send {} characters and {} seconds",
+                        role, max_message_length, max_duration
+                    )
+                })
+                .await
+        }
+        ("remove", Some(role)) => {
+            state
+                .guild_configs
+                .update_config(guild_id.get(), |config| {
+                    match config.role_limits.remove(&role.get()) {
+                        Some(_) => format!("Removed the limits for <@&{}>", role),
+                        None => format!("<@&{}> has no limits set", role),
+                    }
+                })
+                .await
+        }
+        ("list", _) => {
+            let config = state.guild_configs.get_config(guild_id.get()).await;
+            let mut entries = vec![format!(
+                "Everyone: {} characters, {} seconds",
+                config.limits.max_message_length, config.limits.max_duration
+            )];
+            entries.extend(config.role_limits.iter().map(|(role, limits)| {
+                format!(
+                    "<@&{}>: {} characters, {} seconds",
+                    role, limits.max_message_length, limits.max_duration
+                )
+            }));
+            return entries.join("\n");
+        }
+        _ => return "Unknown subcommand.".to_string(),
+    };
+
+    match result {
+        Ok(content) => content,
+        Err(e) => {
+            error!(error = ?e, "Failed to save guild configs");
+            "Failed to save the change.".to_string()
+        }
+    }
+}
+
+pub async fn tts(ctx: &Context, command: &CommandInteraction) -> String {
+    let guild_id = match command.guild_id {
+        Some(guild_id) => guild_id,
+        None => return "This command only works in servers.".to_string(),
+    };
+
+    let state = BotState::get(&ctx.data).await;
+    let options = command.data.options();
+    let enabled = match get_subcommand(&options) {
+        Some(("enable", _)) => true,
+        Some(("disable", _)) => false,
+        _ => return "Unknown subcommand.".to_string(),
+    };
+
+    match state
+        .guild_configs
+        .update_config(guild_id.get(), |config| config.enabled = enabled)
+        .await
+    {
+        Ok(()) if enabled => "Reading messages aloud again.".to_string(),
+        Ok(()) => "No longer reading messages aloud.".to_string(),
+        Err(e) => {
+            error!(error = ?e, "Failed to save guild configs");
+            "Failed to save the change.".to_string()
+        }
+    }
+}
+
+pub async fn transcript(ctx: &Context, command: &CommandInteraction) -> String {
+    let guild_id = match command.guild_id {
+        Some(guild_id) => guild_id,
+        None => return "This command only works in servers.".to_string(),
+    };
+
+    let state = BotState::get(&ctx.data).await;
+    let options = command.data.options();
+    match get_subcommand(&options) {
+        Some(("token", _)) => {
+            let token = Uuid::new_v4().simple().to_string();
+            let result = state
+                .guild_configs
+                .update_config(guild_id.get(), |config| {
+                    config.transcript_token = Some(token.clone())
+                })
+                .await;
+            match result {
+                Ok(()) => format!(
+                    "Point caption overlays at `ws://<bot address>/transcript/{}?token={}`. \
+                     Anyone with this link can read along, run this again to revoke it.",
+                    guild_id, token
+                ),
+                Err(e) => {
+                    error!(error = ?e, "Failed to save guild configs");
+                    "Failed to save the token.".to_string()
+                }
+            }
+        }
+        _ => "Unknown subcommand.".to_string(),
+    }
+}
+
+pub async fn feed(ctx: &Context, command: &CommandInteraction) -> String {
+    let guild_id = match command.guild_id {
+        Some(guild_id) => guild_id,
+        None => return "This command only works in servers.".to_string(),
+    };
+
+    let state = BotState::get(&ctx.data).await;
+    let options = command.data.options();
+    let (subcommand, sub_options) = match get_subcommand(&options) {
+        Some(subcommand) => subcommand,
+        None => return "Unknown subcommand.".to_string(),
+    };
+
+    let url = get_string_option(sub_options, "url")
+        .unwrap_or_default()
+        .trim()
+        .to_string();
+    let result = match subcommand {
+        "add" => {
+            let channel = match sub_options.iter().find_map(|option| match option.value {
+                ResolvedValue::Channel(channel) => Some(channel.id.get()),
+                _ => None,
+            }) {
+                Some(channel) => channel,
+                None => return "Pick a channel for the links.".to_string(),
+            };
+            let title = match feeds::fetch_feed(&url).await {
+                Ok(feed) => feed
+                    .title
+                    .map(|title| title.content)
+                    .unwrap_or_else(|| url.clone()),
+                Err(e) => {
+                    debug!(error = ?e, "Failed to fetch {}", url);
+                    return "Couldn't read that feed.".to_string();
+                }
+            };
+            state
+                .guild_configs
+                .update_config(guild_id.get(), |config| {
+                    config.feeds.retain(|subscription| subscription.url != url);
+                    if config.feeds.len() >= feeds::MAX_FEEDS {
+                        return format!("Servers can follow up to {} feeds.", feeds::MAX_FEEDS);
+                    }
+                    config.feeds.push(FeedSubscription {
+                        url: url.clone(),
+                        channel,
+                    });
+                    format!(
+                        "Following **{}**, new items will show up in <#{}>",
+                        title, channel
+                    )
+                })
+                .await
+        }
+        "remove" => {
+            state
+                .guild_configs
+                .update_config(guild_id.get(), |config| {
+                    let before = config.feeds.len();
+                    config.feeds.retain(|subscription| subscription.url != url);
+                    if config.feeds.len() < before {
+                        format!("Stopped following <{}>", url)
+                    } else {
+                        format!("<{}> isn't followed here", url)
+                    }
+                })
+                .await
+        }
+        "list" => {
+            let config = state.guild_configs.get_config(guild_id.get()).await;
+            if config.feeds.is_empty() {
+                return "No feeds yet.".to_string();
+            }
+            return config
+                .feeds
+                .iter()
+                .map(|subscription| {
+                    format!("<{}> in <#{}>", subscription.url, subscription.channel)
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+        }
+        _ => return "Unknown subcommand.".to_string(),
+    };
+
+    match result {
+        Ok(content) => content,
+        Err(e) => {
+            error!(error = ?e, "Failed to save guild configs");
+            "Failed to save the change.".to_string()
+        }
+    }
+}
+
+pub async fn announce(ctx: &Context, command: &CommandInteraction) -> String {
+    let guild_id = match command.guild_id {
+        Some(guild_id) => guild_id,
+        None => return "This command only works in servers.".to_string(),
+    };
+
+    let state = BotState::get(&ctx.data).await;
+    let options = command.data.options();
+    let (subcommand, sub_options) = match get_subcommand(&options) {
+        Some(subcommand) => subcommand,
+        None => return "Unknown subcommand.".to_string(),
+    };
+
+    let result = match subcommand {
+        "add" => {
+            let schedule = get_string_option(sub_options, "schedule")
+                .unwrap_or_default()
+                .trim()
+                .to_string();
+            if let Err(e) = Schedule::parse(&schedule) {
+                return format!("Invalid schedule: {}", e);
+            }
+            let text = get_string_option(sub_options, "text")
+                .unwrap_or_default()
+                .trim()
+                .to_string();
+            let voice = sub_options.iter().find_map(|option| match option.value {
+                ResolvedValue::Integer(voice) if option.name == "voice" => {
+                    Some(voice.max(0) as u64)
+                }
+                _ => None,
+            });
+            let channel = command.channel_id.get();
+            state
+                .guild_configs
+                .update_config(guild_id.get(), |config| {
+                    if config.announcements.len() >= MAX_ANNOUNCEMENTS {
+                        return format!(
+                            "Servers can have up to {} announcements.",
+                            MAX_ANNOUNCEMENTS
+                        );
+                    }
+                    config.announcements.push(Announcement {
+                        schedule: schedule.clone(),
+                        text,
+                        channel,
+                        voice,
+                    });
+                    format!(
+                        "Scheduled for `{}` UTC, it'll be posted in <#{}>",
+                        schedule, channel
+                    )
+                })
+                .await
+        }
+        "remove" => {
+            let number = sub_options
+                .iter()
+                .find_map(|option| match option.value {
+                    ResolvedValue::Integer(number) => Some(number),
+                    _ => None,
+                })
+                .unwrap_or_default();
+            state
+                .guild_configs
+                .update_config(guild_id.get(), |config| {
+                    if number < 1 || number as usize > config.announcements.len() {
+                        return format!("There's no announcement {}.", number);
+                    }
+                    let announcement = config.announcements.remove(number as usize - 1);
+                    format!("Removed `{}`: {}", announcement.schedule, announcement.text)
+                })
+                .await
+        }
+        "list" => {
+            let config = state.guild_configs.get_config(guild_id.get()).await;
+            if config.announcements.is_empty() {
+                return "No announcements yet.".to_string();
+            }
+            return config
+                .announcements
+                .iter()
+                .enumerate()
+                .map(|(index, announcement)| {
+                    format!(
+                        "{}. `{}` in <#{}>: {}",
+                        index + 1,
+                        announcement.schedule,
+                        announcement.channel,
+                        announcement.text
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+        }
+        _ => return "Unknown subcommand.".to_string(),
+    };
+
+    match result {
+        Ok(content) => content,
+        Err(e) => {
+            error!(error = ?e, "Failed to save guild configs");
+            "Failed to save the change.".to_string()
+        }
+    }
+}
@@ -0,0 +1,392 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use rand::Rng;
+use serenity::all::{
+    CommandInteraction, Context, CreateAllowedMentions, CreateMessage, GuildId, ResolvedValue,
+};
+use tracing::{debug, error, warn};
+
+use super::{get_string_option, get_subcommand};
+use crate::{
+    audio::speak_in_guild,
+    config,
+    reminders::{self, Reminder, MAX_DELAY, MAX_REMINDERS},
+    state::BotState,
+};
+
+const MAX_DICE: u32 = 20;
+const MAX_SIDES: u32 = 1000;
+
+/// Opting out applies in every server, the audio is never sent for transcription.
+pub async fn transcription(ctx: &Context, command: &CommandInteraction) -> String {
+    let options = command.data.options();
+    let allow = options.iter().find_map(|option| match option.value {
+        ResolvedValue::Boolean(allow) => Some(allow),
+        _ => None,
+    });
+
+    let state = BotState::get(&ctx.data).await;
+    let user_id = command.user.id.get();
+    let allow = match allow {
+        Some(allow) => allow,
+        None if state.transcription_opt_outs.contains(user_id).await => {
+            return "You've opted out of transcription in every server.".to_string()
+        }
+        None => {
+            return "Servers that turned on transcription can transcribe what you say.".to_string()
+        }
+    };
+    match state.transcription_opt_outs.set(user_id, !allow).await {
+        Ok(_) if allow => {
+            "Servers that turned on transcription can transcribe what you say again.".to_string()
+        }
+        Ok(_) => "What you say in calls won't be transcribed in any server.".to_string(),
+        Err(e) => {
+            error!(error = ?e, "Failed to save transcription opt-outs");
+            "Failed to save that.".to_string()
+        }
+    }
+}
+
+pub async fn remindme(ctx: &Context, command: &CommandInteraction) -> String {
+    let guild_id = match command.guild_id {
+        Some(guild_id) => guild_id,
+        None => return "This command only works in servers.".to_string(),
+    };
+
+    let options = command.data.options();
+    let delay = match get_string_option(&options, "in").and_then(reminders::parse_delay) {
+        Some(delay) if delay <= MAX_DELAY => delay,
+        Some(_) => return "Reminders can be at most 30 days away.".to_string(),
+        None => return "Give a delay like 10m, 2h or 1h30m.".to_string(),
+    };
+    let text = get_string_option(&options, "text")
+        .unwrap_or_default()
+        .trim()
+        .to_string();
+    if text.is_empty() {
+        return "What should I remind you about?".to_string();
+    }
+
+    let due = reminders::unix_now() + delay.as_secs();
+    let state = BotState::get(&ctx.data).await;
+    let reminder = Reminder {
+        user_id: command.user.id.get(),
+        guild_id: guild_id.get(),
+        due,
+        text,
+        timer: false,
+    };
+    match state.reminders.add(reminder).await {
+        Ok(true) => format!(
+            "I'll remind you <t:{}:R>, out loud if you're in a call with me.",
+            due
+        ),
+        Ok(false) => format!("You can only have {} reminders at once.", MAX_REMINDERS),
+        Err(e) => {
+            error!(error = ?e, "Failed to save reminder");
+            "Failed to save the reminder.".to_string()
+        }
+    }
+}
+
+pub async fn time(ctx: &Context, command: &CommandInteraction) -> String {
+    let guild_id = match command.guild_id {
+        Some(guild_id) => guild_id,
+        None => return "This command only works in servers.".to_string(),
+    };
+
+    let state = BotState::get(&ctx.data).await;
+    let config = state.guild_configs.get_config(guild_id.get()).await;
+    let now = Utc::now().with_timezone(&config.timezone);
+    let spoken = format!("It's {}", now.format("%-I:%M %p"));
+    announce_result(
+        ctx,
+        command,
+        guild_id,
+        &format!("🕒 {} ({}).", spoken, config.timezone),
+        &spoken,
+    )
+    .await
+}
+
+pub async fn timer(ctx: &Context, command: &CommandInteraction) -> String {
+    let guild_id = match command.guild_id {
+        Some(guild_id) => guild_id,
+        None => return "This command only works in servers.".to_string(),
+    };
+
+    let options = command.data.options();
+    let delay = match get_string_option(&options, "duration").and_then(reminders::parse_delay) {
+        Some(delay) if delay <= MAX_DELAY => delay,
+        Some(_) => return "Timers can be at most 30 days long.".to_string(),
+        None => return "Give a duration like 5m, 90s or 1h30m.".to_string(),
+    };
+    let text = match get_string_option(&options, "label").map(str::trim) {
+        Some(label) if !label.is_empty() => format!("Timer done: {}", label),
+        _ => "Timer done".to_string(),
+    };
+
+    let due = reminders::unix_now() + delay.as_secs();
+    let state = BotState::get(&ctx.data).await;
+    let timer = Reminder {
+        user_id: command.user.id.get(),
+        guild_id: guild_id.get(),
+        due,
+        text,
+        timer: true,
+    };
+    match state.reminders.add(timer).await {
+        Ok(true) => format!("Timer set, it goes off <t:{}:R>.", due),
+        Ok(false) => format!(
+            "You can only have {} timers and reminders at once.",
+            MAX_REMINDERS
+        ),
+        Err(e) => {
+            error!(error = ?e, "Failed to save timer");
+            "Failed to save the timer.".to_string()
+        }
+    }
+}
+
+pub async fn roll_dice(ctx: &Context, command: &CommandInteraction) -> String {
+    let guild_id = match command.guild_id {
+        Some(guild_id) => guild_id,
+        None => return "This command only works in servers.".to_string(),
+    };
+
+    let options = command.data.options();
+    let dice = get_string_option(&options, "dice").unwrap_or("1d6").trim();
+    let (count, sides) = match parse_dice(dice) {
+        Some(dice) => dice,
+        None => {
+            return format!(
+                "Give dice like 2d6, with up to {} dice of up to {} sides.",
+                MAX_DICE, MAX_SIDES
+            )
+        }
+    };
+
+    let rolls = (0..count)
+        .map(|_| rand::rng().random_range(1..=sides))
+        .collect::<Vec<_>>();
+    let total = rolls.iter().sum::<u32>();
+    let name = get_member_name(command);
+    let rolls = rolls.iter().map(u32::to_string).collect::<Vec<_>>();
+    let (written, spoken) = if count == 1 {
+        (
+            format!("🎲 **{}** rolled {}: **{}**", name, dice, total),
+            format!("{} rolled {}", name, total),
+        )
+    } else {
+        (
+            format!(
+                "🎲 **{}** rolled {}: {} = **{}**",
+                name,
+                dice,
+                rolls.join(" + "),
+                total
+            ),
+            format!("{} rolled {}, for {}", name, rolls.join(", "), total),
+        )
+    };
+    announce_result(ctx, command, guild_id, &written, &spoken).await
+}
+
+pub async fn flip(ctx: &Context, command: &CommandInteraction) -> String {
+    let guild_id = match command.guild_id {
+        Some(guild_id) => guild_id,
+        None => return "This command only works in servers.".to_string(),
+    };
+
+    let side = if rand::random::<bool>() {
+        "heads"
+    } else {
+        "tails"
+    };
+    let name = get_member_name(command);
+    announce_result(
+        ctx,
+        command,
+        guild_id,
+        &format!("🪙 **{}** flipped **{}**", name, side),
+        &format!("{} flipped {}", name, side),
+    )
+    .await
+}
+
+/// Parses `NdM`, where a missing `N` means one die.
+fn parse_dice(dice: &str) -> Option<(u32, u32)> {
+    let (count, sides) = dice
+        .to_lowercase()
+        .split_once('d')
+        .map(|(count, sides)| (count.trim().to_string(), sides.trim().to_string()))?;
+    let count = match count.as_str() {
+        "" => 1,
+        count => count.parse::<u32>().ok()?,
+    };
+    let sides = sides.parse::<u32>().ok()?;
+    if !(1..=MAX_DICE).contains(&count) || !(2..=MAX_SIDES).contains(&sides) {
+        return None;
+    }
+    Some((count, sides))
+}
+
+fn get_member_name(command: &CommandInteraction) -> String {
+    command
+        .member
+        .as_ref()
+        .and_then(|member| member.nick.clone())
+        .or_else(|| command.user.global_name.clone())
+        .unwrap_or_else(|| command.user.name.clone())
+}
+
+/// Posts `written` in the channel and reads `spoken` in the invoker's voice channel, with the
+/// same rate limit and guild limits as their messages.
+async fn announce_result(
+    ctx: &Context,
+    command: &CommandInteraction,
+    guild_id: GuildId,
+    written: &str,
+    spoken: &str,
+) -> String {
+    let message = CreateMessage::new()
+        .content(written)
+        .allowed_mentions(CreateAllowedMentions::new());
+    if let Err(e) = command.channel_id.send_message(&ctx.http, message).await {
+        warn!(error = ?e, "Failed to post result");
+    }
+
+    let user_channel_id = ctx.cache.guild(guild_id).and_then(|guild| {
+        guild
+            .voice_states
+            .get(&command.user.id)
+            .and_then(|voice_state| voice_state.channel_id)
+    });
+    let channel_id = match user_channel_id {
+        Some(channel_id) => channel_id,
+        None => return written.to_string(),
+    };
+
+    let state = BotState::get(&ctx.data).await;
+    let config = state.guild_configs.get_config(guild_id.get()).await;
+    if !config::get().is_operator(command.user.id.get())
+        && config.rate_limit > 0
+        && !state.user_rate_limits.lock().await.try_take(
+            (guild_id, command.user.id),
+            1.0,
+            config.rate_limit as f64,
+            Duration::from_secs(config.rate_limit_period),
+        )
+    {
+        return written.to_string();
+    }
+
+    let manager = match songbird::get(ctx).await {
+        Some(manager) => manager,
+        None => {
+            error!("Failed to get songbird manager");
+            return written.to_string();
+        }
+    };
+    let voice = state.voice_manager.get_voice(command.user.id.get()).await;
+    match speak_in_guild(
+        &ctx.data,
+        &ctx.cache,
+        &manager,
+        guild_id,
+        Some(channel_id),
+        &get_member_name(command),
+        spoken,
+        &voice,
+    )
+    .await
+    {
+        Ok(()) => {}
+        Err(e) if e.is_expected() => debug!(error = ?e, "Not reading result"),
+        Err(e) => error!(error = ?e, "Failed to read result"),
+    }
+    written.to_string()
+}
+
+pub async fn lottery(ctx: &Context, command: &CommandInteraction) -> String {
+    let guild_id = match command.guild_id {
+        Some(guild_id) => guild_id,
+        None => return "This command only works in servers.".to_string(),
+    };
+
+    let state = BotState::get(&ctx.data).await;
+    let options = command.data.options();
+    let user_id = command.user.id.get();
+    let result = match get_subcommand(&options) {
+        Some(("join", _)) => {
+            state
+                .guild_configs
+                .update_config(guild_id.get(), |config| {
+                    if config.lottery_entrants.contains(&user_id) {
+                        return "You're already in the lottery.".to_string();
+                    }
+                    config.lottery_entrants.push(user_id);
+                    match config.lottery_channel {
+                        Some(_) => format!(
+                            "You're in! The lottery is drawn daily at {}:00 UTC.",
+                            config.lottery_hour
+                        ),
+                        None => "You're in, but this server hasn't turned the lottery on yet."
+                            .to_string(),
+                    }
+                })
+                .await
+        }
+        Some(("leave", _)) => {
+            state
+                .guild_configs
+                .update_config(guild_id.get(), |config| {
+                    let before = config.lottery_entrants.len();
+                    config
+                        .lottery_entrants
+                        .retain(|entrant| *entrant != user_id);
+                    if config.lottery_entrants.len() < before {
+                        "You left the lottery.".to_string()
+                    } else {
+                        "You weren't in the lottery.".to_string()
+                    }
+                })
+                .await
+        }
+        Some(("status", _)) => {
+            let config = state.guild_configs.get_config(guild_id.get()).await;
+            let mut lines = vec![match config.lottery_channel {
+                Some(channel) => format!(
+                    "Drawn daily at {}:00 UTC and announced in <#{}>, {} entrants.",
+                    config.lottery_hour,
+                    channel,
+                    config.lottery_entrants.len()
+                ),
+                None => "The lottery is off here, admins can turn it on with \
+                         `/config set lottery_channel`."
+                    .to_string(),
+            }];
+            if let Some(golden_voice) = config
+                .golden_voice
+                .filter(|golden_voice| golden_voice.until > reminders::unix_now())
+            {
+                lines.push(format!(
+                    "<@{}> has the golden voice until <t:{}:t>.",
+                    golden_voice.user, golden_voice.until
+                ));
+            }
+            return lines.join("\n");
+        }
+        _ => return "Unknown subcommand.".to_string(),
+    };
+
+    match result {
+        Ok(content) => content,
+        Err(e) => {
+            error!(error = ?e, "Failed to save guild configs");
+            "Failed to save the change.".to_string()
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use dectalk_bot_core::{audio, dectalk::Language};
+use serenity::all::{CommandInteraction, Context, ResolvedValue};
+use tracing::error;
+
+use super::get_string_option;
+use crate::{
+    audio::synthesize,
+    guild_config::{AssignedVoice, GuildConfig},
+    preprocess::process_message,
+    state::BotState,
+};
+
+pub async fn preview(ctx: &Context, command: &CommandInteraction) -> (String, Option<Vec<u8>>) {
+    let options = command.data.options();
+    let text = get_string_option(&options, "text").unwrap_or_default();
+    match speak_as(ctx, command, text).await {
+        Some(audio) => ("Here's how that sounds.".to_string(), Some(audio)),
+        None => ("Failed to preview that text.".to_string(), None),
+    }
+}
+
+pub async fn voice(ctx: &Context, command: &CommandInteraction) -> (String, Option<Vec<u8>>) {
+    let state = BotState::get(&ctx.data).await;
+    let user_id = command.user.id;
+    let options = command.data.options();
+    let requested_roll = options.iter().find_map(|option| match option.value {
+        ResolvedValue::Integer(roll) => Some(roll.max(0) as u64),
+        _ => None,
+    });
+    let config = guild_config(&state, command).await;
+    let content = match requested_roll {
+        Some(roll) => {
+            state.voice_manager.set_roll(user_id.get(), roll).await;
+            // Picking a voice replaces the one this server's staff assigned too
+            if let Some(guild_id) = command.guild_id {
+                if config.assigned_voices.contains_key(&user_id.get()) {
+                    let cleared = state
+                        .guild_configs
+                        .update_config(guild_id.get(), |config| {
+                            config.assigned_voices.remove(&user_id.get());
+                        })
+                        .await;
+                    if let Err(e) = cleared {
+                        error!(error = ?e, "Failed to save guild configs");
+                    }
+                }
+            }
+            format!("Switched to voice {}.", roll)
+        }
+        None => match config.assigned_voices.get(&user_id.get()) {
+            Some(AssignedVoice::Preset(preset)) => {
+                format!("This server has you speaking as {}.", preset)
+            }
+            Some(AssignedVoice::Roll(roll)) => {
+                format!("This server has you using voice {}.", roll)
+            }
+            None => match state.voice_manager.get_preset(user_id.get()).await {
+                Some(preset) => format!("You're speaking as {}.", preset),
+                None => format!(
+                    "You're using voice {}.",
+                    state.voice_manager.get_roll(user_id.get()).await
+                ),
+            },
+        },
+    };
+
+    let audio = speak_as(ctx, command, "This is what I sound like.").await;
+    (content, audio)
+}
+
+/// Outside a server only the user's own voice applies, which the default config leaves alone.
+async fn guild_config(state: &BotState, command: &CommandInteraction) -> GuildConfig {
+    match command.guild_id {
+        Some(guild_id) => state.guild_configs.get_config(guild_id.get()).await,
+        None => GuildConfig::default(),
+    }
+}
+
+async fn speak_as(ctx: &Context, command: &CommandInteraction, text: &str) -> Option<Vec<u8>> {
+    let state = BotState::get(&ctx.data).await;
+    let user_id = command.user.id;
+    let mut content = state
+        .pronunciations
+        .apply(&process_message(text, &GuildConfig::default()));
+    if content.is_empty() {
+        return None;
+    }
+    let config = guild_config(&state, command).await;
+    if let Some(preset) = state
+        .voice_manager
+        .get_guild_preset(user_id.get(), &config)
+        .await
+    {
+        content = format!("[:name {}] {}", preset, content);
+    }
+
+    let voice = state
+        .voice_manager
+        .get_guild_voice(user_id.get(), &config)
+        .await;
+    let tts_bytes = match synthesize(&content, &voice, Language::English).await {
+        Ok(tts_bytes) => tts_bytes,
+        Err(e) => {
+            error!(error = ?e, "Failed to generate TTS");
+            return None;
+        }
+    };
+    match audio::normalize_wav_volume(&tts_bytes) {
+        Ok(normalized_tts_bytes) => Some(normalized_tts_bytes),
+        Err(e) => {
+            error!(error = ?e, "Failed to normalize TTS volume");
+            None
+        }
+    }
+}
+
+pub async fn forceroll(ctx: &Context, command: &CommandInteraction) -> String {
+    let state = BotState::get(&ctx.data).await;
+    let options = command.data.options();
+    let user = options.iter().find_map(|option| match option.value {
+        ResolvedValue::User(user, _) => Some(user.id),
+        _ => None,
+    });
+    let roll = options.iter().find_map(|option| match option.value {
+        ResolvedValue::Integer(roll) => Some(roll.max(0) as u64),
+        _ => None,
+    });
+    let (user, roll) = match (user, roll) {
+        (Some(user), Some(roll)) => (user, roll),
+        _ => return "Pick a user and a voice number.".to_string(),
+    };
+
+    state.voice_manager.set_roll(user.get(), roll).await;
+    format!("<@{}> now uses voice {}", user, roll)
+}
@@ -0,0 +1,431 @@
+use std::{
+    env,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::{Arc, LazyLock, RwLock},
+};
+
+use dectalk_bot_core::{
+    dectalk::{Language, PAUL_VOICE},
+    engine::{SayEngine, TtsEngine},
+};
+use figment::{
+    providers::{Env, Format, Serialized, Toml},
+    Figment,
+};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use tokio::fs;
+use tracing::{info, warn};
+
+use crate::guild_config::Limits;
+
+static CONFIG: LazyLock<RwLock<Arc<Config>>> =
+    LazyLock::new(|| RwLock::new(Arc::new(Config::default())));
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub token: String,
+    /// Extra bot accounts that share the main bot's storage, so more than one voice channel in a
+    /// guild can be read at once.
+    pub helper_tokens: Vec<String>,
+    #[serde(deserialize_with = "deserialize_ids")]
+    pub owners: Vec<u64>,
+    /// Errors are posted here as well as logged.
+    pub ops_channel: Option<u64>,
+    pub data_dir: PathBuf,
+    /// Where the data files are kept inside `data_dir`.
+    pub storage: Storage,
+    pub engine: EngineConfig,
+    pub limits: LimitsConfig,
+    pub logging: LoggingConfig,
+    pub http: HttpConfig,
+    /// Reads an IRC channel into a voice channel when set, needs the `irc` feature.
+    pub irc: Option<IrcConfig>,
+    /// Reads a Matrix room into a voice channel when set, needs the `matrix` feature.
+    pub matrix: Option<MatrixConfig>,
+    /// Answers Telegram messages with voice notes when set, needs the `telegram` feature.
+    pub telegram: Option<TelegramConfig>,
+    /// Writes live captions to a file when set.
+    pub captions: Option<CaptionsConfig>,
+    /// Reads MQTT messages into voice channels when set, needs the `mqtt` feature.
+    pub mqtt: Option<MqttConfig>,
+    /// Transcribes what people say in calls when set, needs the `stt` feature.
+    pub stt: Option<SttConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EngineConfig {
+    pub say_path: PathBuf,
+    pub output_dir: PathBuf,
+    pub volume: f32,
+    pub words_per_minute: f64,
+    /// Synthesize a short sample whenever the bot joins a call, so the first message doesn't
+    /// wait on DECtalk starting cold.
+    pub warm_up: bool,
+    /// Saves the command line, input and output of every failed `say` run here when set.
+    pub debug_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LimitsConfig {
+    pub default: Limits,
+    pub max_text_attachment_size: u32,
+    pub max_text_attachment_duration: f64,
+    pub max_embed_length: usize,
+    pub max_sound_size: u32,
+    pub max_sound_duration: f64,
+    /// Seconds of synthesis each guild may use per minute, 0 for no limit.
+    pub guild_synthesis_seconds: f64,
+    /// Seconds between `/sing` requests in each guild.
+    pub song_cooldown: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoggingConfig {
+    /// An env-filter directive, `RUST_LOG` takes precedence when set.
+    pub level: String,
+    /// Also write logs here when set, rotating files and keeping the newest `max_files`.
+    pub directory: Option<PathBuf>,
+    pub rotation: LogRotation,
+    pub max_files: usize,
+    /// Exports spans to this OTLP gRPC collector, needs the `otel` feature.
+    pub otlp_endpoint: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HttpConfig {
+    /// Serves `/metrics`, `/healthz`, `/readyz`, `/transcript` and `/captions` here when set,
+    /// e.g. `127.0.0.1:9100`.
+    pub bind: Option<SocketAddr>,
+    /// Enables the `/admin` API for requests with `Authorization: Bearer <admin_token>`.
+    pub admin_token: Option<String>,
+    /// Enables `POST /speak` for requests with `Authorization: Bearer <speak_token>`.
+    pub speak_token: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IrcConfig {
+    pub server: String,
+    pub port: Option<u16>,
+    #[serde(default = "default_true")]
+    pub tls: bool,
+    pub nickname: String,
+    /// The IRC channel to read, e.g. `#dectalk`.
+    pub channel: String,
+    /// The guild to speak in, and the voice channel to join. Without a voice channel, messages
+    /// are only read while the bot is already in a call there.
+    pub guild: u64,
+    pub voice_channel: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptionsConfig {
+    pub file: PathBuf,
+    /// Only caption this guild, otherwise the file follows whichever guild spoke last.
+    pub guild: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MqttConfig {
+    pub host: String,
+    #[serde(default = "default_mqtt_port")]
+    pub port: u16,
+    #[serde(default = "default_mqtt_client_id")]
+    pub client_id: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub subscriptions: Vec<MqttSubscription>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MqttSubscription {
+    /// May use `+` and `#` wildcards.
+    pub topic: String,
+    pub guild: u64,
+    /// Join this voice channel to read, otherwise only read while the bot is in a call.
+    pub voice_channel: Option<u64>,
+    /// What to say, with `{payload}`, `{topic}` or JSON fields like `{attributes.name}`.
+    #[serde(default = "default_mqtt_template")]
+    pub template: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SttConfig {
+    /// A whisper.cpp server's inference endpoint, e.g. `http://127.0.0.1:8080/inference`.
+    pub endpoint: String,
+    /// Passed to whisper, which detects the language itself when unset.
+    pub language: Option<String>,
+    /// Seconds to wait for each transcription.
+    #[serde(default = "default_stt_timeout")]
+    pub timeout: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatrixConfig {
+    /// e.g. `https://matrix.org`.
+    pub homeserver: String,
+    pub user: String,
+    pub password: String,
+    /// The room to read, by ID or alias, e.g. `#dectalk:matrix.org`. Encrypted rooms can't be
+    /// read.
+    pub room: String,
+    /// The guild to speak in, and the voice channel to join, like `irc`.
+    pub guild: u64,
+    pub voice_channel: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TelegramConfig {
+    /// From @BotFather.
+    pub token: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Storage {
+    /// One JSON file per store.
+    #[default]
+    Json,
+    /// Every store as a row in `data.db`. Existing JSON files are imported on the next start.
+    Sqlite,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogRotation {
+    Hourly,
+    Daily,
+    Never,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            token: String::new(),
+            helper_tokens: Vec::new(),
+            owners: Vec::new(),
+            ops_channel: None,
+            data_dir: PathBuf::from("data"),
+            storage: Storage::default(),
+            engine: EngineConfig::default(),
+            limits: LimitsConfig::default(),
+            logging: LoggingConfig::default(),
+            http: HttpConfig::default(),
+            irc: None,
+            matrix: None,
+            telegram: None,
+            captions: None,
+            mqtt: None,
+            stt: None,
+        }
+    }
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        EngineConfig {
+            say_path: PathBuf::from("dectalk/say"),
+            output_dir: PathBuf::from("dectalk"),
+            volume: 0.25,
+            words_per_minute: 200.0,
+            warm_up: false,
+            debug_dir: None,
+        }
+    }
+}
+
+impl EngineConfig {
+    pub fn say_engine(&self) -> SayEngine {
+        SayEngine {
+            say_path: self.say_path.clone(),
+            output_dir: self.output_dir.clone(),
+        }
+    }
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        LoggingConfig {
+            level: "warn,dectalk=info".to_string(),
+            directory: None,
+            rotation: LogRotation::Daily,
+            max_files: 7,
+            otlp_endpoint: None,
+        }
+    }
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        LimitsConfig {
+            default: Limits::default(),
+            max_text_attachment_size: 8 * 1024,
+            max_text_attachment_duration: 60.0,
+            max_embed_length: 200,
+            max_sound_size: 1024 * 1024,
+            max_sound_duration: 10.0,
+            guild_synthesis_seconds: 30.0,
+            song_cooldown: 300,
+        }
+    }
+}
+
+impl Config {
+    /// Reads `config.toml` (or `DECTALK_CONFIG`), then lets the environment override it.
+    /// `DECTALK_ENGINE__VOLUME=0.5` sets `engine.volume`, and the older `DISCORD_TOKEN` and
+    /// `DISCORD_OWNER` (now a comma-separated list) variables still work.
+    pub fn load() -> anyhow::Result<Self> {
+        let path = env::var("DECTALK_CONFIG").unwrap_or_else(|_| "config.toml".to_string());
+        let config = Figment::from(Serialized::defaults(Config::default()))
+            .merge(Toml::file(path))
+            .merge(
+                Env::raw()
+                    .only(&["discord_token", "discord_owner"])
+                    .map(|key| match key.as_str().to_lowercase().as_str() {
+                        "discord_owner" => "owners".into(),
+                        _ => "token".into(),
+                    }),
+            )
+            .merge(Env::prefixed("DECTALK_").ignore(&["config"]).split("__"))
+            .extract()?;
+        Ok(config)
+    }
+
+    /// Checks everything the bot needs before connecting, so a bad deploy fails at startup
+    /// instead of on the first message. Returns one line per problem.
+    pub async fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.token.is_empty() {
+            problems.push("No Discord token, set `token` in config.toml or DISCORD_TOKEN".into());
+        }
+        if self
+            .helper_tokens
+            .iter()
+            .any(|token| token.is_empty() || *token == self.token)
+        {
+            problems.push("Each of `helper_tokens` must be a different, non-empty token".into());
+        }
+        if self.owners.is_empty() {
+            warn!("No operators configured, nobody can bypass limits");
+        }
+
+        if let Err(e) = check_writable(&self.data_dir).await {
+            problems.push(format!(
+                "Data directory {} isn't writable: {}",
+                self.data_dir.display(),
+                e
+            ));
+        }
+        if !self.engine.say_path.is_file() {
+            problems.push(format!(
+                "DECtalk isn't installed at {}, set `engine.say_path`",
+                self.engine.say_path.display()
+            ));
+        } else if let Err(e) = self
+            .engine
+            .say_engine()
+            .synthesize("ok", &PAUL_VOICE, Language::English)
+            .await
+        {
+            problems.push(format!(
+                "DECtalk at {} failed a test run: {}",
+                self.engine.say_path.display(),
+                e
+            ));
+        }
+        problems
+    }
+
+    pub fn data_path(&self, name: &str) -> PathBuf {
+        self.data_dir.join(name)
+    }
+
+    /// Operators skip every limit and can use admin commands in any server.
+    pub fn is_operator(&self, id: u64) -> bool {
+        self.owners.contains(&id)
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_mqtt_port() -> u16 {
+    1883
+}
+
+fn default_mqtt_client_id() -> String {
+    "dectalk".to_string()
+}
+
+fn default_mqtt_template() -> String {
+    "{payload}".to_string()
+}
+
+fn default_stt_timeout() -> u64 {
+    30
+}
+
+fn deserialize_ids<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u64>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Ids {
+        One(u64),
+        Many(Vec<u64>),
+        List(String),
+    }
+
+    match Ids::deserialize(deserializer)? {
+        Ids::One(id) => Ok(vec![id]),
+        Ids::Many(ids) => Ok(ids),
+        Ids::List(ids) => ids
+            .split(',')
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .map(|id| id.parse().map_err(serde::de::Error::custom))
+            .collect(),
+    }
+}
+
+pub async fn check_writable(dir: &Path) -> anyhow::Result<()> {
+    fs::create_dir_all(dir).await?;
+    let path = dir.join(".write_test");
+    fs::write(&path, b"").await?;
+    fs::remove_file(&path).await?;
+    Ok(())
+}
+
+pub fn init(config: Config) {
+    *CONFIG.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(config);
+}
+
+pub fn get() -> Arc<Config> {
+    CONFIG.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Re-reads the config file and environment. Anything read through `get` picks up the new
+/// values, but the tokens, data directory and storage only take effect after a restart.
+pub fn reload() -> anyhow::Result<()> {
+    info!("Reloading config...");
+    let mut config = Config::load()?;
+    let current = get();
+    if config.token != current.token
+        || config.helper_tokens != current.helper_tokens
+        || config.data_dir != current.data_dir
+        || config.storage != current.storage
+    {
+        warn!("Token, data directory and storage changes need a restart");
+        config.token = current.token.clone();
+        config.helper_tokens = current.helper_tokens.clone();
+        config.data_dir = current.data_dir.clone();
+        config.storage = current.storage;
+    }
+    init(config);
+    Ok(())
+}
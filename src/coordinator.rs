@@ -0,0 +1,87 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use serenity::all::{ChannelId, GuildId};
+use songbird::Songbird;
+use tokio::{sync::Mutex, time::Instant};
+
+// Long enough for the claiming account to join, after which its call is the claim
+const CLAIM_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Decides which bot account reads each voice channel when `helper_tokens` are set. The main
+/// bot is instance 0 and helpers follow in config order. Every account sees the same messages,
+/// so each asks here before joining or reading, and only one gets a yes per channel.
+pub struct Coordinator {
+    songbirds: Vec<Arc<Songbird>>,
+    claims: Mutex<HashMap<(GuildId, ChannelId), (usize, Instant)>>,
+}
+
+impl Coordinator {
+    pub fn new(songbirds: Vec<Arc<Songbird>>) -> Self {
+        Coordinator {
+            songbirds,
+            claims: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Each instance's voice manager, the main bot's first.
+    pub fn songbirds(&self) -> &[Arc<Songbird>] {
+        &self.songbirds
+    }
+
+    /// Whether `instance` should read into `channel_id`. The account already in the channel
+    /// keeps it, otherwise the first free account to ask gets it. An account is free when it
+    /// isn't in, or on its way to, another channel in the guild.
+    pub async fn claim(&self, guild_id: GuildId, channel_id: ChannelId, instance: usize) -> bool {
+        if self.songbirds.len() <= 1 {
+            return true;
+        }
+
+        let mut claims = self.claims.lock().await;
+        claims.retain(|_, (_, claimed_at)| claimed_at.elapsed() < CLAIM_TIMEOUT);
+        if let Some(owner) = self.find_owner(&claims, guild_id, channel_id).await {
+            return owner == instance;
+        }
+        let is_busy = current_channel(&self.songbirds[instance], guild_id)
+            .await
+            .is_some()
+            || claims
+                .iter()
+                .any(|((guild, _), (owner, _))| *guild == guild_id && *owner == instance);
+        if is_busy {
+            return false;
+        }
+        claims.insert((guild_id, channel_id), (instance, Instant::now()));
+        true
+    }
+
+    /// The instance in `channel_id`, or about to join it.
+    pub async fn owner(&self, guild_id: GuildId, channel_id: ChannelId) -> Option<usize> {
+        let mut claims = self.claims.lock().await;
+        claims.retain(|_, (_, claimed_at)| claimed_at.elapsed() < CLAIM_TIMEOUT);
+        self.find_owner(&claims, guild_id, channel_id).await
+    }
+
+    async fn find_owner(
+        &self,
+        claims: &HashMap<(GuildId, ChannelId), (usize, Instant)>,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+    ) -> Option<usize> {
+        for (index, songbird) in self.songbirds.iter().enumerate() {
+            if current_channel(songbird, guild_id).await == Some(channel_id.into()) {
+                return Some(index);
+            }
+        }
+        claims.get(&(guild_id, channel_id)).map(|(owner, _)| *owner)
+    }
+}
+
+async fn current_channel(
+    songbird: &Songbird,
+    guild_id: GuildId,
+) -> Option<songbird::id::ChannelId> {
+    match songbird.get(guild_id) {
+        Some(handler_lock) => handler_lock.lock().await.current_channel(),
+        None => None,
+    }
+}
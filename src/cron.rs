@@ -0,0 +1,129 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Datelike, DurationRound, TimeDelta, Timelike, Utc};
+use tokio::time;
+
+const MONTHS: [&str; 12] = [
+    "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+];
+const WEEKDAYS: [&str; 7] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+
+/// A five field cron expression (minute, hour, day of month, month, day of week) in UTC.
+/// Fields take `*`, numbers, ranges, lists and `/` steps, months and weekdays also take their
+/// three letter names.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Schedule {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl Schedule {
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let fields = expression.split_whitespace().collect::<Vec<_>>();
+        let [minutes, hours, days, months, weekdays] = fields[..] else {
+            return Err(format!(
+                "Expected 5 fields (minute hour day month weekday), found {}",
+                fields.len()
+            ));
+        };
+
+        let mut weekday_mask = parse_field(weekdays, 0, 7, &WEEKDAYS, 0)?;
+        // 7 is Sunday too
+        if weekday_mask & (1 << 7) != 0 {
+            weekday_mask |= 1;
+        }
+        Ok(Schedule {
+            minutes: parse_field(minutes, 0, 59, &[], 0)?,
+            hours: parse_field(hours, 0, 23, &[], 0)?,
+            days: parse_field(days, 1, 31, &[], 1)?,
+            months: parse_field(months, 1, 12, &MONTHS, 1)?,
+            weekdays: weekday_mask,
+            any_day: days == "*",
+            any_weekday: weekdays == "*",
+        })
+    }
+
+    /// Whether the schedule fires during `time`'s minute.
+    pub fn matches(&self, time: &DateTime<Utc>) -> bool {
+        let day = self.days & (1 << time.day()) != 0;
+        let weekday = self.weekdays & (1 << time.weekday().num_days_from_sunday()) != 0;
+        // Like cron, a restricted day of month and day of week match if either does
+        let day_matches = match (self.any_day, self.any_weekday) {
+            (true, true) => true,
+            (false, true) => day,
+            (true, false) => weekday,
+            (false, false) => day || weekday,
+        };
+        self.minutes & (1 << time.minute()) != 0
+            && self.hours & (1 << time.hour()) != 0
+            && self.months & (1 << time.month()) != 0
+            && day_matches
+    }
+}
+
+/// Sleeps until the next minute starts and returns it, for checking schedules once a minute.
+pub async fn wait_for_next_minute() -> DateTime<Utc> {
+    let now = Utc::now();
+    let next_minute =
+        now.duration_trunc(TimeDelta::minutes(1)).unwrap_or(now) + TimeDelta::minutes(1);
+    time::sleep(
+        (next_minute - now)
+            .to_std()
+            .unwrap_or(Duration::from_secs(60)),
+    )
+    .await;
+    next_minute
+}
+
+fn parse_field(
+    field: &str,
+    min: u32,
+    max: u32,
+    names: &[&str],
+    first_name: u32,
+) -> Result<u64, String> {
+    let parse_value = |value: &str| -> Result<u32, String> {
+        let upper = value.to_uppercase();
+        if let Some(index) = names.iter().position(|name| *name == upper) {
+            return Ok(index as u32 + first_name);
+        }
+        match value.parse::<u32>() {
+            Ok(number) if (min..=max).contains(&number) => Ok(number),
+            _ => Err(format!("`{}` should be between {} and {}", value, min, max)),
+        }
+    };
+
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => match step.parse::<u32>() {
+                Ok(step) if step > 0 => (range, step),
+                _ => return Err(format!("`{}` has an invalid step", part)),
+            },
+            None => (part, 1),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            range => match range.split_once('-') {
+                Some((start, end)) => (parse_value(start)?, parse_value(end)?),
+                None if step > 1 => (parse_value(range)?, max),
+                None => {
+                    let value = parse_value(range)?;
+                    (value, value)
+                }
+            },
+        };
+        if start > end {
+            return Err(format!("`{}` runs backwards", part));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
@@ -0,0 +1,72 @@
+use std::{
+    path::Path,
+    sync::{Mutex, OnceLock, PoisonError},
+};
+
+use rusqlite::{Connection, OptionalExtension};
+use tokio::task;
+
+use crate::{config, error::BotError};
+
+pub const DATABASE_FILE: &str = "data.db";
+
+static CONNECTION: OnceLock<Mutex<Connection>> = OnceLock::new();
+
+/// Opens `data.db` for `storage = "sqlite"`. Each store is a row holding what would otherwise
+/// be its JSON file, so the stores don't need to know where they're kept.
+pub fn open(data_dir: &Path) -> rusqlite::Result<Connection> {
+    let connection = Connection::open(data_dir.join(DATABASE_FILE))?;
+    connection.execute_batch(
+        "CREATE TABLE IF NOT EXISTS documents (name TEXT PRIMARY KEY, json TEXT NOT NULL)",
+    )?;
+    Ok(connection)
+}
+
+/// Shares a connection the migrations already opened, rather than opening another.
+pub fn install(connection: Connection) {
+    let _ = CONNECTION.set(Mutex::new(connection));
+}
+
+pub fn read(connection: &Connection, name: &str) -> rusqlite::Result<Option<String>> {
+    connection
+        .query_row(
+            "SELECT json FROM documents WHERE name = ?1",
+            [name],
+            |row| row.get(0),
+        )
+        .optional()
+}
+
+pub fn write(connection: &Connection, name: &str, json: &str) -> rusqlite::Result<()> {
+    connection.execute(
+        "INSERT INTO documents (name, json) VALUES (?1, ?2)
+         ON CONFLICT(name) DO UPDATE SET json = excluded.json",
+        [name, json],
+    )?;
+    Ok(())
+}
+
+pub fn remove(connection: &Connection, name: &str) -> rusqlite::Result<()> {
+    connection.execute("DELETE FROM documents WHERE name = ?1", [name])?;
+    Ok(())
+}
+
+/// Runs `f` on the shared connection, off the async runtime since SQLite blocks.
+pub async fn with_connection<T: Send + 'static>(
+    f: impl FnOnce(&Connection) -> rusqlite::Result<T> + Send + 'static,
+) -> Result<T, BotError> {
+    task::spawn_blocking(move || {
+        let connection = match CONNECTION.get() {
+            Some(connection) => connection,
+            None => {
+                let connection = open(&config::get().data_dir)?;
+                CONNECTION.get_or_init(|| Mutex::new(connection))
+            }
+        };
+        let connection = connection.lock().unwrap_or_else(PoisonError::into_inner);
+        f(&connection)
+    })
+    .await
+    .map_err(|e| BotError::Database(e.into()))?
+    .map_err(|e| BotError::Database(e.into()))
+}
@@ -0,0 +1,79 @@
+use std::{collections::HashMap, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use tracing::debug;
+
+use crate::{
+    config,
+    error::{self, BotError},
+};
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct DuelRecord {
+    pub wins: u64,
+    pub losses: u64,
+}
+
+/// Each guild's `/voiceduel` wins and losses, saved after every duel.
+pub struct DuelRecords {
+    pub records: Arc<Mutex<HashMap<u64, HashMap<u64, DuelRecord>>>>,
+}
+
+impl DuelRecords {
+    pub fn new() -> Self {
+        DuelRecords {
+            records: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Returns the winner's and loser's updated records.
+    pub async fn record_result(
+        &self,
+        guild_id: u64,
+        winner: u64,
+        loser: u64,
+    ) -> Result<(DuelRecord, DuelRecord), BotError> {
+        let result = {
+            let mut records = self.records.lock().await;
+            let guild_records = records.entry(guild_id).or_default();
+            guild_records.entry(winner).or_default().wins += 1;
+            guild_records.entry(loser).or_default().losses += 1;
+            (guild_records[&winner], guild_records[&loser])
+        };
+        self.save_duels().await?;
+        Ok(result)
+    }
+
+    /// The guild's duelists with the most wins, then the fewest losses.
+    pub async fn top(&self, guild_id: u64, count: usize) -> Vec<(u64, DuelRecord)> {
+        let mut records = self
+            .records
+            .lock()
+            .await
+            .get(&guild_id)
+            .map(|records| {
+                records
+                    .iter()
+                    .map(|(user_id, record)| (*user_id, *record))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        records.sort_by(|(_, a), (_, b)| b.wins.cmp(&a.wins).then(a.losses.cmp(&b.losses)));
+        records.truncate(count);
+        records
+    }
+
+    pub async fn load_duels(&self) -> Result<(), BotError> {
+        debug!("Loading duel records...");
+        let records = error::read_json(config::get().data_path("duels.json")).await?;
+        *self.records.lock().await = records;
+        Ok(())
+    }
+
+    pub async fn save_duels(&self) -> Result<(), BotError> {
+        debug!("Saving duel records...");
+        let records = self.records.lock().await;
+        error::write_json(config::get().data_path("duels.json"), &*records).await
+    }
+}
@@ -0,0 +1,72 @@
+use std::{collections::HashMap, hash::Hash, time::Duration};
+
+use tokio::time::Instant;
+
+// Refreshed by every repeat, so a steady stream of copypasta stays suppressed
+const DUPLICATE_WINDOW: Duration = Duration::from_secs(30);
+
+struct RecentMessage {
+    text: String,
+    count: u32,
+    at: Instant,
+}
+
+/// Remembers each user's latest message to spot copypasta and repeated spam.
+pub struct DuplicateTracker<K> {
+    recent: HashMap<K, RecentMessage>,
+}
+
+impl<K: Eq + Hash> DuplicateTracker<K> {
+    pub fn new() -> Self {
+        DuplicateTracker {
+            recent: HashMap::new(),
+        }
+    }
+
+    /// Returns how many times in a row `key` has sent this message, 1 for something new.
+    pub fn record(&mut self, key: K, text: &str) -> u32 {
+        let text = normalize(text);
+        let now = Instant::now();
+        match self.recent.get_mut(&key) {
+            Some(recent)
+                if recent.text == text && now.duration_since(recent.at) < DUPLICATE_WINDOW =>
+            {
+                recent.count += 1;
+                recent.at = now;
+                recent.count
+            }
+            _ => {
+                self.recent.insert(
+                    key,
+                    RecentMessage {
+                        text,
+                        count: 1,
+                        at: now,
+                    },
+                );
+                1
+            }
+        }
+    }
+
+    pub fn prune(&mut self) {
+        self.recent
+            .retain(|_, recent| recent.at.elapsed() < DUPLICATE_WINDOW);
+    }
+}
+
+/// Ignores case, punctuation, spacing and stretched letters, so "LOL!!" repeats "lol".
+fn normalize(text: &str) -> String {
+    let mut normalized = String::with_capacity(text.len());
+    for c in text.chars().filter(|c| c.is_alphanumeric()) {
+        let c = c.to_lowercase().next().unwrap_or(c);
+        if !normalized.ends_with(c) {
+            normalized.push(c);
+        }
+    }
+    // Emoji-only messages have nothing left, compare them as they are
+    if normalized.is_empty() {
+        return text.trim().to_string();
+    }
+    normalized
+}
@@ -0,0 +1,154 @@
+use std::{
+    error::Error,
+    io,
+    path::{Path, PathBuf},
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+use songbird::error::JoinError;
+use thiserror::Error;
+use tokio::fs;
+
+use crate::{
+    config::{self, Storage},
+    database,
+};
+
+/// Everything that can go wrong while the bot is running. Startup and the CLI, which can only
+/// give up, use anyhow instead.
+#[derive(Debug, Error)]
+pub enum BotError {
+    #[error("shutting down")]
+    ShuttingDown,
+    #[error("stopped by an operator")]
+    Panicked,
+    #[error("{0} is missing from the shared state")]
+    MissingState(&'static str),
+    #[error("reading is disabled in this server")]
+    Disabled,
+    #[error("message is too long")]
+    TooLong,
+    #[error("message was filtered")]
+    Filtered,
+    #[error("nothing to read")]
+    Empty,
+    #[error("server is over its synthesis budget")]
+    Throttled,
+    #[error("not in a voice channel")]
+    NotConnected,
+    #[error("reading isn't allowed in that voice channel")]
+    ChannelBlocked,
+    #[error("busy in another voice channel")]
+    Busy,
+    #[error("failed to join the voice channel")]
+    Join(#[from] JoinError),
+    #[error("DECtalk failed")]
+    Synthesis(#[source] Box<dyn Error + Send + Sync>),
+    #[error("couldn't process the audio")]
+    Audio(#[source] Box<dyn Error + Send + Sync>),
+    #[error("failed to {action} {}", path.display())]
+    Io {
+        action: &'static str,
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("couldn't use the database")]
+    Database(#[source] Box<dyn Error + Send + Sync>),
+    #[error("{} isn't valid", path.display())]
+    Parse {
+        path: PathBuf,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    Discord(#[from] serenity::Error),
+}
+
+impl BotError {
+    /// An ordinary refusal rather than something broken. These are logged at debug, everything
+    /// else is an error and ends up in the ops channel.
+    pub fn is_expected(&self) -> bool {
+        matches!(
+            self,
+            BotError::ShuttingDown
+                | BotError::Panicked
+                | BotError::Disabled
+                | BotError::TooLong
+                | BotError::Filtered
+                | BotError::Empty
+                | BotError::Throttled
+                | BotError::NotConnected
+                | BotError::ChannelBlocked
+                | BotError::Busy
+        )
+    }
+
+    /// A data file that hasn't been written yet, which is fine on a fresh install.
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, BotError::Io { source, .. } if source.kind() == io::ErrorKind::NotFound)
+    }
+
+    pub fn io(action: &'static str, path: impl Into<PathBuf>) -> impl FnOnce(io::Error) -> Self {
+        let path = path.into();
+        move |source| BotError::Io {
+            action,
+            path,
+            source,
+        }
+    }
+}
+
+/// Reads a data file, or its row in the database with `storage = "sqlite"`.
+pub async fn read_json<T: DeserializeOwned>(path: PathBuf) -> Result<T, BotError> {
+    let json = match config::get().storage {
+        Storage::Json => fs::read_to_string(&path)
+            .await
+            .map_err(BotError::io("read", &path))?,
+        Storage::Sqlite => {
+            let name = document_name(&path);
+            match database::with_connection(move |connection| database::read(connection, &name))
+                .await?
+            {
+                Some(json) => json,
+                None => return Err(BotError::io("read", &path)(io::ErrorKind::NotFound.into())),
+            }
+        }
+    };
+    serde_json::from_str(&json).map_err(|source| BotError::Parse { path, source })
+}
+
+pub async fn write_json<T: Serialize + ?Sized>(path: PathBuf, value: &T) -> Result<(), BotError> {
+    let json = serde_json::to_string(value)?;
+    match config::get().storage {
+        Storage::Json => fs::write(&path, json)
+            .await
+            .map_err(BotError::io("write", path)),
+        Storage::Sqlite => {
+            let name = document_name(&path);
+            database::with_connection(move |connection| database::write(connection, &name, &json))
+                .await
+        }
+    }
+}
+
+pub async fn remove_json(path: PathBuf) -> Result<(), BotError> {
+    match config::get().storage {
+        Storage::Json => fs::remove_file(&path)
+            .await
+            .map_err(BotError::io("remove", path)),
+        Storage::Sqlite => {
+            let name = document_name(&path);
+            database::with_connection(move |connection| database::remove(connection, &name)).await
+        }
+    }
+}
+
+/// Rows are named after the file they replace.
+fn document_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
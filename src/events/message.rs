@@ -0,0 +1,526 @@
+use std::{collections::HashSet, sync::Arc, time::Duration};
+
+use dectalk_bot_core::{
+    audio::get_wav_duration,
+    dectalk::PAUL_VOICE,
+    preprocess::{get_requested_roll, take_voice_tag, truncate},
+    slang::expand_macros,
+};
+use serenity::{
+    all::{ChannelId, CreateAllowedMentions, CreateMessage, GuildId, MessageId, Timestamp, UserId},
+    client::Context,
+    http::Http,
+    model::channel::{Attachment, Message},
+};
+use songbird::{input::Input, tracks::Track};
+use tracing::{debug, error, info_span, instrument, Instrument};
+
+use super::voice_state::{get_binding, sync_guild_users};
+use crate::{
+    audio::{record_clip, THROUGHPUT_PERIOD},
+    config, idle, lottery, metrics,
+    pipeline::{self, Prepared, RenderOptions, SystemClock},
+    preprocess::{
+        describe_attachments, describe_embeds, describe_poll, describe_stickers, get_author_name,
+    },
+    reconnect, shutdown,
+    state::BotState,
+    stats, transcript,
+};
+
+#[instrument(skip_all, fields(
+    guild_id = ?new_message.guild_id,
+    user_id = %new_message.author.id,
+    message_id = %new_message.id,
+))]
+pub async fn handle(ctx: Context, new_message: Message) {
+    let author_id = new_message.author.id;
+    let guild_id = match new_message.guild_id {
+        Some(guild_id) => guild_id,
+        None => {
+            debug!("Ignoring message outside a guild");
+            return;
+        }
+    };
+    metrics::MESSAGES_SEEN.inc();
+    if shutdown::is_shutting_down() || shutdown::is_panicked() {
+        return;
+    }
+
+    let state = BotState::get(&ctx.data).await;
+
+    if state.blacklist.contains(author_id.get()).await {
+        return;
+    }
+
+    let is_operator = config::get().is_operator(author_id.get());
+
+    let config = state.guild_configs.get_config(guild_id.get()).await;
+    if !config.enabled || config.blacklist.contains(&author_id.get()) {
+        return;
+    }
+
+    let message_channel_id = if config.read_threads {
+        resolve_thread_parent(&ctx, guild_id, new_message.channel_id)
+    } else {
+        new_message.channel_id
+    };
+    if !config
+        .text_channels
+        .permits_thread(new_message.channel_id.get(), message_channel_id.get())
+    {
+        return;
+    }
+
+    if !config.read_nsfw && is_nsfw_channel(&ctx, guild_id, new_message.channel_id) {
+        return;
+    }
+
+    if config.has_ignored_prefix(&new_message.content) {
+        return;
+    }
+
+    if (config.ignore_bots && new_message.author.bot)
+        || (config.ignore_webhooks && new_message.webhook_id.is_some())
+    {
+        return;
+    }
+
+    let application_id = new_message
+        .application_id
+        .map(|id| id.get())
+        .unwrap_or(author_id.get());
+    if config.ignored_applications.contains(&application_id) {
+        return;
+    }
+
+    let requested_roll = get_requested_roll(&new_message.content);
+    if let Some(roll) = requested_roll {
+        if state.voice_manager.reroll(author_id.get(), roll).await {
+            stats::record(&ctx.data, guild_id.get(), "roll").await;
+        }
+    }
+
+    let roles = match &new_message.member {
+        Some(member) => member.roles.iter().map(|role| role.get()).collect(),
+        None => Vec::new(),
+    };
+    let limits = config.limits_for(&roles);
+
+    // Macros expand first so the limits apply to what actually gets read
+    let (voice_tag, message_text) = take_voice_tag(&new_message.content);
+    let user_macros = state.macros.get(author_id.get()).await;
+    let message_text = expand_macros(message_text, &[&user_macros, &config.macros]);
+
+    if !is_operator && message_text.len() > limits.max_message_length {
+        return;
+    }
+
+    if config.is_filtered(&message_text) {
+        return;
+    }
+
+    let user_channel_id = match new_message.guild(&ctx.cache) {
+        Some(guild) => guild
+            .voice_states
+            .get(&author_id)
+            .and_then(|voice_state| voice_state.channel_id),
+        None => {
+            error!("Failed to get guild");
+            return;
+        }
+    };
+    let binding = get_binding(&ctx, guild_id).await;
+    let channel_id = match pipeline::route(&config, binding, message_channel_id, user_channel_id) {
+        Some(channel_id) => channel_id,
+        None => return,
+    };
+    if !state
+        .coordinator
+        .claim(guild_id, channel_id, state.instance)
+        .await
+    {
+        debug!("Another bot account reads {}", channel_id);
+        return;
+    }
+
+    let mut repeats = 1;
+    if config.suppress_duplicates {
+        repeats = state
+            .recent_messages
+            .lock()
+            .await
+            .record((guild_id, author_id), &new_message.content);
+        if repeats > 1 && !config.count_duplicates {
+            debug!("Skipping duplicate message from {}", author_id);
+            return;
+        }
+    }
+
+    let preset = state
+        .voice_manager
+        .get_guild_preset(author_id.get(), &config)
+        .await;
+    let preprocess = info_span!("preprocess").entered();
+    let author_name = get_author_name(&new_message);
+    let mut descriptions = Vec::new();
+    if new_message.content.split_whitespace().count() <= 3 {
+        descriptions.extend(describe_attachments(&author_name, &new_message.attachments));
+    }
+    if config.read_stickers {
+        descriptions.extend(describe_stickers(&author_name, &new_message.sticker_items));
+    }
+    if config.read_embeds {
+        descriptions.extend(describe_embeds(&new_message.embeds));
+    }
+    if let Some(poll) = &new_message.poll {
+        descriptions.extend(describe_poll(&author_name, poll));
+    }
+
+    let mut text = message_text;
+    for description in descriptions {
+        text = if text.trim().is_empty() {
+            description
+        } else {
+            format!("{}. {}", text, description)
+        };
+    }
+
+    // Staff-assigned presets apply whenever the message doesn't pick its own voice
+    let (voice_tag, allow_voice_tag) = match (voice_tag, preset) {
+        (None, Some(preset)) => (Some(preset), true),
+        (voice_tag, _) => (voice_tag, is_operator || config.allows_voice_tags(&roles)),
+    };
+    let prepared = pipeline::prepare(
+        &text,
+        voice_tag,
+        repeats,
+        &config,
+        &state.pronunciations,
+        allow_voice_tag,
+    );
+    let Prepared {
+        content,
+        caption,
+        language,
+    } = match prepared {
+        Some(prepared) => prepared,
+        None => {
+            debug!("Skipping message from {}", author_id);
+            return;
+        }
+    };
+
+    drop(preprocess);
+
+    let has_text_attachments = new_message.attachments.iter().any(is_text_attachment);
+    if content.is_empty() && !has_text_attachments {
+        return;
+    }
+
+    if is_author_silenced(&ctx, guild_id, author_id) {
+        debug!("Skipping message from silenced user {}", author_id);
+        return;
+    }
+
+    debug!("Found valid message from {}", author_id);
+
+    if !is_operator && config.rate_limit > 0 {
+        let allowed = state.user_rate_limits.lock().await.try_take(
+            (guild_id, author_id),
+            1.0,
+            config.rate_limit as f64,
+            Duration::from_secs(config.rate_limit_period),
+        );
+        if !allowed {
+            debug!("Rate limited {}", author_id);
+            if let Err(e) = new_message.react(&ctx.http, '⏱').await {
+                debug!(error = ?e, "Failed to react to rate limited message");
+            }
+            return;
+        }
+    }
+
+    let synthesis_budget = config::get().limits.guild_synthesis_seconds;
+    if !is_operator
+        && synthesis_budget > 0.0
+        && !state.guild_throughput.lock().await.has_tokens(
+            guild_id,
+            synthesis_budget,
+            THROUGHPUT_PERIOD,
+        )
+    {
+        debug!("Guild is over its synthesis budget");
+        metrics::MESSAGES_THROTTLED.inc();
+        if let Err(e) = new_message.react(&ctx.http, '🐢').await {
+            debug!(error = ?e, "Failed to react to throttled message");
+        }
+        return;
+    }
+
+    if config.speak_delay > 0.0 && !is_operator {
+        tokio::time::sleep(Duration::from_secs_f64(config.speak_delay)).await;
+    }
+    if is_moderated(&ctx, new_message.id).await {
+        debug!("Skipping moderated message");
+        return;
+    }
+
+    let attachment_texts = if has_text_attachments {
+        read_text_attachments(&new_message.attachments).await
+    } else {
+        Vec::new()
+    };
+
+    let manager = match songbird::get(&ctx).await {
+        Some(manager) => manager,
+        None => {
+            error!("Failed to get songbird manager");
+            return;
+        }
+    };
+
+    let handler_lock = reconnect::get_or_insert_call(&manager, guild_id).await;
+    let mut handler = handler_lock.lock().await;
+
+    let was_connected = handler.current_channel() == Some(channel_id.into());
+    if let Err(e) = handler.join(channel_id).await {
+        error!(error = ?e, "Failed to join channel");
+        return;
+    }
+
+    if !was_connected {
+        sync_guild_users(
+            &ctx.cache,
+            &ctx.data,
+            guild_id,
+            Some((channel_id, &mut *handler)),
+        )
+        .await;
+    }
+
+    let voice = lottery::voice_in_guild(
+        &config,
+        author_id.get(),
+        state
+            .voice_manager
+            .get_guild_voice(author_id.get(), &config)
+            .await,
+    );
+    let voice = if is_operator { &PAUL_VOICE } else { &voice };
+
+    let attachments = attachment_texts
+        .iter()
+        .filter_map(|text| pipeline::prepare_attachment(text, &config, &state.pronunciations))
+        .collect::<Vec<_>>();
+    let options = RenderOptions {
+        voice,
+        language,
+        limits: (!is_operator).then_some(limits),
+        max_attachment_duration: config::get().limits.max_text_attachment_duration,
+        words_per_minute: config::get().engine.words_per_minute,
+        phonemes: config.phoneme_captions,
+    };
+    let rendered = match pipeline::render(
+        &*state.engine,
+        &SystemClock,
+        &content,
+        &attachments,
+        &options,
+    )
+    .await
+    {
+        Ok(rendered) => rendered,
+        Err(e) => {
+            error!(error = ?e, "Failed to generate TTS");
+            return;
+        }
+    };
+
+    if synthesis_budget > 0.0 {
+        state.guild_throughput.lock().await.charge(
+            guild_id,
+            rendered.synthesis_seconds,
+            synthesis_budget,
+            THROUGHPUT_PERIOD,
+        );
+    }
+
+    let normalized_tts_bytes = match rendered.wav {
+        Some(wav) => wav,
+        None => return,
+    };
+
+    if let Some(phonemes) = rendered.phonemes {
+        tokio::spawn(reply_with_phonemes(
+            ctx.http.clone(),
+            new_message.channel_id,
+            new_message.id,
+            phonemes,
+        ));
+    }
+
+    let mut guild_users = state.guild_users.lock().await;
+    guild_users
+        .entry(guild_id)
+        .or_insert_with(HashSet::new)
+        .insert(author_id);
+
+    idle::mark_played(&ctx.data, guild_id).await;
+
+    state.serving.lock().await.insert(guild_id, author_id);
+
+    // Synthesis takes long enough for moderation to catch up
+    if is_moderated(&ctx, new_message.id).await {
+        debug!("Skipping moderated message");
+        return;
+    }
+
+    state.read_history.lock().await.record(
+        guild_id,
+        author_id,
+        new_message.channel_id,
+        new_message.id,
+        new_message.timestamp,
+        caption.clone(),
+    );
+    record_clip(
+        &ctx.data,
+        guild_id,
+        channel_id,
+        Some(new_message.id),
+        &author_name,
+        &caption,
+        &normalized_tts_bytes,
+    )
+    .await;
+
+    let spoken_seconds = get_wav_duration(&normalized_tts_bytes).await.unwrap_or(0.0);
+    let track = handler
+        .enqueue(Track::from(Input::from(normalized_tts_bytes)).volume(config::get().engine.volume))
+        .instrument(info_span!("play"))
+        .await;
+    transcript::follow_track(
+        &track,
+        transcript::TranscriptEvent::Speaking {
+            guild_id: guild_id.get(),
+            user_id: author_id.get(),
+            user: author_name,
+            text: caption,
+        },
+    );
+    metrics::MESSAGES_SPOKEN.inc();
+    stats::record(&ctx.data, guild_id.get(), "message").await;
+    state
+        .leaderboard
+        .record(guild_id.get(), author_id.get(), spoken_seconds)
+        .await;
+
+    // A fresh track starts playing as soon as it reaches the front of the queue
+    if is_bot_muted(&ctx, guild_id) {
+        if let Err(e) = handler.queue().pause() {
+            error!(error = ?e, "Failed to pause queue");
+        }
+    }
+}
+
+pub async fn mark_moderated(ctx: &Context, message_id: MessageId) {
+    let state = BotState::get(&ctx.data).await;
+    state.moderated.lock().await.insert(message_id);
+    state.clips.lock().await.remove_message(message_id);
+}
+
+async fn is_moderated(ctx: &Context, message_id: MessageId) -> bool {
+    let state = BotState::get(&ctx.data).await;
+    let is_moderated = state.moderated.lock().await.contains(message_id);
+    is_moderated
+}
+
+fn resolve_thread_parent(ctx: &Context, guild_id: GuildId, channel_id: ChannelId) -> ChannelId {
+    ctx.cache
+        .guild(guild_id)
+        .and_then(|guild| {
+            guild
+                .threads
+                .iter()
+                .find(|thread| thread.id == channel_id)
+                .and_then(|thread| thread.parent_id)
+        })
+        .unwrap_or(channel_id)
+}
+
+fn is_author_silenced(ctx: &Context, guild_id: GuildId, author_id: UserId) -> bool {
+    let guild = match ctx.cache.guild(guild_id) {
+        Some(guild) => guild,
+        None => return false,
+    };
+
+    let voice_state = guild.voice_states.get(&author_id);
+    let member = guild
+        .members
+        .get(&author_id)
+        .or_else(|| voice_state.and_then(|voice_state| voice_state.member.as_ref()));
+    let is_timed_out = member
+        .and_then(|member| member.communication_disabled_until)
+        .is_some_and(|until| until.unix_timestamp() > Timestamp::now().unix_timestamp());
+    let is_muted = voice_state.is_some_and(|voice_state| voice_state.mute);
+    is_timed_out || is_muted
+}
+
+fn is_bot_muted(ctx: &Context, guild_id: GuildId) -> bool {
+    let bot_id = ctx.cache.current_user().id;
+    ctx.cache
+        .guild(guild_id)
+        .and_then(|guild| {
+            guild
+                .voice_states
+                .get(&bot_id)
+                .map(|voice_state| voice_state.mute || voice_state.deaf)
+        })
+        .unwrap_or(false)
+}
+
+fn is_nsfw_channel(ctx: &Context, guild_id: GuildId, channel_id: ChannelId) -> bool {
+    // Threads inherit the age restriction of the channel they were created in
+    let channel_id = resolve_thread_parent(ctx, guild_id, channel_id);
+    ctx.cache
+        .guild(guild_id)
+        .and_then(|guild| guild.channels.get(&channel_id).map(|channel| channel.nsfw))
+        .unwrap_or(false)
+}
+
+async fn reply_with_phonemes(
+    http: Arc<Http>,
+    channel_id: ChannelId,
+    message_id: MessageId,
+    phonemes: String,
+) {
+    debug!("Spoke {}", phonemes);
+
+    let message = CreateMessage::new()
+        .content(format!("```\n{}\n```", truncate(&phonemes, 1900)))
+        .reference_message((channel_id, message_id))
+        .allowed_mentions(CreateAllowedMentions::new());
+    if let Err(e) = channel_id.send_message(&http, message).await {
+        debug!(error = ?e, "Failed to post phonemes");
+    }
+}
+
+fn is_text_attachment(attachment: &Attachment) -> bool {
+    let is_text = match attachment.content_type.as_deref() {
+        Some(content_type) => content_type.starts_with("text/plain"),
+        None => attachment.filename.to_lowercase().ends_with(".txt"),
+    };
+    is_text && attachment.size <= config::get().limits.max_text_attachment_size
+}
+
+async fn read_text_attachments(attachments: &[Attachment]) -> Vec<String> {
+    let mut texts = Vec::new();
+    for attachment in attachments.iter().filter(|a| is_text_attachment(a)) {
+        match attachment.download().await {
+            Ok(bytes) => texts.push(String::from_utf8_lossy(&bytes).to_string()),
+            Err(e) => error!(error = ?e, "Failed to download attachment"),
+        }
+    }
+    texts
+}
@@ -0,0 +1,159 @@
+use serenity::{
+    all::{
+        ActionExecution, ChannelId, Command, ConnectionStage, Guild, GuildChannel, GuildId,
+        Interaction, MessageId, ShardStageUpdateEvent, UnavailableGuild, VoiceState,
+    },
+    async_trait,
+    client::{Context, EventHandler},
+    model::{channel::Message, gateway::Ready},
+};
+use tracing::{debug, error, info};
+
+use crate::{commands, guild_config::GuildConfig, http, sessions, state::BotState, systemd};
+
+use message::mark_moderated;
+use voice_state::{get_binding, leave_voice};
+
+mod message;
+mod voice_state;
+
+pub use voice_state::{leave_guild, sync_guild_users};
+
+pub struct Handler;
+
+#[async_trait]
+impl EventHandler for Handler {
+    async fn ready(&self, ctx: Context, ready: Ready) {
+        info!("{} is connected!", ready.user.name);
+        // Helper accounts only read, the main bot owns the commands and the service status
+        if BotState::get(&ctx.data).await.instance != 0 {
+            return;
+        }
+        systemd::notify_ready();
+
+        if let Err(e) = Command::set_global_commands(&ctx.http, commands::register()).await {
+            error!(error = ?e, "Failed to register commands");
+        }
+    }
+
+    async fn auto_moderation_action_execution(&self, ctx: Context, execution: ActionExecution) {
+        if let Some(message_id) = execution.message_id {
+            debug!("AutoMod acted on {}", message_id);
+            mark_moderated(&ctx, message_id).await;
+        }
+    }
+
+    async fn message_delete(
+        &self,
+        ctx: Context,
+        _channel_id: ChannelId,
+        deleted_message_id: MessageId,
+        _guild_id: Option<GuildId>,
+    ) {
+        mark_moderated(&ctx, deleted_message_id).await;
+    }
+
+    async fn shard_stage_update(&self, ctx: Context, event: ShardStageUpdateEvent) {
+        // Health checks follow the main bot
+        if BotState::get(&ctx.data).await.instance == 0 {
+            http::set_gateway_connected(event.new == ConnectionStage::Connected);
+        }
+    }
+
+    async fn cache_ready(&self, ctx: Context, guilds: Vec<GuildId>) {
+        for guild_id in guilds {
+            sync_guild_users(&ctx.cache, &ctx.data, guild_id, None).await;
+        }
+
+        // Sessions are only saved for the main bot
+        if BotState::get(&ctx.data).await.instance != 0 {
+            return;
+        }
+        if let Err(e) = sessions::restore_sessions(&ctx).await {
+            error!(error = ?e, "Failed to restore sessions");
+        }
+    }
+
+    async fn guild_delete(&self, ctx: Context, incomplete: UnavailableGuild, _full: Option<Guild>) {
+        // Outages also remove guilds from the cache, only a kick or ban means the bot is gone for good
+        if incomplete.unavailable {
+            return;
+        }
+
+        info!("Removed from guild {}", incomplete.id);
+        leave_voice(&ctx, incomplete.id).await;
+
+        let state = BotState::get(&ctx.data).await;
+        if let Err(e) = state.guild_configs.remove_config(incomplete.id.get()).await {
+            error!(error = ?e, "Failed to remove guild config");
+        }
+    }
+
+    async fn channel_delete(
+        &self,
+        ctx: Context,
+        channel: GuildChannel,
+        _messages: Option<Vec<Message>>,
+    ) {
+        let guild_id = channel.guild_id;
+        let is_bound = get_binding(&ctx, guild_id).await.is_some_and(|binding| {
+            binding.voice_channel_id == channel.id || binding.text_channel_id == channel.id
+        });
+        let is_connected = match songbird::get(&ctx).await.and_then(|m| m.get(guild_id)) {
+            Some(handler_lock) => {
+                handler_lock.lock().await.current_channel() == Some(channel.id.into())
+            }
+            None => false,
+        };
+        if is_bound || is_connected {
+            info!("Channel {} in use was deleted", channel.id);
+            leave_voice(&ctx, guild_id).await;
+        }
+
+        let state = BotState::get(&ctx.data).await;
+        let id = channel.id.get();
+        let is_listed = |config: &GuildConfig| {
+            config.transcript_channel == Some(id)
+                || config.feeds.iter().any(|feed| feed.channel == id)
+                || [&config.text_channels, &config.voice_channels]
+                    .iter()
+                    .any(|list| list.allowed.contains(&id) || list.blocked.contains(&id))
+        };
+        if !is_listed(&state.guild_configs.get_config(guild_id.get()).await) {
+            return;
+        }
+
+        if let Err(e) = state
+            .guild_configs
+            .update_config(guild_id.get(), |config| {
+                for list in [&mut config.text_channels, &mut config.voice_channels] {
+                    list.allowed.retain(|allowed| *allowed != id);
+                    list.blocked.retain(|blocked| *blocked != id);
+                }
+                if config.transcript_channel == Some(id) {
+                    config.transcript_channel = None;
+                }
+                config.feeds.retain(|feed| feed.channel != id);
+            })
+            .await
+        {
+            error!(error = ?e, "Failed to save guild configs");
+        }
+    }
+
+    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        match interaction {
+            Interaction::Command(command) => commands::run(&ctx, &command).await,
+            Interaction::Autocomplete(command) => commands::autocomplete(&ctx, &command).await,
+            _ => {}
+        }
+    }
+
+    async fn message(&self, ctx: Context, new_message: Message) {
+        message::handle(ctx, new_message).await
+    }
+
+    async fn voice_state_update(&self, ctx: Context, old: Option<VoiceState>, new: VoiceState) {
+        voice_state::handle(ctx, old, new).await
+    }
+}
@@ -0,0 +1,492 @@
+use std::{collections::HashSet, time::Duration};
+
+use dectalk_bot_core::{
+    audio::normalize_wav_volume,
+    dectalk::{Language, PAUL_VOICE},
+    engine::TtsEngine,
+};
+use serenity::{
+    all::{ChannelId, GuildId, UserId, VoiceState},
+    cache::Cache,
+    client::Context,
+    prelude::{RwLock, TypeMap},
+};
+use songbird::{input::Input, tracks::Track, Call, Songbird};
+use tokio::time::Instant;
+use tracing::{debug, error, info, instrument, warn};
+
+use crate::{
+    audio::synthesize,
+    config,
+    guild_config::GuildConfig,
+    preprocess::process_message,
+    shutdown,
+    state::{Binding, BotState},
+};
+
+const ANNOUNCEMENT_COOLDOWN: Duration = Duration::from_secs(10);
+const TRANSCRIPTION_NOTICE: &str =
+    "Speech in this call is transcribed. Use the transcription command to opt out.";
+
+#[instrument(skip_all, fields(guild_id = ?new.guild_id, user_id = %new.user_id))]
+pub async fn handle(ctx: Context, old: Option<VoiceState>, new: VoiceState) {
+    let guild_id = match new.guild_id {
+        Some(guild_id) => guild_id,
+        None => {
+            debug!("Ignoring voice state outside a guild");
+            return;
+        }
+    };
+
+    let bot_id = ctx.cache.current_user().id;
+    if new.user_id == bot_id {
+        pause_while_muted(&ctx, guild_id, &new).await;
+    }
+
+    let state = BotState::get(&ctx.data).await;
+    let bot_channel_id = ctx.cache.guild(guild_id).and_then(|guild| {
+        guild
+            .voice_states
+            .get(&bot_id)
+            .and_then(|voice_state| voice_state.channel_id)
+    });
+    let is_bot = match &new.member {
+        Some(member) => member.user.bot,
+        None => false,
+    };
+
+    if let (Some(channel_id), Some(bot_channel_id)) = (new.channel_id, bot_channel_id) {
+        if channel_id != bot_channel_id && follow_author(&ctx, guild_id, &new).await {
+            return;
+        }
+    }
+
+    let mut guild_users = state.guild_users.lock().await;
+
+    if new.channel_id.is_none() || (bot_channel_id.is_some() && new.channel_id != bot_channel_id) {
+        guild_users
+            .entry(guild_id)
+            .or_insert_with(HashSet::new)
+            .remove(&new.user_id);
+    } else if !is_bot && new.channel_id == bot_channel_id {
+        guild_users
+            .entry(guild_id)
+            .or_insert_with(HashSet::new)
+            .insert(new.user_id);
+    }
+
+    let is_empty = guild_users
+        .entry(guild_id)
+        .or_insert_with(HashSet::new)
+        .is_empty();
+    drop(guild_users);
+
+    let config = state.guild_configs.get_config(guild_id.get()).await;
+    let is_sticky = config.sticky && get_binding(&ctx, guild_id).await.is_some();
+    if !is_sticky && is_empty {
+        let manager = match songbird::get(&ctx).await {
+            Some(manager) => manager,
+            None => {
+                error!("Failed to get songbird manager");
+                return;
+            }
+        };
+
+        let handler_lock = match manager.get(guild_id) {
+            Some(handler_lock) => handler_lock,
+            None => {
+                error!("Failed to get handler lock");
+                return;
+            }
+        };
+        let mut handler = handler_lock.lock().await;
+
+        if let Err(e) = handler.leave().await {
+            warn!(error = ?e, "Failed to leave channel");
+        }
+        return;
+    }
+
+    let old_channel_id = old.and_then(|old| old.channel_id);
+    if bot_channel_id.is_some() && !is_bot && old_channel_id != new.channel_id {
+        let name = match &new.member {
+            Some(member) => member.display_name().to_string(),
+            None => new.user_id.to_string(),
+        };
+        if new.channel_id == bot_channel_id {
+            if config.welcome_members && should_welcome(&ctx, guild_id, new.user_id, &config).await
+            {
+                announce(&ctx, guild_id, &format!("welcome, {}", name), &config).await;
+            } else if config.announce_members {
+                announce(&ctx, guild_id, &format!("{} joined", name), &config).await;
+            }
+        } else if config.announce_members && old_channel_id == bot_channel_id {
+            announce(&ctx, guild_id, &format!("{} left", name), &config).await;
+        }
+    }
+}
+
+/// Whether `user_id` hasn't been welcomed within the guild's cooldown, marking them as
+/// welcomed if so.
+async fn should_welcome(
+    ctx: &Context,
+    guild_id: GuildId,
+    user_id: UserId,
+    config: &GuildConfig,
+) -> bool {
+    let state = BotState::get(&ctx.data).await;
+    let mut welcomed = state.welcomed.lock().await;
+    let cooldown = Duration::from_secs(config.welcome_cooldown);
+    if welcomed
+        .get(&(guild_id, user_id))
+        .is_some_and(|welcomed_at| welcomed_at.elapsed() < cooldown)
+    {
+        return false;
+    }
+    welcomed.insert((guild_id, user_id), Instant::now());
+    true
+}
+
+async fn announce(ctx: &Context, guild_id: GuildId, text: &str, config: &GuildConfig) {
+    if shutdown::is_shutting_down() || shutdown::is_panicked() {
+        return;
+    }
+
+    {
+        let state = BotState::get(&ctx.data).await;
+        let mut announced = state.announced.lock().await;
+        if announced
+            .get(&guild_id)
+            .is_some_and(|announced_at| announced_at.elapsed() < ANNOUNCEMENT_COOLDOWN)
+        {
+            return;
+        }
+        announced.insert(guild_id, Instant::now());
+    }
+
+    speak_announcement(ctx, guild_id, text, config).await;
+}
+
+/// Queues `text` in Paul's voice, skipping the announcement cooldown.
+async fn speak_announcement(ctx: &Context, guild_id: GuildId, text: &str, config: &GuildConfig) {
+    let content = process_message(text, config);
+    if content.is_empty() {
+        return;
+    }
+
+    let handler_lock = match songbird::get(ctx).await.and_then(|m| m.get(guild_id)) {
+        Some(handler_lock) => handler_lock,
+        None => {
+            error!("Failed to get handler lock");
+            return;
+        }
+    };
+
+    let tts_bytes = match synthesize(&content, &PAUL_VOICE, Language::English).await {
+        Ok(tts_bytes) => tts_bytes,
+        Err(e) => {
+            error!(error = ?e, "Failed to generate announcement TTS");
+            return;
+        }
+    };
+    let normalized_tts_bytes = match normalize_wav_volume(&tts_bytes) {
+        Ok(normalized_tts_bytes) => normalized_tts_bytes,
+        Err(e) => {
+            error!(error = ?e, "Failed to normalize TTS volume");
+            return;
+        }
+    };
+
+    info!("Announcing \"{}\" in {}", content, guild_id);
+    handler_lock
+        .lock()
+        .await
+        .enqueue(Track::from(Input::from(normalized_tts_bytes)).volume(config::get().engine.volume))
+        .await;
+}
+
+async fn pause_while_muted(ctx: &Context, guild_id: GuildId, voice_state: &VoiceState) {
+    let manager = match songbird::get(ctx).await {
+        Some(manager) => manager,
+        None => {
+            error!("Failed to get songbird manager");
+            return;
+        }
+    };
+
+    let handler_lock = match manager.get(guild_id) {
+        Some(handler_lock) => handler_lock,
+        None => return,
+    };
+    let handler = handler_lock.lock().await;
+
+    let result = if voice_state.mute || voice_state.deaf {
+        handler.queue().pause()
+    } else {
+        handler.queue().resume()
+    };
+    if let Err(e) = result {
+        error!(error = ?e, "Failed to update queue");
+    }
+}
+
+async fn follow_author(ctx: &Context, guild_id: GuildId, new: &VoiceState) -> bool {
+    let channel_id = match new.channel_id {
+        Some(channel_id) if !shutdown::is_panicked() => channel_id,
+        _ => return false,
+    };
+
+    let state = BotState::get(&ctx.data).await;
+    if state.serving.lock().await.get(&guild_id) != Some(&new.user_id) {
+        return false;
+    }
+
+    let config = state.guild_configs.get_config(guild_id.get()).await;
+    if !config.follow_author || (config.sticky && get_binding(ctx, guild_id).await.is_some()) {
+        return false;
+    }
+    // Another bot account is already reading there
+    if state
+        .coordinator
+        .owner(guild_id, channel_id)
+        .await
+        .is_some_and(|owner| owner != state.instance)
+    {
+        return false;
+    }
+
+    let manager = match songbird::get(ctx).await {
+        Some(manager) => manager,
+        None => {
+            error!("Failed to get songbird manager");
+            return false;
+        }
+    };
+
+    info!("Following {} to {}", new.user_id, channel_id);
+    let handler_lock = match manager.join(guild_id, channel_id).await {
+        Ok(handler_lock) => handler_lock,
+        Err(e) => {
+            error!(error = ?e, "Failed to follow author");
+            return false;
+        }
+    };
+
+    sync_guild_users(
+        &ctx.cache,
+        &ctx.data,
+        guild_id,
+        Some((channel_id, &mut *handler_lock.lock().await)),
+    )
+    .await;
+    true
+}
+
+/// Refreshes who's in the bot's call. After a join, pass the channel and the call, which the
+/// caller is expected to still hold since songbird's lock isn't reentrant.
+pub async fn sync_guild_users(
+    cache: &Cache,
+    data: &RwLock<TypeMap>,
+    guild_id: GuildId,
+    joined: Option<(ChannelId, &mut Call)>,
+) {
+    let bot_id = cache.current_user().id;
+    let joined_channel = joined.as_ref().map(|(channel_id, _)| *channel_id);
+    let users = {
+        let guild = match cache.guild(guild_id) {
+            Some(guild) => guild,
+            None => return,
+        };
+
+        let channel_id = match joined_channel.or_else(|| {
+            guild
+                .voice_states
+                .get(&bot_id)
+                .and_then(|voice_state| voice_state.channel_id)
+        }) {
+            Some(channel_id) => channel_id,
+            None => return,
+        };
+
+        guild
+            .voice_states
+            .values()
+            .filter(|voice_state| voice_state.channel_id == Some(channel_id))
+            .filter(|voice_state| voice_state.user_id != bot_id)
+            .filter(|voice_state| match &voice_state.member {
+                Some(member) => !member.user.bot,
+                None => true,
+            })
+            .map(|voice_state| voice_state.user_id)
+            .collect::<HashSet<_>>()
+    };
+
+    debug!("Tracking {} users in {}", users.len(), guild_id);
+    let state = BotState::get(data).await;
+    // Only joins pass the channel, so everyone already there gets their voice ready
+    if let Some((_, call)) = joined {
+        state
+            .voice_manager
+            .prewarm(
+                &users
+                    .iter()
+                    .map(|user_id| user_id.get())
+                    .collect::<Vec<_>>(),
+            )
+            .await;
+        if config::get().engine.warm_up {
+            tokio::spawn(warm_up_engine());
+        }
+
+        let config = state.guild_configs.get_config(guild_id.get()).await;
+        let stt_configured = cfg!(feature = "stt") && config::get().stt.is_some();
+        announce_transcription(call, &*state.engine, &config, stt_configured).await;
+    }
+    state.guild_users.lock().await.insert(guild_id, users);
+}
+
+/// Nobody's speech should be sent off without them hearing about it first, so this is queued
+/// whenever the bot joins a call that's transcribed.
+pub async fn announce_transcription(
+    call: &mut Call,
+    engine: &dyn TtsEngine,
+    config: &GuildConfig,
+    stt_configured: bool,
+) {
+    let is_transcribing = stt_configured
+        && ((config.transcribe_speech && config.transcript_channel.is_some())
+            || config.voice_commands);
+    if !is_transcribing {
+        return;
+    }
+
+    let content = process_message(TRANSCRIPTION_NOTICE, config);
+    let tts_bytes = match engine
+        .synthesize(&content, &PAUL_VOICE, Language::English)
+        .await
+    {
+        Ok(tts_bytes) => tts_bytes,
+        Err(e) => {
+            error!(error = ?e, "Failed to generate transcription notice TTS");
+            return;
+        }
+    };
+    let normalized_tts_bytes = match normalize_wav_volume(&tts_bytes) {
+        Ok(normalized_tts_bytes) => normalized_tts_bytes,
+        Err(e) => {
+            error!(error = ?e, "Failed to normalize TTS volume");
+            return;
+        }
+    };
+    call.enqueue(
+        Track::from(Input::from(normalized_tts_bytes)).volume(config::get().engine.volume),
+    )
+    .await;
+}
+
+async fn warm_up_engine() {
+    match synthesize("[_<10>]", &PAUL_VOICE, Language::English).await {
+        Ok(_) => debug!("Warmed up the engine"),
+        Err(e) => warn!(error = ?e, "Failed to warm up the engine"),
+    }
+}
+
+pub async fn get_binding(ctx: &Context, guild_id: GuildId) -> Option<Binding> {
+    let state = BotState::get(&ctx.data).await;
+    let binding = state.bindings.lock().await.get(&guild_id).copied();
+    binding
+}
+
+pub async fn leave_voice(ctx: &Context, guild_id: GuildId) {
+    let manager = match songbird::get(ctx).await {
+        Some(manager) => manager,
+        None => {
+            error!("Failed to get songbird manager");
+            return;
+        }
+    };
+    leave_guild(&ctx.data, &manager, guild_id).await;
+}
+
+/// Leaves the guild's call and forgets everything tracked for it.
+pub async fn leave_guild(data: &RwLock<TypeMap>, manager: &Songbird, guild_id: GuildId) {
+    if manager.get(guild_id).is_some() {
+        if let Err(e) = manager.remove(guild_id).await {
+            warn!(error = ?e, "Failed to leave channel");
+        }
+    }
+
+    let state = BotState::get(data).await;
+    state.guild_users.lock().await.remove(&guild_id);
+    state.last_played.lock().await.remove(&guild_id);
+    state.serving.lock().await.remove(&guild_id);
+    state.bindings.lock().await.remove(&guild_id);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{error::Error, io::Cursor};
+
+    use dectalk_bot_core::dectalk::DectalkVoice;
+    use serenity::async_trait;
+    use tokio::{sync::Mutex, time::timeout};
+
+    use super::*;
+
+    /// A short beep, without running DECtalk.
+    struct BeepEngine;
+
+    #[async_trait]
+    impl TtsEngine for BeepEngine {
+        async fn synthesize(
+            &self,
+            _text: &str,
+            _voice: &DectalkVoice,
+            _language: Language,
+        ) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+            let spec = hound::WavSpec {
+                channels: 1,
+                sample_rate: 8000,
+                bits_per_sample: 16,
+                sample_format: hound::SampleFormat::Int,
+            };
+            let mut buf = Vec::new();
+            let mut writer = hound::WavWriter::new(Cursor::new(&mut buf), spec)?;
+            for i in 0..800 {
+                writer.write_sample(if i % 8 < 4 { 4000i16 } else { -4000 })?;
+            }
+            writer.finalize()?;
+            Ok(buf)
+        }
+    }
+
+    fn transcribed_config() -> GuildConfig {
+        GuildConfig {
+            transcribe_speech: true,
+            transcript_channel: Some(1),
+            ..GuildConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn joining_a_transcribed_call_queues_the_notice() {
+        let call = Mutex::new(Call::standalone(GuildId::new(1), UserId::new(2)));
+        // Held the way every caller holds it after joining
+        let mut handler = call.lock().await;
+        timeout(
+            Duration::from_secs(5),
+            announce_transcription(&mut handler, &BeepEngine, &transcribed_config(), true),
+        )
+        .await
+        .expect("announcing locked up the call");
+        assert_eq!(handler.queue().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn untranscribed_calls_get_no_notice() {
+        let mut call = Call::standalone(GuildId::new(1), UserId::new(2));
+        announce_transcription(&mut call, &BeepEngine, &transcribed_config(), false).await;
+        announce_transcription(&mut call, &BeepEngine, &GuildConfig::default(), true).await;
+        assert!(call.queue().is_empty());
+    }
+}
@@ -0,0 +1,233 @@
+use std::{
+    collections::{HashMap, HashSet},
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::{bail, Context as _};
+use feed_rs::model::Feed;
+use reqwest::{header::LOCATION, redirect::Policy, Client, Url};
+use serenity::{
+    all::{ChannelId, CreateAllowedMentions, CreateMessage, GuildId},
+    cache::Cache,
+    http::Http,
+    prelude::{RwLock, TypeMap},
+};
+use songbird::Songbird;
+use tokio::{net::lookup_host, time};
+use tracing::{debug, error, warn};
+
+use dectalk_bot_core::dectalk::PAUL_VOICE;
+
+use crate::{audio::speak_in_guild, state::BotState};
+
+pub const MAX_FEEDS: usize = 5;
+const POLL_INTERVAL: Duration = Duration::from_secs(10 * 60);
+const FETCH_TIMEOUT: Duration = Duration::from_secs(20);
+// A feed that republishes everything shouldn't take over the voice channel
+const MAX_NEW_ITEMS: usize = 3;
+
+const MAX_FEED_SIZE: usize = 1024 * 1024;
+const MAX_REDIRECTS: usize = 5;
+
+/// Guild admins pick the URL, so only public http(s) addresses are fetched, redirects are
+/// checked the same way and the body is capped.
+pub async fn fetch_feed(url: &str) -> anyhow::Result<Feed> {
+    let mut url = Url::parse(url)?;
+    for _ in 0..=MAX_REDIRECTS {
+        let response = public_client(&url)
+            .await?
+            .get(url.clone())
+            .timeout(FETCH_TIMEOUT)
+            .send()
+            .await?;
+        if response.status().is_redirection() {
+            let location = response
+                .headers()
+                .get(LOCATION)
+                .context("Redirect without a location")?
+                .to_str()?;
+            url = url.join(location)?;
+            continue;
+        }
+
+        let mut response = response.error_for_status()?;
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            if body.len() + chunk.len() > MAX_FEED_SIZE {
+                bail!("Feed is over {} bytes", MAX_FEED_SIZE);
+            }
+            body.extend_from_slice(&chunk);
+        }
+        return Ok(feed_rs::parser::parse(&body[..])?);
+    }
+    bail!("Too many redirects")
+}
+
+/// A client that only connects to the addresses `url`'s host resolves to now, so a second
+/// lookup can't point it somewhere internal.
+async fn public_client(url: &Url) -> anyhow::Result<Client> {
+    if !matches!(url.scheme(), "http" | "https") {
+        bail!("Only http and https feeds are supported");
+    }
+    let host = url.host_str().context("Feed URL has no host")?;
+    let port = url
+        .port_or_known_default()
+        .context("Feed URL has no port")?;
+    let addrs = match host.trim_matches(['[', ']']).parse::<IpAddr>() {
+        Ok(ip) => vec![SocketAddr::new(ip, port)],
+        Err(_) => lookup_host((host, port)).await?.collect(),
+    };
+    if addrs.is_empty() || !addrs.iter().all(|addr| is_public(addr.ip())) {
+        bail!("{} isn't a public address", host);
+    }
+    Ok(Client::builder()
+        .redirect(Policy::none())
+        .resolve_to_addrs(host, &addrs)
+        .build()?)
+}
+
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [first, second, ..] = ip.octets();
+            // 0.0.0.0/8 and the 100.64.0.0/10 carrier-grade NAT range aren't covered by std
+            let is_reserved = first == 0 || (first == 100 && second & 0xc0 == 64);
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                || is_reserved)
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(IpAddr::V4(ip)),
+            None => {
+                let segment = ip.segments()[0];
+                let is_unique_local = segment & 0xfe00 == 0xfc00;
+                let is_link_local = segment & 0xffc0 == 0xfe80;
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_multicast()
+                    || is_unique_local
+                    || is_link_local)
+            }
+        },
+    }
+}
+
+/// Polls every guild's feeds, posting links to new items and reading their titles when the bot
+/// is in a call there. Items already in a feed when it's first seen, including after a restart,
+/// are skipped so nothing old gets read.
+pub async fn poll_feeds(
+    http: Arc<Http>,
+    cache: Arc<Cache>,
+    data: Arc<RwLock<TypeMap>>,
+    songbird: Arc<Songbird>,
+) {
+    let state = BotState::get(&data).await;
+
+    let mut seen: HashMap<(u64, String), HashSet<String>> = HashMap::new();
+    let mut interval = time::interval(POLL_INTERVAL);
+    loop {
+        interval.tick().await;
+
+        let subscriptions = state
+            .guild_configs
+            .configs
+            .lock()
+            .await
+            .iter()
+            .flat_map(|(guild_id, config)| {
+                config
+                    .feeds
+                    .iter()
+                    .map(|subscription| (*guild_id, subscription.clone()))
+            })
+            .collect::<Vec<_>>();
+        seen.retain(|(guild_id, url), _| {
+            subscriptions
+                .iter()
+                .any(|(id, subscription)| id == guild_id && &subscription.url == url)
+        });
+
+        let mut feeds = HashMap::new();
+        for (guild_id, subscription) in subscriptions {
+            if !feeds.contains_key(&subscription.url) {
+                let feed = fetch_feed(&subscription.url)
+                    .await
+                    .inspect_err(|e| debug!(error = ?e, "Failed to fetch {}", subscription.url))
+                    .ok();
+                feeds.insert(subscription.url.clone(), feed);
+            }
+            let feed = match &feeds[&subscription.url] {
+                Some(feed) => feed,
+                None => continue,
+            };
+
+            let ids = feed
+                .entries
+                .iter()
+                .map(|entry| entry.id.clone())
+                .collect::<HashSet<_>>();
+            let previous = seen.insert((guild_id, subscription.url.clone()), ids);
+            let previous = match previous {
+                Some(previous) => previous,
+                None => continue,
+            };
+
+            let feed_title = feed
+                .title
+                .as_ref()
+                .map(|title| title.content.clone())
+                .unwrap_or_else(|| "Feed".to_string());
+            let entries = feed
+                .entries
+                .iter()
+                .filter(|entry| !previous.contains(&entry.id))
+                .take(MAX_NEW_ITEMS);
+            for entry in entries {
+                let title = match &entry.title {
+                    Some(title) => title.content.trim().to_string(),
+                    None => continue,
+                };
+                let link = entry
+                    .links
+                    .first()
+                    .map(|link| link.href.clone())
+                    .unwrap_or_default();
+
+                let message = CreateMessage::new()
+                    .content(format!("📰 **{}**: {}\n{}", feed_title, title, link))
+                    .allowed_mentions(CreateAllowedMentions::new());
+                if let Err(e) = ChannelId::new(subscription.channel)
+                    .send_message(&http, message)
+                    .await
+                {
+                    warn!(error = ?e, "Failed to post feed item");
+                }
+
+                if songbird.get(GuildId::new(guild_id)).is_some() {
+                    match speak_in_guild(
+                        &data,
+                        &cache,
+                        &songbird,
+                        GuildId::new(guild_id),
+                        None,
+                        &feed_title,
+                        &title,
+                        &PAUL_VOICE,
+                    )
+                    .await
+                    {
+                        Ok(()) => {}
+                        Err(e) if e.is_expected() => debug!(error = ?e, "Skipping feed item"),
+                        Err(e) => error!(error = ?e, "Failed to read feed item"),
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use std::{sync::Arc, time::Duration};
+
+use serenity::{
+    all::{ChannelId, GuildId},
+    async_trait,
+    cache::Cache,
+    prelude::{RwLock, TypeMap},
+};
+use songbird::Songbird;
+use tracing::{error, info};
+
+use dectalk_bot_core::dectalk::DectalkVoice;
+
+use crate::{audio::speak_in_guild, error::BotError, shutdown};
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// A chat platform other than Discord that feeds text into the voice pipeline. Frontends only
+/// deal with their own protocol and hand each message to a `Speaker`.
+#[async_trait]
+pub trait Frontend: Send + 'static {
+    fn name(&self) -> &'static str;
+
+    /// Connects and reads messages until the connection drops.
+    async fn run(&mut self, speaker: &Speaker) -> anyhow::Result<()>;
+}
+
+/// Reads text in a Discord voice channel on behalf of a frontend.
+#[derive(Clone)]
+pub struct Speaker {
+    data: Arc<RwLock<TypeMap>>,
+    cache: Arc<Cache>,
+    songbird: Arc<Songbird>,
+}
+
+impl Speaker {
+    pub fn new(data: Arc<RwLock<TypeMap>>, cache: Arc<Cache>, songbird: Arc<Songbird>) -> Self {
+        Speaker {
+            data,
+            cache,
+            songbird,
+        }
+    }
+
+    pub async fn speak(
+        &self,
+        guild_id: GuildId,
+        channel_id: Option<ChannelId>,
+        speaker: &str,
+        text: &str,
+        voice: &DectalkVoice,
+    ) -> Result<(), BotError> {
+        speak_in_guild(
+            &self.data,
+            &self.cache,
+            &self.songbird,
+            guild_id,
+            channel_id,
+            speaker,
+            text,
+            voice,
+        )
+        .await
+    }
+}
+
+/// Gives each nickname or user ID its own voice, the same way a Discord user ID does. FNV-1a
+/// rather than the standard hasher, whose output can change between Rust releases and would
+/// change everyone's voice with it.
+#[cfg(any(feature = "irc", feature = "matrix"))]
+pub fn name_id(name: &str) -> u64 {
+    name.to_lowercase()
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+        })
+}
+
+/// Runs the frontend in the background, reconnecting whenever it stops until the bot shuts down.
+pub fn spawn(mut frontend: impl Frontend, speaker: Speaker) {
+    tokio::spawn(async move {
+        loop {
+            info!("Starting {} frontend", frontend.name());
+            if let Err(e) = frontend.run(&speaker).await {
+                error!(error = ?e, "{} frontend disconnected", frontend.name());
+            }
+            if shutdown::is_shutting_down() {
+                return;
+            }
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+    });
+}
@@ -0,0 +1,662 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    str::FromStr,
+    sync::{Arc, OnceLock},
+};
+
+use chrono_tz::Tz;
+use dectalk_bot_core::{
+    preprocess::{PreprocessOptions, BUILTIN_VOICES},
+    profanity::{self, ProfanityAction},
+};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use tracing::{debug, info, warn};
+
+use crate::{
+    config,
+    error::{self, BotError},
+};
+
+/// Settings holding a channel the bot posts in, which has to be in the same guild.
+pub const CHANNEL_SETTINGS: &[&str] = &["transcript_channel", "lottery_channel"];
+
+pub const SETTINGS: &[&str] = &[
+    "enabled",
+    "read_emoji",
+    "emoji_limit",
+    "read_link_domains",
+    "expand_slang",
+    "read_stickers",
+    "read_embeds",
+    "foreign_language",
+    "profanity_action",
+    "profanity_replacement",
+    "ignored_prefixes",
+    "ignore_bots",
+    "ignore_webhooks",
+    "ignored_applications",
+    "max_message_length",
+    "max_duration",
+    "voice_tags",
+    "voice_tag_roles",
+    "idle_timeout",
+    "follow_author",
+    "sticky",
+    "channel_mode",
+    "read_threads",
+    "read_nsfw",
+    "announce_members",
+    "welcome_members",
+    "welcome_cooldown",
+    "admin_role",
+    "transcript_channel",
+    "transcribe_speech",
+    "voice_commands",
+    "rate_limit",
+    "rate_limit_period",
+    "suppress_duplicates",
+    "count_duplicates",
+    "speak_delay",
+    "phoneme_captions",
+    "lottery_channel",
+    "lottery_hour",
+    "timezone",
+];
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Limits {
+    pub max_message_length: usize,
+    pub max_duration: f64,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_message_length: 256,
+            max_duration: 15.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChannelList {
+    pub allowed: Vec<u64>,
+    pub blocked: Vec<u64>,
+}
+
+impl ChannelList {
+    pub fn permits(&self, channel_id: u64) -> bool {
+        !self.blocked.contains(&channel_id)
+            && (self.allowed.is_empty() || self.allowed.contains(&channel_id))
+    }
+
+    /// Threads count as their parent channel, but can also be blocked on their own.
+    pub fn permits_thread(&self, thread_id: u64, parent_id: u64) -> bool {
+        !self.blocked.contains(&thread_id)
+            && !self.blocked.contains(&parent_id)
+            && (self.allowed.is_empty()
+                || self.allowed.contains(&parent_id)
+                || self.allowed.contains(&thread_id))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChannelMode {
+    VoiceChat,
+    Bound,
+    Any,
+}
+
+impl fmt::Display for ChannelMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            ChannelMode::VoiceChat => "voice_chat",
+            ChannelMode::Bound => "bound",
+            ChannelMode::Any => "any",
+        })
+    }
+}
+
+impl FromStr for ChannelMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "voice_chat" => Ok(ChannelMode::VoiceChat),
+            "bound" => Ok(ChannelMode::Bound),
+            "any" => Ok(ChannelMode::Any),
+            _ => Err(format!(
+                "Expected voice_chat, bound or any, got `{}`",
+                value
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ForeignLanguageMode {
+    Read,
+    Skip,
+    Spell,
+    Route,
+}
+
+impl fmt::Display for ForeignLanguageMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            ForeignLanguageMode::Read => "read",
+            ForeignLanguageMode::Skip => "skip",
+            ForeignLanguageMode::Spell => "spell",
+            ForeignLanguageMode::Route => "route",
+        })
+    }
+}
+
+impl FromStr for ForeignLanguageMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "read" => Ok(ForeignLanguageMode::Read),
+            "skip" => Ok(ForeignLanguageMode::Skip),
+            "spell" => Ok(ForeignLanguageMode::Spell),
+            "route" => Ok(ForeignLanguageMode::Route),
+            _ => Err(format!(
+                "Expected read, skip, spell or route, got `{}`",
+                value
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GuildConfig {
+    pub enabled: bool,
+    pub read_emoji: bool,
+    pub emoji_limit: usize,
+    pub read_link_domains: bool,
+    pub expand_slang: bool,
+    pub slang: HashMap<String, String>,
+    /// Server wide `/macro` triggers, expanded before the length limit is checked.
+    pub macros: HashMap<String, String>,
+    pub read_stickers: bool,
+    pub read_embeds: bool,
+    pub foreign_language: ForeignLanguageMode,
+    pub allow_filters: Vec<String>,
+    pub deny_filters: Vec<String>,
+    pub profanity_words: Vec<String>,
+    pub profanity_action: ProfanityAction,
+    pub profanity_replacement: String,
+    pub ignored_prefixes: Vec<String>,
+    pub ignore_bots: bool,
+    pub ignore_webhooks: bool,
+    pub ignored_applications: Vec<u64>,
+    pub limits: Limits,
+    pub role_limits: HashMap<u64, Limits>,
+    pub voice_tags: bool,
+    pub voice_tag_roles: Vec<u64>,
+    pub idle_timeout: u64,
+    pub follow_author: bool,
+    pub sticky: bool,
+    pub channel_mode: ChannelMode,
+    pub read_threads: bool,
+    pub text_channels: ChannelList,
+    pub voice_channels: ChannelList,
+    pub read_nsfw: bool,
+    pub announce_members: bool,
+    /// Say "welcome, name" when someone joins the bot's voice channel.
+    pub welcome_members: bool,
+    /// Seconds before the same user is welcomed again.
+    pub welcome_cooldown: u64,
+    pub admin_role: Option<u64>,
+    /// Lets caption overlays connect to `/transcript/<guild>`, set with `/transcript token`.
+    pub transcript_token: Option<String>,
+    pub transcript_channel: Option<u64>,
+    /// Also post what people say in calls to `transcript_channel`, when the bot has `stt` set up.
+    pub transcribe_speech: bool,
+    /// Lets anyone in the call say "hey dectalk" then skip, stop or leave, when the bot has
+    /// `stt` set up.
+    pub voice_commands: bool,
+    /// Messages each user may have read per `rate_limit_period` seconds, 0 for no limit.
+    pub rate_limit: u32,
+    pub rate_limit_period: u64,
+    /// Only read the first of several identical messages from the same user.
+    pub suppress_duplicates: bool,
+    /// Say "times 2", "times 3"... for each suppressed repeat instead of staying quiet.
+    pub count_duplicates: bool,
+    /// Seconds to wait before reading, giving moderation bots a chance to delete the message.
+    pub speak_delay: f64,
+    /// Reply to each message read with the phonemes DECtalk spoke, for crafting phoneme
+    /// messages and debugging pronunciations.
+    pub phoneme_captions: bool,
+    /// Users this server's admins have stopped the bot from reading.
+    pub blacklist: Vec<u64>,
+    /// Voices this server's admins assigned with `/admin setvoice`, used here instead of the
+    /// user's own until they pick another with `/voice`.
+    pub assigned_voices: HashMap<u64, AssignedVoice>,
+    /// Feeds whose new items are read aloud and linked, managed with `/feed`.
+    pub feeds: Vec<FeedSubscription>,
+    /// Messages read and posted on a cron schedule, managed with `/announce`.
+    pub announcements: Vec<Announcement>,
+    /// Where the daily voice lottery is announced, the lottery only runs when this is set.
+    pub lottery_channel: Option<u64>,
+    /// The hour, in UTC, the lottery is drawn.
+    pub lottery_hour: u32,
+    /// Users who joined the lottery with `/lottery join`.
+    pub lottery_entrants: Vec<u64>,
+    pub golden_voice: Option<GoldenVoice>,
+    /// Used by `/time`.
+    pub timezone: Tz,
+    /// Shared between clones, and reset by `update_config`.
+    #[serde(skip)]
+    pub compiled_filters: Arc<OnceLock<CompiledFilters>>,
+}
+
+/// The profanity words and filters as regexes, built on first use rather than per message.
+#[derive(Debug)]
+pub struct CompiledFilters {
+    profanity: Vec<Regex>,
+    allow: Vec<Regex>,
+    deny: Vec<Regex>,
+}
+
+/// The lottery winner, who speaks with a golden voice until `until`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GoldenVoice {
+    pub user: u64,
+    /// Seconds since the Unix epoch.
+    pub until: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AssignedVoice {
+    /// A built-in voice like `betty`.
+    Preset(String),
+    /// A numbered voice like `/voice` picks.
+    Roll(u64),
+}
+
+impl AssignedVoice {
+    pub fn preset(&self) -> Option<&'static str> {
+        match self {
+            AssignedVoice::Preset(preset) => BUILTIN_VOICES
+                .iter()
+                .map(|(name, _)| *name)
+                .find(|name| name == preset),
+            AssignedVoice::Roll(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeedSubscription {
+    pub url: String,
+    /// Where links to new items are posted.
+    pub channel: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Announcement {
+    /// A cron expression in UTC, see `cron::Schedule`.
+    pub schedule: String,
+    pub text: String,
+    /// Where the text is posted.
+    pub channel: u64,
+    /// The voice number to read it in, Paul when unset.
+    pub voice: Option<u64>,
+}
+
+impl Default for GuildConfig {
+    fn default() -> Self {
+        GuildConfig {
+            enabled: true,
+            read_emoji: true,
+            emoji_limit: 3,
+            read_link_domains: false,
+            expand_slang: false,
+            slang: HashMap::new(),
+            macros: HashMap::new(),
+            read_stickers: true,
+            read_embeds: true,
+            foreign_language: ForeignLanguageMode::Read,
+            allow_filters: Vec::new(),
+            deny_filters: Vec::new(),
+            profanity_words: Vec::new(),
+            profanity_action: ProfanityAction::Bleep,
+            profanity_replacement: "beep".to_string(),
+            ignored_prefixes: ["!", "?", ".", "-"].map(String::from).to_vec(),
+            ignore_bots: true,
+            ignore_webhooks: true,
+            ignored_applications: Vec::new(),
+            limits: config::get().limits.default,
+            role_limits: HashMap::new(),
+            voice_tags: false,
+            voice_tag_roles: Vec::new(),
+            idle_timeout: 10,
+            follow_author: false,
+            sticky: false,
+            channel_mode: ChannelMode::VoiceChat,
+            read_threads: true,
+            text_channels: ChannelList::default(),
+            voice_channels: ChannelList::default(),
+            read_nsfw: false,
+            announce_members: false,
+            welcome_members: false,
+            welcome_cooldown: 600,
+            admin_role: None,
+            transcript_token: None,
+            transcript_channel: None,
+            transcribe_speech: false,
+            voice_commands: false,
+            rate_limit: 5,
+            rate_limit_period: 30,
+            blacklist: Vec::new(),
+            suppress_duplicates: true,
+            count_duplicates: false,
+            speak_delay: 0.0,
+            phoneme_captions: false,
+            assigned_voices: HashMap::new(),
+            feeds: Vec::new(),
+            announcements: Vec::new(),
+            lottery_channel: None,
+            lottery_hour: 18,
+            lottery_entrants: Vec::new(),
+            golden_voice: None,
+            timezone: Tz::UTC,
+            compiled_filters: Arc::default(),
+        }
+    }
+}
+
+impl GuildConfig {
+    pub fn limits_for(&self, roles: &[u64]) -> Limits {
+        roles
+            .iter()
+            .filter_map(|role| self.role_limits.get(role))
+            .fold(self.limits, |limits, role_limits| Limits {
+                max_message_length: limits
+                    .max_message_length
+                    .max(role_limits.max_message_length),
+                max_duration: limits.max_duration.max(role_limits.max_duration),
+            })
+    }
+
+    pub fn allows_voice_tags(&self, roles: &[u64]) -> bool {
+        self.voice_tags
+            && (self.voice_tag_roles.is_empty()
+                || roles.iter().any(|role| self.voice_tag_roles.contains(role)))
+    }
+
+    pub fn preprocess_options(&self) -> PreprocessOptions<'_> {
+        PreprocessOptions {
+            read_link_domains: self.read_link_domains,
+            expand_slang: self.expand_slang,
+            slang: &self.slang,
+            emoji_limit: if self.read_emoji { self.emoji_limit } else { 0 },
+        }
+    }
+
+    fn compiled_filters(&self) -> &CompiledFilters {
+        self.compiled_filters.get_or_init(|| {
+            let compile = |filters: &[String]| {
+                filters
+                    .iter()
+                    .filter_map(|filter| {
+                        match RegexBuilder::new(filter).size_limit(1 << 16).build() {
+                            Ok(re) => Some(re),
+                            Err(e) => {
+                                warn!(error = ?e, "Invalid filter {}", filter);
+                                None
+                            }
+                        }
+                    })
+                    .collect()
+            };
+            CompiledFilters {
+                profanity: profanity::compile_words(&self.profanity_words),
+                allow: compile(&self.allow_filters),
+                deny: compile(&self.deny_filters),
+            }
+        })
+    }
+
+    /// Returns `None` when the message should be skipped entirely.
+    pub fn apply_profanity_filter(&self, text: &str) -> Option<String> {
+        profanity::apply_profanity_filter(
+            text,
+            &self.compiled_filters().profanity,
+            self.profanity_action,
+            &self.profanity_replacement,
+        )
+    }
+
+    pub fn has_ignored_prefix(&self, text: &str) -> bool {
+        let text = text.trim_start();
+        self.ignored_prefixes
+            .iter()
+            .any(|prefix| text.starts_with(prefix.as_str()))
+    }
+
+    pub fn is_filtered(&self, text: &str) -> bool {
+        let filters = self.compiled_filters();
+        if filters.deny.iter().any(|re| re.is_match(text)) {
+            return true;
+        }
+        // An allowlist of only invalid filters still blocks everything, as before
+        !self.allow_filters.is_empty() && !filters.allow.iter().any(|re| re.is_match(text))
+    }
+
+    pub fn get(&self, setting: &str) -> Option<String> {
+        Some(match setting {
+            "enabled" => self.enabled.to_string(),
+            "read_emoji" => self.read_emoji.to_string(),
+            "emoji_limit" => self.emoji_limit.to_string(),
+            "read_link_domains" => self.read_link_domains.to_string(),
+            "expand_slang" => self.expand_slang.to_string(),
+            "read_stickers" => self.read_stickers.to_string(),
+            "read_embeds" => self.read_embeds.to_string(),
+            "foreign_language" => self.foreign_language.to_string(),
+            "profanity_action" => self.profanity_action.to_string(),
+            "profanity_replacement" => self.profanity_replacement.clone(),
+            "ignored_prefixes" => self.ignored_prefixes.join(" "),
+            "ignore_bots" => self.ignore_bots.to_string(),
+            "ignore_webhooks" => self.ignore_webhooks.to_string(),
+            "ignored_applications" => format_id_list(&self.ignored_applications),
+            "max_message_length" => self.limits.max_message_length.to_string(),
+            "max_duration" => self.limits.max_duration.to_string(),
+            "voice_tags" => self.voice_tags.to_string(),
+            "voice_tag_roles" => format_id_list(&self.voice_tag_roles),
+            "idle_timeout" => self.idle_timeout.to_string(),
+            "follow_author" => self.follow_author.to_string(),
+            "sticky" => self.sticky.to_string(),
+            "channel_mode" => self.channel_mode.to_string(),
+            "read_threads" => self.read_threads.to_string(),
+            "read_nsfw" => self.read_nsfw.to_string(),
+            "announce_members" => self.announce_members.to_string(),
+            "welcome_members" => self.welcome_members.to_string(),
+            "welcome_cooldown" => self.welcome_cooldown.to_string(),
+            "admin_role" => match self.admin_role {
+                Some(role) => format!("<@&{}>", role),
+                None => "none".to_string(),
+            },
+            "transcript_channel" => match self.transcript_channel {
+                Some(channel) => format!("<#{}>", channel),
+                None => "none".to_string(),
+            },
+            "transcribe_speech" => self.transcribe_speech.to_string(),
+            "voice_commands" => self.voice_commands.to_string(),
+            "rate_limit" => self.rate_limit.to_string(),
+            "rate_limit_period" => self.rate_limit_period.to_string(),
+            "suppress_duplicates" => self.suppress_duplicates.to_string(),
+            "count_duplicates" => self.count_duplicates.to_string(),
+            "speak_delay" => self.speak_delay.to_string(),
+            "phoneme_captions" => self.phoneme_captions.to_string(),
+            "lottery_channel" => match self.lottery_channel {
+                Some(channel) => format!("<#{}>", channel),
+                None => "none".to_string(),
+            },
+            "lottery_hour" => self.lottery_hour.to_string(),
+            "timezone" => self.timezone.name().to_string(),
+            _ => return None,
+        })
+    }
+
+    pub fn set(&mut self, setting: &str, value: &str) -> Result<(), String> {
+        match setting {
+            "enabled" => self.enabled = parse_bool(value)?,
+            "read_emoji" => self.read_emoji = parse_bool(value)?,
+            "emoji_limit" => self.emoji_limit = parse_number(value)?,
+            "read_link_domains" => self.read_link_domains = parse_bool(value)?,
+            "expand_slang" => self.expand_slang = parse_bool(value)?,
+            "read_stickers" => self.read_stickers = parse_bool(value)?,
+            "read_embeds" => self.read_embeds = parse_bool(value)?,
+            "foreign_language" => self.foreign_language = value.parse()?,
+            "profanity_action" => self.profanity_action = value.parse()?,
+            "profanity_replacement" => self.profanity_replacement = value.trim().to_string(),
+            "ignored_prefixes" => self.ignored_prefixes = parse_list(value),
+            "ignore_bots" => self.ignore_bots = parse_bool(value)?,
+            "ignore_webhooks" => self.ignore_webhooks = parse_bool(value)?,
+            "ignored_applications" => self.ignored_applications = parse_id_list(value)?,
+            "max_message_length" => self.limits.max_message_length = parse_number(value)?,
+            "max_duration" => self.limits.max_duration = parse_number(value)?,
+            "voice_tags" => self.voice_tags = parse_bool(value)?,
+            "voice_tag_roles" => self.voice_tag_roles = parse_id_list(value)?,
+            "idle_timeout" => self.idle_timeout = parse_number(value)?,
+            "follow_author" => self.follow_author = parse_bool(value)?,
+            "sticky" => self.sticky = parse_bool(value)?,
+            "channel_mode" => self.channel_mode = value.parse()?,
+            "read_threads" => self.read_threads = parse_bool(value)?,
+            "read_nsfw" => self.read_nsfw = parse_bool(value)?,
+            "announce_members" => self.announce_members = parse_bool(value)?,
+            "welcome_members" => self.welcome_members = parse_bool(value)?,
+            "welcome_cooldown" => {
+                self.welcome_cooldown = parse_number::<u64>(value)?.min(24 * 60 * 60)
+            }
+            "admin_role" => self.admin_role = parse_id_list(value)?.first().copied(),
+            "transcript_channel" => {
+                self.transcript_channel = parse_id_list(value)?.first().copied()
+            }
+            "transcribe_speech" => self.transcribe_speech = parse_bool(value)?,
+            "voice_commands" => self.voice_commands = parse_bool(value)?,
+            "rate_limit" => self.rate_limit = parse_number(value)?,
+            "rate_limit_period" => self.rate_limit_period = parse_number::<u64>(value)?.max(1),
+            "suppress_duplicates" => self.suppress_duplicates = parse_bool(value)?,
+            "count_duplicates" => self.count_duplicates = parse_bool(value)?,
+            "speak_delay" => self.speak_delay = parse_number::<f64>(value)?.clamp(0.0, 10.0),
+            "phoneme_captions" => self.phoneme_captions = parse_bool(value)?,
+            "lottery_channel" => self.lottery_channel = parse_id_list(value)?.first().copied(),
+            "lottery_hour" => self.lottery_hour = parse_number::<u32>(value)?.min(23),
+            "timezone" => self.timezone = parse_timezone(value)?,
+            _ => return Err(format!("Unknown setting `{}`", setting)),
+        }
+        Ok(())
+    }
+}
+
+fn parse_bool(value: &str) -> Result<bool, String> {
+    match value.trim().to_lowercase().as_str() {
+        "true" | "on" | "yes" | "enable" | "enabled" => Ok(true),
+        "false" | "off" | "no" | "disable" | "disabled" => Ok(false),
+        _ => Err(format!("Expected on or off, got `{}`", value)),
+    }
+}
+
+fn parse_list(value: &str) -> Vec<String> {
+    if value.trim().eq_ignore_ascii_case("none") {
+        return Vec::new();
+    }
+    value.split_whitespace().map(String::from).collect()
+}
+
+pub fn parse_id_list(value: &str) -> Result<Vec<u64>, String> {
+    // Accept raw ids as well as pasted mentions like <@&123>
+    parse_list(value)
+        .iter()
+        .map(|id| parse_number(id.trim_matches(|c: char| !c.is_ascii_digit())))
+        .collect()
+}
+
+fn format_id_list(ids: &[u64]) -> String {
+    ids.iter()
+        .map(|id| id.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Parses offsets like `+2`, `-8`, `+5:30` or `UTC-3:30` into minutes.
+fn parse_timezone(value: &str) -> Result<Tz, String> {
+    Tz::from_str_insensitive(value.trim()).map_err(|_| {
+        format!(
+            "Expected a timezone like Europe/London or America/New_York, got `{}`",
+            value
+        )
+    })
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value
+        .trim()
+        .parse::<T>()
+        .map_err(|_| format!("Expected a number, got `{}`", value))
+}
+
+pub struct GuildConfigManager {
+    pub configs: Arc<Mutex<HashMap<u64, GuildConfig>>>,
+}
+
+impl GuildConfigManager {
+    pub fn new() -> Self {
+        GuildConfigManager {
+            configs: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub async fn get_config(&self, id: u64) -> GuildConfig {
+        let configs = self.configs.lock().await;
+        configs.get(&id).cloned().unwrap_or_default()
+    }
+
+    pub async fn update_config<T>(
+        &self,
+        id: u64,
+        update: impl FnOnce(&mut GuildConfig) -> T,
+    ) -> Result<T, BotError> {
+        debug!("Updating config for {}", id);
+        let mut configs = self.configs.lock().await;
+        let config = configs.entry(id).or_default();
+        let result = update(config);
+        config.compiled_filters = Arc::default();
+        drop(configs);
+        self.save_configs().await?;
+        Ok(result)
+    }
+
+    pub async fn remove_config(&self, id: u64) -> Result<(), BotError> {
+        info!("Removing config for {}", id);
+        if self.configs.lock().await.remove(&id).is_some() {
+            self.save_configs().await?;
+        }
+        Ok(())
+    }
+
+    pub async fn load_configs(&self) -> Result<(), BotError> {
+        debug!("Loading guild configs...");
+        let configs = error::read_json(config::get().data_path("guilds.json")).await?;
+        *self.configs.lock().await = configs;
+        Ok(())
+    }
+
+    pub async fn save_configs(&self) -> Result<(), BotError> {
+        debug!("Saving guild configs...");
+        let configs = self.configs.lock().await;
+        error::write_json(config::get().data_path("guilds.json"), &*configs).await
+    }
+}
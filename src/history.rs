@@ -0,0 +1,70 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    time::Duration,
+};
+
+use serenity::all::{ChannelId, GuildId, MessageId, Timestamp, UserId};
+use tokio::time::Instant;
+
+const HISTORY_LENGTH: usize = 10;
+
+#[derive(Debug, Clone)]
+pub struct ReadMessage {
+    pub channel_id: ChannelId,
+    pub message_id: MessageId,
+    pub sent_at: Timestamp,
+    pub text: String,
+    read_at: Instant,
+}
+
+/// The last few messages the bot read for each user, kept as evidence for `/report` after the
+/// audio is gone.
+pub struct ReadHistory {
+    messages: HashMap<(GuildId, UserId), VecDeque<ReadMessage>>,
+}
+
+impl ReadHistory {
+    pub fn new() -> Self {
+        ReadHistory {
+            messages: HashMap::new(),
+        }
+    }
+
+    pub fn record(
+        &mut self,
+        guild_id: GuildId,
+        user_id: UserId,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        sent_at: Timestamp,
+        text: String,
+    ) {
+        let messages = self.messages.entry((guild_id, user_id)).or_default();
+        if messages.len() == HISTORY_LENGTH {
+            messages.pop_front();
+        }
+        messages.push_back(ReadMessage {
+            channel_id,
+            message_id,
+            sent_at,
+            text,
+            read_at: Instant::now(),
+        });
+    }
+
+    pub fn recent(&self, guild_id: GuildId, user_id: UserId) -> Vec<ReadMessage> {
+        self.messages
+            .get(&(guild_id, user_id))
+            .map(|messages| messages.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Forgets users the bot hasn't read for `max_age`.
+    pub fn prune(&mut self, max_age: Duration) {
+        self.messages.retain(|_, messages| {
+            messages
+                .back()
+                .is_some_and(|message| message.read_at.elapsed() < max_age)
+        });
+    }
+}
@@ -0,0 +1,242 @@
+use std::{
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, Query, State,
+    },
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use dectalk_bot_core::dectalk::PAUL_VOICE;
+use serde::Deserialize;
+use serenity::{
+    all::{ChannelId, GuildId},
+    cache::Cache,
+    prelude::{RwLock, TypeMap},
+};
+use songbird::Songbird;
+use subtle::ConstantTimeEq;
+use tokio::{net::TcpListener, sync::broadcast::error::RecvError};
+use tracing::{debug, error, info};
+
+use crate::{
+    admin_api, audio::speak_in_guild, config, error::BotError, metrics, state::BotState, transcript,
+};
+
+static GATEWAY_CONNECTED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Clone)]
+pub struct HttpState {
+    pub songbird: Arc<Songbird>,
+    pub data: Arc<RwLock<TypeMap>>,
+    pub cache: Arc<Cache>,
+}
+
+pub async fn serve(addr: SocketAddr, state: HttpState) {
+    if let Err(e) = try_serve(addr, state).await {
+        error!(error = ?e, "HTTP server stopped");
+    }
+}
+
+async fn try_serve(addr: SocketAddr, state: HttpState) -> anyhow::Result<()> {
+    let app = Router::new()
+        .route("/metrics", get(get_metrics))
+        .route("/healthz", get(get_health))
+        .route("/readyz", get(get_ready))
+        .route("/transcript/{guild_id}", get(get_transcript))
+        .route("/captions/{guild_id}", get(get_captions))
+        .route("/speak", post(speak))
+        .nest("/admin", admin_api::router())
+        .with_state(state);
+
+    let listener = TcpListener::bind(addr).await?;
+    info!("Serving HTTP on {}", addr);
+    axum::serve(listener, app).await?;
+    Ok(())
+}
+
+async fn get_metrics(State(state): State<HttpState>) -> String {
+    metrics::gather(&state.songbird).await
+}
+
+async fn get_health() -> &'static str {
+    "ok"
+}
+
+/// Ready once the gateway is connected and DECtalk is still where the config says it is.
+async fn get_ready() -> (StatusCode, &'static str) {
+    if !GATEWAY_CONNECTED.load(Ordering::Relaxed) {
+        return (StatusCode::SERVICE_UNAVAILABLE, "gateway disconnected");
+    }
+    if !config::get().engine.say_path.is_file() {
+        return (StatusCode::SERVICE_UNAVAILABLE, "say binary missing");
+    }
+    (StatusCode::OK, "ok")
+}
+
+pub fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+}
+
+/// Compares in constant time, so response timing doesn't give the token away a byte at a time.
+pub fn token_matches(given: Option<&str>, expected: &str) -> bool {
+    given.is_some_and(|given| bool::from(given.as_bytes().ct_eq(expected.as_bytes())))
+}
+
+#[derive(Deserialize)]
+struct SpeakRequest {
+    guild: u64,
+    text: String,
+    /// Speak with this user's voice instead of Paul's.
+    voice: Option<u64>,
+    /// Join this voice channel first, otherwise the bot has to be in a call already.
+    channel: Option<u64>,
+}
+
+/// Reads text aloud for external systems like CI alerts or stream events. Needs
+/// `http.speak_token`, and follows the guild's filters and limits like any message.
+async fn speak(
+    State(state): State<HttpState>,
+    headers: HeaderMap,
+    Json(request): Json<SpeakRequest>,
+) -> (StatusCode, String) {
+    let speak_token = match &config::get().http.speak_token {
+        Some(speak_token) if !speak_token.is_empty() => speak_token.clone(),
+        _ => return (StatusCode::NOT_FOUND, String::new()),
+    };
+    if !token_matches(bearer_token(&headers), &speak_token) {
+        return (StatusCode::UNAUTHORIZED, String::new());
+    }
+    if request.guild == 0 || request.channel == Some(0) {
+        return (StatusCode::BAD_REQUEST, "Invalid ID".to_string());
+    }
+
+    let voice = match request.voice {
+        Some(user_id) => {
+            let bot = BotState::get(&state.data).await;
+            bot.voice_manager.get_voice(user_id).await
+        }
+        None => PAUL_VOICE,
+    };
+
+    match speak_in_guild(
+        &state.data,
+        &state.cache,
+        &state.songbird,
+        GuildId::new(request.guild),
+        request.channel.map(ChannelId::new),
+        "API",
+        &request.text,
+        &voice,
+    )
+    .await
+    {
+        Ok(()) => (StatusCode::ACCEPTED, "queued".to_string()),
+        Err(e @ (BotError::ShuttingDown | BotError::Panicked)) => {
+            (StatusCode::SERVICE_UNAVAILABLE, e.to_string())
+        }
+        Err(e @ (BotError::NotConnected | BotError::Busy)) => (StatusCode::CONFLICT, e.to_string()),
+        Err(e) if e.is_expected() => (StatusCode::UNPROCESSABLE_ENTITY, e.to_string()),
+        Err(e) => {
+            error!(error = ?e, "Failed to speak through the API");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct TranscriptQuery {
+    token: String,
+}
+
+/// Streams the guild's transcript events as JSON over a WebSocket. The token goes in the query
+/// string because OBS browser sources can't set headers.
+async fn get_transcript(
+    ws: WebSocketUpgrade,
+    State(state): State<HttpState>,
+    Path(guild_id): Path<u64>,
+    Query(query): Query<TranscriptQuery>,
+) -> Response {
+    if !transcript_token_matches(&state.data, guild_id, &query.token).await {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+
+    ws.on_upgrade(move |socket| stream_transcript(socket, state.data, guild_id, query.token))
+}
+
+/// The current caption as plain text, for overlays that poll rather than hold a WebSocket open.
+async fn get_captions(
+    State(state): State<HttpState>,
+    Path(guild_id): Path<u64>,
+    Query(query): Query<TranscriptQuery>,
+) -> Response {
+    if !transcript_token_matches(&state.data, guild_id, &query.token).await {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+
+    transcript::current(guild_id).into_response()
+}
+
+async fn transcript_token_matches(data: &RwLock<TypeMap>, guild_id: u64, token: &str) -> bool {
+    let bot = BotState::get(data).await;
+    let config = bot.guild_configs.get_config(guild_id).await;
+    config
+        .transcript_token
+        .is_some_and(|transcript_token| token_matches(Some(token), &transcript_token))
+}
+
+/// Checks the token again before every event, so rotating or clearing it cuts off sockets that
+/// were opened with the old one.
+async fn stream_transcript(
+    mut socket: WebSocket,
+    data: Arc<RwLock<TypeMap>>,
+    guild_id: u64,
+    token: String,
+) {
+    let mut events = transcript::subscribe();
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(skipped)) => {
+                debug!("Transcript stream skipped {} events", skipped);
+                continue;
+            }
+            Err(RecvError::Closed) => return,
+        };
+        if event.guild_id() != guild_id {
+            continue;
+        }
+        if !transcript_token_matches(&data, guild_id, &token).await {
+            debug!("Closing a transcript stream after its token changed");
+            let _ = socket.send(Message::Close(None)).await;
+            return;
+        }
+
+        let event = match serde_json::to_string(&event) {
+            Ok(event) => event,
+            Err(e) => {
+                error!(error = ?e, "Failed to serialize transcript event");
+                continue;
+            }
+        };
+        if socket.send(Message::Text(event.into())).await.is_err() {
+            return;
+        }
+    }
+}
+
+pub fn set_gateway_connected(connected: bool) {
+    GATEWAY_CONNECTED.store(connected, Ordering::Relaxed);
+}
@@ -0,0 +1,47 @@
+use std::{sync::Arc, time::Duration};
+
+use serenity::prelude::{RwLock, TypeMap};
+use songbird::Songbird;
+use tokio::time::{self, Instant};
+use tracing::{error, info};
+
+use crate::state::BotState;
+
+pub async fn leave_idle_channels(data: Arc<RwLock<TypeMap>>, manager: Arc<Songbird>) {
+    let mut interval = time::interval(Duration::from_secs(30));
+    loop {
+        interval.tick().await;
+
+        let state = BotState::get(&data).await;
+
+        let played = state.last_played.lock().await.clone();
+        for (guild_id, played_at) in played {
+            let config = state.guild_configs.get_config(guild_id.get()).await;
+            if config.idle_timeout == 0
+                || played_at.elapsed() < Duration::from_secs(config.idle_timeout * 60)
+                || (config.sticky && state.bindings.lock().await.contains_key(&guild_id))
+            {
+                continue;
+            }
+
+            info!("Leaving idle channel in {}", guild_id);
+            if manager.get(guild_id).is_some() {
+                if let Err(e) = manager.remove(guild_id).await {
+                    error!(error = ?e, "Failed to leave idle channel");
+                }
+            }
+
+            state.last_played.lock().await.remove(&guild_id);
+            state.guild_users.lock().await.remove(&guild_id);
+        }
+    }
+}
+
+pub async fn mark_played(data: &RwLock<TypeMap>, guild_id: serenity::all::GuildId) {
+    let state = BotState::get(data).await;
+    state
+        .last_played
+        .lock()
+        .await
+        .insert(guild_id, Instant::now());
+}
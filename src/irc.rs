@@ -0,0 +1,89 @@
+use anyhow::bail;
+use dectalk_bot_core::dectalk::DectalkVoice;
+use futures_util::StreamExt;
+use irc::client::prelude::{Client, Command, Config as ClientConfig};
+use serenity::{
+    all::{ChannelId, GuildId},
+    async_trait,
+};
+use tracing::{debug, error, info};
+
+use crate::{
+    config::IrcConfig,
+    frontend::{self, Frontend, Speaker},
+};
+
+/// Reads the configured IRC channel into the guild's voice channel.
+pub struct IrcFrontend {
+    irc: IrcConfig,
+}
+
+impl IrcFrontend {
+    pub fn new(irc: IrcConfig) -> Self {
+        IrcFrontend { irc }
+    }
+}
+
+#[async_trait]
+impl Frontend for IrcFrontend {
+    fn name(&self) -> &'static str {
+        "IRC"
+    }
+
+    async fn run(&mut self, speaker: &Speaker) -> anyhow::Result<()> {
+        bridge(&self.irc, speaker).await
+    }
+}
+
+async fn bridge(irc: &IrcConfig, speaker: &Speaker) -> anyhow::Result<()> {
+    let mut client = Client::from_config(ClientConfig {
+        nickname: Some(irc.nickname.clone()),
+        server: Some(irc.server.clone()),
+        port: irc.port,
+        use_tls: Some(irc.tls),
+        channels: vec![irc.channel.clone()],
+        ..ClientConfig::default()
+    })
+    .await?;
+    client.identify()?;
+    info!("Connected to IRC at {}", irc.server);
+
+    let guild_id = GuildId::new(irc.guild);
+    let channel_id = irc.voice_channel.map(ChannelId::new);
+    let mut stream = client.stream()?;
+    while let Some(message) = stream.next().await.transpose()? {
+        let (target, text) = match &message.command {
+            Command::PRIVMSG(target, text) => (target, text),
+            _ => continue,
+        };
+        let nickname = match message.source_nickname() {
+            Some(nickname) => nickname,
+            None => continue,
+        };
+        if !target.eq_ignore_ascii_case(&irc.channel) {
+            continue;
+        }
+
+        // `/me` arrives as CTCP ACTION, other CTCP requests aren't meant to be read
+        let text = match text.strip_prefix('\u{1}') {
+            Some(ctcp) => match ctcp.strip_prefix("ACTION ") {
+                Some(action) => format!("{} {}", nickname, action.trim_end_matches('\u{1}')),
+                None => continue,
+            },
+            None => text.clone(),
+        };
+
+        let voice = DectalkVoice::generate(frontend::name_id(nickname), 0);
+        match speaker
+            .speak(guild_id, channel_id, nickname, &text, &voice)
+            .await
+        {
+            Ok(()) => {}
+            Err(e) if e.is_expected() => {
+                debug!(error = ?e, "Skipping IRC message from {}", nickname)
+            }
+            Err(e) => error!(error = ?e, "Failed to read IRC message from {}", nickname),
+        }
+    }
+    bail!("Connection closed")
+}
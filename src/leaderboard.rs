@@ -0,0 +1,112 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use tracing::debug;
+
+use crate::{
+    config,
+    error::{self, BotError},
+    reminders::unix_now,
+};
+
+const DAY: u64 = 24 * 60 * 60;
+/// Days of history kept, enough for the monthly board.
+pub const MAX_DAYS: u64 = 30;
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct Tally {
+    pub messages: u64,
+    pub seconds: f64,
+}
+
+/// Each user's tally per day, keyed by days since the Unix epoch.
+type GuildDays = BTreeMap<u64, HashMap<u64, Tally>>;
+
+/// How much each user had read aloud per guild and day, for `/leaderboard`. Like the stats it's
+/// updated on every message, so it's saved by the maintenance task and on shutdown, which also
+/// drops days older than `MAX_DAYS`.
+pub struct Leaderboard {
+    pub days: Arc<Mutex<HashMap<u64, GuildDays>>>,
+    dirty: AtomicBool,
+}
+
+impl Leaderboard {
+    pub fn new() -> Self {
+        Leaderboard {
+            days: Arc::new(Mutex::new(HashMap::new())),
+            dirty: AtomicBool::new(false),
+        }
+    }
+
+    pub async fn record(&self, guild_id: u64, user_id: u64, seconds: f64) {
+        let mut days = self.days.lock().await;
+        let tally = days
+            .entry(guild_id)
+            .or_default()
+            .entry(unix_now() / DAY)
+            .or_default()
+            .entry(user_id)
+            .or_default();
+        tally.messages += 1;
+        tally.seconds += seconds;
+        self.dirty.store(true, Ordering::Relaxed);
+    }
+
+    /// The users who were read the longest over the last `days` days, longest first.
+    pub async fn top(&self, guild_id: u64, days: u64, count: usize) -> Vec<(u64, Tally)> {
+        let since = (unix_now() / DAY).saturating_sub(days - 1);
+        let mut totals: HashMap<u64, Tally> = HashMap::new();
+        if let Some(guild_days) = self.days.lock().await.get(&guild_id) {
+            for tallies in guild_days.range(since..).map(|(_, tallies)| tallies) {
+                for (user_id, tally) in tallies {
+                    let total = totals.entry(*user_id).or_default();
+                    total.messages += tally.messages;
+                    total.seconds += tally.seconds;
+                }
+            }
+        }
+
+        let mut totals = totals.into_iter().collect::<Vec<_>>();
+        totals.sort_by(|(_, a), (_, b)| b.seconds.total_cmp(&a.seconds));
+        totals.truncate(count);
+        totals
+    }
+
+    /// Drops days that fell off the monthly board.
+    pub async fn rotate(&self) {
+        let oldest = (unix_now() / DAY).saturating_sub(MAX_DAYS - 1);
+        let mut days = self.days.lock().await;
+        for guild_days in days.values_mut() {
+            let before = guild_days.len();
+            *guild_days = guild_days.split_off(&oldest);
+            if guild_days.len() != before {
+                self.dirty.store(true, Ordering::Relaxed);
+            }
+        }
+        days.retain(|_, guild_days| !guild_days.is_empty());
+    }
+
+    pub async fn load_leaderboard(&self) -> Result<(), BotError> {
+        debug!("Loading leaderboard...");
+        let days = error::read_json(config::get().data_path("leaderboard.json")).await?;
+        *self.days.lock().await = days;
+        Ok(())
+    }
+
+    /// Writes the tallies if anything changed since the last save.
+    pub async fn save_leaderboard(&self) -> Result<(), BotError> {
+        if !self.dirty.swap(false, Ordering::Relaxed) {
+            return Ok(());
+        }
+        debug!("Saving leaderboard...");
+        let days = self.days.lock().await;
+        error::write_json(config::get().data_path("leaderboard.json"), &*days).await
+    }
+}
@@ -0,0 +1,127 @@
+use tracing_appender::{
+    non_blocking::WorkerGuard,
+    rolling::{RollingFileAppender, Rotation},
+};
+use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
+
+use crate::{
+    config::{LogRotation, LoggingConfig},
+    ops,
+};
+
+/// Flushes buffered log files and pending trace exports when dropped, so keep it alive until
+/// shutdown.
+pub struct LogGuard {
+    _file: Option<WorkerGuard>,
+    #[cfg(feature = "otel")]
+    tracer_provider: Option<opentelemetry_sdk::trace::SdkTracerProvider>,
+}
+
+impl Drop for LogGuard {
+    fn drop(&mut self) {
+        #[cfg(feature = "otel")]
+        if let Some(tracer_provider) = &self.tracer_provider {
+            if let Err(e) = tracer_provider.shutdown() {
+                eprintln!("Failed to flush traces: {}", e);
+            }
+        }
+    }
+}
+
+/// Logs to stdout, to rotating files when `logging.directory` is set, and exports spans over
+/// OTLP when built with the `otel` feature and `logging.otlp_endpoint` is set.
+pub fn init(config: &LoggingConfig) -> anyhow::Result<LogGuard> {
+    let filter =
+        || EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(&config.level));
+    let stdout = fmt::layer().with_filter(filter());
+
+    let mut file_guard = None;
+    let file = match &config.directory {
+        Some(directory) => {
+            let rotation = match config.rotation {
+                LogRotation::Hourly => Rotation::HOURLY,
+                LogRotation::Daily => Rotation::DAILY,
+                LogRotation::Never => Rotation::NEVER,
+            };
+            let appender = RollingFileAppender::builder()
+                .rotation(rotation)
+                .filename_prefix("dectalk")
+                .filename_suffix("log")
+                .max_log_files(config.max_files.max(1))
+                .build(directory)?;
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            file_guard = Some(guard);
+            Some(
+                fmt::layer()
+                    .with_ansi(false)
+                    .with_writer(writer)
+                    .with_filter(filter()),
+            )
+        }
+        None => None,
+    };
+
+    #[cfg(feature = "otel")]
+    {
+        let tracer_provider = match &config.otlp_endpoint {
+            Some(endpoint) => Some(otel::tracer_provider(endpoint)?),
+            None => None,
+        };
+        let traces = tracer_provider
+            .as_ref()
+            .map(|tracer_provider| otel::layer(tracer_provider).with_filter(filter()));
+        tracing_subscriber::registry()
+            .with(stdout)
+            .with(file)
+            .with(ops::layer())
+            .with(traces)
+            .try_init()?;
+        Ok(LogGuard {
+            _file: file_guard,
+            tracer_provider,
+        })
+    }
+
+    #[cfg(not(feature = "otel"))]
+    {
+        tracing_subscriber::registry()
+            .with(stdout)
+            .with(file)
+            .with(ops::layer())
+            .try_init()?;
+        if config.otlp_endpoint.is_some() {
+            tracing::warn!("Ignoring logging.otlp_endpoint, rebuild with --features otel");
+        }
+        Ok(LogGuard { _file: file_guard })
+    }
+}
+
+#[cfg(feature = "otel")]
+mod otel {
+    use opentelemetry::trace::TracerProvider;
+    use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+    use opentelemetry_sdk::{trace::SdkTracerProvider, Resource};
+    use tracing::Subscriber;
+    use tracing_opentelemetry::OpenTelemetryLayer;
+    use tracing_subscriber::registry::LookupSpan;
+
+    pub fn tracer_provider(endpoint: &str) -> anyhow::Result<SdkTracerProvider> {
+        let exporter = SpanExporter::builder()
+            .with_tonic()
+            .with_endpoint(endpoint)
+            .build()?;
+        Ok(SdkTracerProvider::builder()
+            .with_resource(Resource::builder().with_service_name("dectalk").build())
+            .with_batch_exporter(exporter)
+            .build())
+    }
+
+    pub fn layer<S>(
+        tracer_provider: &SdkTracerProvider,
+    ) -> OpenTelemetryLayer<S, opentelemetry_sdk::trace::Tracer>
+    where
+        S: Subscriber + for<'span> LookupSpan<'span>,
+    {
+        tracing_opentelemetry::layer().with_tracer(tracer_provider.tracer("dectalk"))
+    }
+}
@@ -0,0 +1,139 @@
+use std::sync::Arc;
+
+use chrono::Timelike;
+use rand::seq::IndexedRandom;
+use serenity::{
+    all::{ChannelId, CreateAllowedMentions, CreateMessage, GuildId},
+    cache::Cache,
+    http::Http,
+    prelude::{RwLock, TypeMap},
+};
+use songbird::Songbird;
+use tracing::{debug, error, info, warn};
+
+use dectalk_bot_core::dectalk::{DectalkVoice, PAUL_VOICE};
+
+use crate::{
+    audio::speak_in_guild,
+    cron,
+    guild_config::{GoldenVoice, GuildConfig},
+    reminders::unix_now,
+    state::BotState,
+};
+
+const GOLDEN_DURATION: u64 = 24 * 60 * 60;
+
+/// The lottery winner's voice for the day: their own, but rich, lively and assertive.
+pub fn golden(voice: &DectalkVoice) -> DectalkVoice {
+    voice
+        .to_builder()
+        .richness(100)
+        .smoothness(0)
+        .breathiness(0)
+        .assertiveness(100)
+        .pitch_range(200)
+        .build()
+        .unwrap_or_else(|_| voice.clone())
+}
+
+/// Applies the guild's temporary voice modifiers to a user's voice.
+pub fn voice_in_guild(config: &GuildConfig, user_id: u64, voice: DectalkVoice) -> DectalkVoice {
+    match config.golden_voice {
+        Some(golden_voice) if golden_voice.user == user_id && golden_voice.until > unix_now() => {
+            golden(&voice)
+        }
+        _ => voice,
+    }
+}
+
+/// Draws each opted-in guild's lottery at the start of its `lottery_hour`, giving one random
+/// entrant the golden voice for a day.
+pub async fn run_lotteries(
+    http: Arc<Http>,
+    cache: Arc<Cache>,
+    data: Arc<RwLock<TypeMap>>,
+    songbird: Arc<Songbird>,
+) {
+    let state = BotState::get(&data).await;
+
+    loop {
+        let minute = cron::wait_for_next_minute().await;
+        if minute.minute() != 0 {
+            continue;
+        }
+
+        let guilds = state
+            .guild_configs
+            .configs
+            .lock()
+            .await
+            .iter()
+            .filter(|(_, config)| {
+                config.lottery_channel.is_some()
+                    && config.lottery_hour == minute.hour()
+                    && !config.lottery_entrants.is_empty()
+            })
+            .map(|(guild_id, _)| *guild_id)
+            .collect::<Vec<_>>();
+
+        for guild_id in guilds {
+            let draw = state
+                .guild_configs
+                .update_config(guild_id, |config| {
+                    let winner = *config.lottery_entrants.choose(&mut rand::rng())?;
+                    config.golden_voice = Some(GoldenVoice {
+                        user: winner,
+                        until: unix_now() + GOLDEN_DURATION,
+                    });
+                    Some((winner, config.lottery_channel))
+                })
+                .await;
+            let (winner, channel) = match draw {
+                Ok(Some(draw)) => draw,
+                Ok(None) => continue,
+                Err(e) => {
+                    error!(error = ?e, "Failed to save lottery winner");
+                    continue;
+                }
+            };
+            info!("User {} won the lottery in {}", winner, guild_id);
+
+            if let Some(channel) = channel {
+                let message = CreateMessage::new()
+                    .content(format!(
+                        "🏆 <@{}> won today's voice lottery and gets a golden voice for 24 hours!",
+                        winner
+                    ))
+                    .allowed_mentions(CreateAllowedMentions::new().users([winner]));
+                if let Err(e) = ChannelId::new(channel).send_message(&http, message).await {
+                    warn!(error = ?e, "Failed to post lottery winner");
+                }
+            }
+
+            let guild_id = GuildId::new(guild_id);
+            if songbird.get(guild_id).is_none() {
+                continue;
+            }
+            let name = match http.get_member(guild_id, winner.into()).await {
+                Ok(member) => member.display_name().to_string(),
+                Err(_) => "someone".to_string(),
+            };
+            match speak_in_guild(
+                &data,
+                &cache,
+                &songbird,
+                guild_id,
+                None,
+                "Lottery",
+                &format!("Today's voice lottery goes to {}!", name),
+                &PAUL_VOICE,
+            )
+            .await
+            {
+                Ok(()) => {}
+                Err(e) if e.is_expected() => debug!(error = ?e, "Not reading lottery winner"),
+                Err(e) => error!(error = ?e, "Failed to read lottery winner"),
+            }
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use std::{collections::HashMap, sync::Arc};
+
+use tokio::sync::Mutex;
+use tracing::debug;
+
+use crate::{
+    config,
+    error::{self, BotError},
+};
+
+pub const MAX_USER_MACROS: usize = 25;
+pub const MAX_GUILD_MACROS: usize = 100;
+pub const MAX_PHRASE_LENGTH: usize = 300;
+
+/// Each user's personal `/macro` triggers, which follow them across servers.
+pub struct UserMacros {
+    pub macros: Arc<Mutex<HashMap<u64, HashMap<String, String>>>>,
+}
+
+impl UserMacros {
+    pub fn new() -> Self {
+        UserMacros {
+            macros: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub async fn get(&self, user_id: u64) -> HashMap<String, String> {
+        self.macros
+            .lock()
+            .await
+            .get(&user_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Returns false if the user already has `MAX_USER_MACROS` other triggers.
+    pub async fn add(
+        &self,
+        user_id: u64,
+        trigger: String,
+        phrase: String,
+    ) -> Result<bool, BotError> {
+        {
+            let mut macros = self.macros.lock().await;
+            let user_macros = macros.entry(user_id).or_default();
+            if user_macros.len() >= MAX_USER_MACROS && !user_macros.contains_key(&trigger) {
+                return Ok(false);
+            }
+            user_macros.insert(trigger, phrase);
+        }
+        self.save_macros().await?;
+        Ok(true)
+    }
+
+    /// Returns whether the trigger existed.
+    pub async fn remove(&self, user_id: u64, trigger: &str) -> Result<bool, BotError> {
+        {
+            let mut macros = self.macros.lock().await;
+            let Some(user_macros) = macros.get_mut(&user_id) else {
+                return Ok(false);
+            };
+            if user_macros.remove(trigger).is_none() {
+                return Ok(false);
+            }
+            if user_macros.is_empty() {
+                macros.remove(&user_id);
+            }
+        }
+        self.save_macros().await?;
+        Ok(true)
+    }
+
+    pub async fn load_macros(&self) -> Result<(), BotError> {
+        debug!("Loading macros...");
+        let macros = error::read_json(config::get().data_path("macros.json")).await?;
+        *self.macros.lock().await = macros;
+        Ok(())
+    }
+
+    pub async fn save_macros(&self) -> Result<(), BotError> {
+        debug!("Saving macros...");
+        let macros = self.macros.lock().await;
+        error::write_json(config::get().data_path("macros.json"), &*macros).await
+    }
+}
@@ -1,406 +1,555 @@
-use std::{
-    collections::{HashMap, HashSet},
-    env,
-    error::Error,
-    io::Cursor,
-    sync::Arc,
+use std::{path::Path, sync::Arc};
+
+use anyhow::{bail, Context as _};
+use audio::{synthesize, ConfiguredEngine};
+use clap::Parser;
+use cli::{Cli, CliCommand};
+use coordinator::Coordinator;
+use dectalk_bot_core::{
+    audio::normalize_wav_volume,
+    dectalk::{DectalkVoice, Language, PAUL_VOICE},
+    pronunciation::PronunciationMap,
 };
-
-use dectalk::PAUL_VOICE;
-use regex::Regex;
-use serenity::{
-    all::{GuildId, UserId, VoiceState},
-    async_trait,
-    client::{Client, Context, EventHandler},
-    model::{channel::Message, gateway::Ready},
-    prelude::{GatewayIntents, TypeMapKey},
-};
-use songbird::{input::Input, tracks::Track, SerenityInit};
+use error::BotError;
+use guild_config::GuildConfig;
+use preprocess::process_message;
+use serenity::{client::Client, prelude::GatewayIntents};
+use songbird::{SerenityInit, Songbird};
+use state::{BotState, BotStateKey};
 use tokio::{
-    fs::{self, File},
-    io::AsyncReadExt,
+    fs,
+    io::{AsyncBufReadExt, BufReader},
     signal,
-    sync::Mutex,
 };
+use tracing::{error, info};
 use voice_manager::VoiceManager;
 
-mod dectalk;
+mod admin_api;
+mod announcements;
+mod audio;
+mod blacklist;
+mod cli;
+mod clips;
+mod commands;
+mod config;
+mod coordinator;
+mod cron;
+mod database;
+mod duels;
+mod duplicates;
+mod error;
+mod events;
+mod feeds;
+// Only the optional chat platforms use this
+#[cfg(any(feature = "irc", feature = "matrix", feature = "mqtt"))]
+mod frontend;
+mod guild_config;
+mod history;
+mod http;
+mod idle;
+#[cfg(feature = "irc")]
+mod irc;
+mod leaderboard;
+mod logging;
+mod lottery;
+mod macros;
+mod maintenance;
+#[cfg(feature = "matrix")]
+mod matrix;
+mod metrics;
+mod migrations;
+mod moderation;
+#[cfg(feature = "mqtt")]
+mod mqtt;
+mod ops;
+mod opt_outs;
+mod pipeline;
+mod preprocess;
+mod rate_limit;
+mod reconnect;
+mod reminders;
+mod sessions;
+mod shutdown;
+mod songs;
+mod soundboard;
+mod state;
+mod stats;
+#[cfg(feature = "stt")]
+mod stt;
+mod systemd;
+#[cfg(feature = "telegram")]
+mod telegram;
+mod transcript;
 mod voice_manager;
 
-struct VoiceManagerKey;
-
-impl TypeMapKey for VoiceManagerKey {
-    type Value = Arc<VoiceManager>;
-}
-
-struct GuildUsersKey;
-
-impl TypeMapKey for GuildUsersKey {
-    type Value = Arc<Mutex<HashMap<GuildId, HashSet<UserId>>>>;
-}
-
-struct Handler;
-
-#[async_trait]
-impl EventHandler for Handler {
-    async fn ready(&self, _: Context, ready: Ready) {
-        println!("{} is connected!", ready.user.name);
-    }
-
-    async fn message(&self, ctx: Context, new_message: Message) {
-        let author_id = new_message.author.id;
-        let guild_id = match new_message.guild_id {
-            Some(guild_id) => guild_id,
-            None => {
-                eprintln!("Failed to get guild id");
-                return;
-            }
-        };
-
-        let is_owner = author_id.get()
-            == env::var("DISCORD_OWNER")
-                .expect("Expected a owner in the environment")
-                .parse::<u64>()
-                .expect("Expected the owner to be a u64");
-
-        let voice_manager = match ctx.data.read().await.get::<VoiceManagerKey>() {
-            Some(voice_manager) => voice_manager.clone(),
-            None => {
-                eprintln!("Failed to get voice manager");
-                return;
-            }
-        };
-
-        let requested_roll = get_requested_roll(&new_message.content);
-        if let Some(roll) = requested_roll {
-            println!("Setting roll for {}: {}", author_id, roll);
-            if let Err(e) = voice_manager.set_roll(author_id.get(), roll).await {
-                eprintln!("Failed to set roll: {:?}", e);
-                return;
-            }
-        }
-
-        if !is_owner && new_message.content.len() > 256 {
-            return;
-        }
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    dotenv::dotenv().ok();
 
-        let content = remove_requested_roll(&process_message(&new_message.content));
-        if content.is_empty() {
-            return;
+    let cli = Cli::parse();
+    let config = match config::Config::load() {
+        Ok(config) => config,
+        Err(e) => bail!("Invalid configuration: {}", e),
+    };
+    let _log_guard = logging::init(&config.logging)?;
+    config::init(config);
+
+    match cli.command.unwrap_or(CliCommand::Run) {
+        CliCommand::Run => run().await,
+        CliCommand::Say {
+            text,
+            output,
+            user,
+            roll,
+        } => say(&text, &output, user, roll).await,
+        CliCommand::Local {
+            input,
+            output_dir,
+            player,
+            user,
+            roll,
+        } => local(input.as_deref(), output_dir.as_deref(), &player, user, roll).await,
+        CliCommand::Voice { user_id, roll } => {
+            let voice = load_voice(user_id, roll).await;
+            println!("{:#?}", voice);
+            Ok(())
         }
+        CliCommand::Migrate => migrate().await,
+    }
+}
 
-        let user_channel_id = {
-            let guild = match new_message.guild(&ctx.cache) {
-                Some(guild) => guild,
-                None => {
-                    eprintln!("Failed to get guild");
-                    return;
-                }
-            };
-
-            let voice_states = match guild.voice_states.get(&author_id) {
-                Some(voice_states) => voice_states,
-                None => {
-                    eprintln!("Failed to get voice states");
-                    return;
-                }
-            };
-
-            match voice_states.channel_id {
-                Some(channel_id) => channel_id,
-                None => {
-                    eprintln!("Failed to get channel id");
-                    return;
-                }
-            }
-        };
-
-        let channel_id = new_message.channel_id;
-        if user_channel_id != channel_id {
-            return;
+async fn run() -> anyhow::Result<()> {
+    let problems = config::get().validate().await;
+    if !problems.is_empty() {
+        for problem in &problems {
+            error!("{}", problem);
         }
+        bail!("Found {} problems, not starting", problems.len());
+    }
 
-        println!("Found valid message from {}", author_id);
-
-        let manager = match songbird::get(&ctx).await {
-            Some(manager) => manager,
-            None => {
-                eprintln!("Failed to get songbird manager");
-                return;
-            }
-        };
-
-        let handler_lock = manager.get_or_insert(guild_id);
-        let mut handler = handler_lock.lock().await;
+    migrations::run_migrations()?;
 
-        if let Err(e) = handler.join(channel_id).await {
-            eprintln!("Failed to join channel: {:?}", e);
-            return;
-        }
-
-        let voice = voice_manager.get_voice(author_id.get()).await;
-        let tts_path =
-            match dectalk::tts(&content, if is_owner { &PAUL_VOICE } else { &voice }).await {
-                Ok(tts_path) => tts_path,
-                Err(e) => {
-                    eprintln!("Failed to generate TTS: {:?}", e);
-                    return;
-                }
-            };
-
-        let mut tts_file = match File::open(&tts_path).await {
-            Ok(tts_file) => tts_file,
+    let pronunciations =
+        match PronunciationMap::load(&config::get().data_path("pronunciations.json")).await {
+            Ok(pronunciations) => pronunciations,
             Err(e) => {
-                eprintln!("Failed to open TTS file: {:?}", e);
-                return;
-            }
-        };
-
-        let mut tts_bytes = Vec::new();
-        if let Err(e) = tts_file.read_to_end(&mut tts_bytes).await {
-            eprintln!("Failed to read TTS file: {:?}", e);
-            return;
-        }
-
-        if let Err(e) = fs::remove_file(&tts_path).await {
-            eprintln!("Failed to remove TTS file: {:?}", e);
-            return;
-        }
-
-        let duration = match get_wav_duration(&tts_bytes).await {
-            Some(duration) => duration,
-            None => {
-                eprintln!("Failed to get duration");
-                return;
+                error!(error = ?e, "Failed to load pronunciations");
+                PronunciationMap::new()
             }
         };
 
-        if !is_owner && duration > 15.0 {
-            eprintln!("TTS duration is too long");
-            return;
+    // The main bot is instance 0, each helper token adds another
+    let songbirds = (0..=config::get().helper_tokens.len())
+        .map(|_| new_songbird())
+        .collect::<Vec<_>>();
+    let songbird = songbirds[0].clone();
+    let coordinator = Arc::new(Coordinator::new(songbirds.clone()));
+    let state = BotState::new(pronunciations, Arc::new(ConfiguredEngine), coordinator);
+    match state.voice_manager.load_rolls().await {
+        Ok(_) => {}
+        Err(e) if e.is_not_found() => info!("No rolls saved yet"),
+        Err(e) => {
+            error!(error = ?e, "Failed to load rolls");
         }
+    }
 
-        let guild_users = match ctx.data.read().await.get::<GuildUsersKey>() {
-            Some(guild_users) => guild_users.clone(),
-            None => {
-                eprintln!("Failed to get guild users");
-                return;
-            }
-        };
-
-        let normalized_tts_bytes = match normalize_wav_volume(&tts_bytes) {
-            Ok(normalized_tts_bytes) => normalized_tts_bytes,
-            Err(e) => {
-                eprintln!("Failed to normalize TTS volume: {:?}", e);
-                return;
-            }
-        };
+    match state.voice_manager.load_presets().await {
+        Ok(_) => {}
+        Err(e) if e.is_not_found() => info!("No presets saved yet"),
+        Err(e) => error!(error = ?e, "Failed to load presets"),
+    }
 
-        let mut guild_users = guild_users.lock().await;
-        guild_users
-            .entry(guild_id)
-            .or_insert_with(HashSet::new)
-            .insert(author_id);
+    match state.guild_configs.load_configs().await {
+        Ok(_) => {}
+        Err(e) if e.is_not_found() => info!("No guild configs saved yet"),
+        Err(e) => error!(error = ?e, "Failed to load guild configs"),
+    }
 
-        handler.play(Track::from(Input::from(normalized_tts_bytes)).volume(0.25));
+    match state.blacklist.load_blacklist().await {
+        Ok(_) => {}
+        Err(e) if e.is_not_found() => info!("No blacklist saved yet"),
+        Err(e) => error!(error = ?e, "Failed to load blacklist"),
     }
 
-    async fn voice_state_update(&self, ctx: Context, _old: Option<VoiceState>, new: VoiceState) {
-        let guild_id = match new.guild_id {
-            Some(guild_id) => guild_id,
-            None => {
-                eprintln!("Failed to get guild id");
-                return;
-            }
-        };
+    match state.stats.load_stats().await {
+        Ok(_) => {}
+        Err(e) if e.is_not_found() => info!("No stats saved yet"),
+        Err(e) => error!(error = ?e, "Failed to load stats"),
+    }
 
-        let guild_users = match ctx.data.read().await.get::<GuildUsersKey>() {
-            Some(guild_users) => guild_users.clone(),
-            None => {
-                eprintln!("Failed to get guild users");
-                return;
-            }
-        };
-        let mut guild_users = guild_users.lock().await;
+    match state.reminders.load_reminders().await {
+        Ok(_) => {}
+        Err(e) if e.is_not_found() => info!("No reminders saved yet"),
+        Err(e) => error!(error = ?e, "Failed to load reminders"),
+    }
 
-        if new.channel_id.is_none() {
-            guild_users
-                .entry(guild_id)
-                .or_insert_with(HashSet::new)
-                .remove(&new.user_id);
-        }
+    match state.duels.load_duels().await {
+        Ok(_) => {}
+        Err(e) if e.is_not_found() => info!("No duel records saved yet"),
+        Err(e) => error!(error = ?e, "Failed to load duel records"),
+    }
 
-        if guild_users
-            .entry(guild_id)
-            .or_insert_with(HashSet::new)
-            .is_empty()
-        {
-            let manager = match songbird::get(&ctx).await {
-                Some(manager) => manager,
-                None => {
-                    eprintln!("Failed to get songbird manager");
-                    return;
-                }
-            };
-
-            let handler_lock = match manager.get(guild_id) {
-                Some(handler_lock) => handler_lock,
-                None => {
-                    eprintln!("Failed to get handler lock");
-                    return;
-                }
-            };
-            let mut handler = handler_lock.lock().await;
-
-            if let Err(e) = handler.leave().await {
-                println!("Failed to leave channel: {:?}", e);
-            }
-        }
+    match state.macros.load_macros().await {
+        Ok(_) => {}
+        Err(e) if e.is_not_found() => info!("No macros saved yet"),
+        Err(e) => error!(error = ?e, "Failed to load macros"),
     }
-}
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn Error>> {
-    dotenv::dotenv().ok();
+    match state.transcription_opt_outs.load_opt_outs().await {
+        Ok(_) => {}
+        Err(e) if e.is_not_found() => info!("No transcription opt-outs saved yet"),
+        Err(e) => error!(error = ?e, "Failed to load transcription opt-outs"),
+    }
 
-    let voice_manager = VoiceManager::new();
-    match voice_manager.load_rolls().await {
+    match state.leaderboard.load_leaderboard().await {
         Ok(_) => {}
-        Err(e) => {
-            eprintln!("Failed to load rolls: {:?}", e);
-        }
+        Err(e) if e.is_not_found() => info!("No leaderboard saved yet"),
+        Err(e) => error!(error = ?e, "Failed to load leaderboard"),
     }
 
-    let mut client = Client::builder(
-        &env::var("DISCORD_TOKEN")?,
-        GatewayIntents::non_privileged() | GatewayIntents::MESSAGE_CONTENT,
-    )
-    .type_map_insert::<VoiceManagerKey>(Arc::new(voice_manager))
-    .type_map_insert::<GuildUsersKey>(Arc::new(Mutex::new(HashMap::new())))
-    .event_handler(Handler)
-    .register_songbird()
-    .await
-    .expect("Err creating client");
+    let mut helpers = Vec::new();
+    for (index, token) in config::get().helper_tokens.iter().enumerate() {
+        let instance = index + 1;
+        let helper = build_client(
+            token,
+            state.for_helper(instance),
+            songbirds[instance].clone(),
+        )
+        .await;
+        helpers.push(helper);
+    }
+    let mut client = build_client(&config::get().token, state, songbird.clone()).await;
+
+    let data = client.data.clone();
+    let shard_manager = client.shard_manager.clone();
+    ops::start(client.http.clone());
+    tokio::spawn(idle::leave_idle_channels(data.clone(), songbird.clone()));
+    tokio::spawn(maintenance::run_maintenance(data.clone()));
+    tokio::spawn(transcript::post_to_channels(
+        client.http.clone(),
+        data.clone(),
+    ));
+    tokio::spawn(feeds::poll_feeds(
+        client.http.clone(),
+        client.cache.clone(),
+        data.clone(),
+        songbird.clone(),
+    ));
+    tokio::spawn(announcements::run_announcements(
+        client.http.clone(),
+        client.cache.clone(),
+        data.clone(),
+        songbird.clone(),
+    ));
+    tokio::spawn(lottery::run_lotteries(
+        client.http.clone(),
+        client.cache.clone(),
+        data.clone(),
+        songbird.clone(),
+    ));
+    tokio::spawn(reminders::deliver_reminders(
+        client.http.clone(),
+        client.cache.clone(),
+        data.clone(),
+        songbird.clone(),
+    ));
+    if let Some(captions) = config::get().captions.clone() {
+        tokio::spawn(transcript::write_captions(captions));
+    }
+    #[cfg(unix)]
+    tokio::spawn(reload_on_hangup());
+    #[cfg(feature = "irc")]
+    if let Some(irc) = config::get().irc.clone() {
+        frontend::spawn(
+            irc::IrcFrontend::new(irc),
+            frontend::Speaker::new(data.clone(), client.cache.clone(), songbird.clone()),
+        );
+    }
+    #[cfg(not(feature = "irc"))]
+    if config::get().irc.is_some() {
+        tracing::warn!("Ignoring `irc`, the bot was built without the `irc` feature");
+    }
+    #[cfg(feature = "matrix")]
+    if let Some(matrix) = config::get().matrix.clone() {
+        frontend::spawn(
+            matrix::MatrixFrontend::new(matrix),
+            frontend::Speaker::new(data.clone(), client.cache.clone(), songbird.clone()),
+        );
+    }
+    #[cfg(not(feature = "matrix"))]
+    if config::get().matrix.is_some() {
+        tracing::warn!("Ignoring `matrix`, the bot was built without the `matrix` feature");
+    }
+    #[cfg(feature = "mqtt")]
+    if let Some(mqtt) = config::get().mqtt.clone() {
+        frontend::spawn(
+            mqtt::MqttFrontend::new(mqtt),
+            frontend::Speaker::new(data.clone(), client.cache.clone(), songbird.clone()),
+        );
+    }
+    #[cfg(not(feature = "mqtt"))]
+    if config::get().mqtt.is_some() {
+        tracing::warn!("Ignoring `mqtt`, the bot was built without the `mqtt` feature");
+    }
+    #[cfg(feature = "stt")]
+    if let Some(stt) = config::get().stt.clone() {
+        tokio::spawn(stt::transcribe_speech(
+            client.http.clone(),
+            data.clone(),
+            songbird.clone(),
+            stt,
+        ));
+    }
+    #[cfg(not(feature = "stt"))]
+    if config::get().stt.is_some() {
+        tracing::warn!("Ignoring `stt`, the bot was built without the `stt` feature");
+    }
+    #[cfg(feature = "telegram")]
+    if let Some(telegram) = config::get().telegram.clone() {
+        tokio::spawn(telegram::run_bot(telegram, data.clone()));
+    }
+    #[cfg(not(feature = "telegram"))]
+    if config::get().telegram.is_some() {
+        tracing::warn!("Ignoring `telegram`, the bot was built without the `telegram` feature");
+    }
+    if let Some(addr) = config::get().http.bind {
+        tokio::spawn(http::serve(
+            addr,
+            http::HttpState {
+                songbird: songbird.clone(),
+                data: data.clone(),
+                cache: client.cache.clone(),
+            },
+        ));
+    }
 
     tokio::spawn(async move {
         let _ = client
             .start()
             .await
-            .map_err(|e| eprintln!("Client ended: {:?}", e));
+            .map_err(|e| error!(error = ?e, "Client ended"));
     });
 
-    let _signal_err = signal::ctrl_c().await;
-    println!("Received Ctrl-C, shutting down.");
+    let mut helper_shutdowns = Vec::new();
+    for (mut helper, helper_songbird) in helpers.into_iter().zip(songbirds.into_iter().skip(1)) {
+        let helper_shard_manager = helper.shard_manager.clone();
+        helper_shutdowns.push((helper_songbird, helper_shard_manager));
+        tokio::spawn(async move {
+            let _ = helper
+                .start()
+                .await
+                .map_err(|e| error!(error = ?e, "Helper client ended"));
+        });
+    }
+
+    let signal_name = wait_for_shutdown().await;
+    info!("Received {}, shutting down.", signal_name);
+    systemd::notify_stopping();
+    let helper_shutdowns = helper_shutdowns
+        .into_iter()
+        .map(|(helper_songbird, helper_shard_manager)| {
+            tokio::spawn(async move {
+                shutdown::shut_down_helper(&helper_songbird, &helper_shard_manager).await;
+            })
+        })
+        .collect::<Vec<_>>();
+    shutdown::shut_down(&data, &songbird, &shard_manager).await;
+    for helper_shutdown in helper_shutdowns {
+        let _ = helper_shutdown.await;
+    }
     Ok(())
 }
 
-async fn get_wav_duration(wav_bytes: &[u8]) -> Option<f64> {
-    let mut cursor = Cursor::new(wav_bytes);
-
-    let mut riff_header = [0; 12];
-    cursor.read_exact(&mut riff_header).await.ok()?;
-
-    if &riff_header[0..4] != b"RIFF" || &riff_header[8..12] != b"WAVE" {
-        return None;
+fn new_songbird() -> Arc<Songbird> {
+    // Decoding everyone's audio is only worth it when something listens to it
+    #[cfg(feature = "stt")]
+    if config::get().stt.is_some() {
+        return Songbird::serenity_from_config(
+            songbird::Config::default().decode_mode(songbird::driver::DecodeMode::Decode),
+        );
     }
+    Songbird::serenity()
+}
 
-    let mut fmt_chunk_header = [0; 8];
-    cursor.read_exact(&mut fmt_chunk_header).await.ok()?;
+async fn build_client(token: &str, state: BotState, songbird: Arc<Songbird>) -> Client {
+    Client::builder(
+        token,
+        GatewayIntents::non_privileged()
+            | GatewayIntents::MESSAGE_CONTENT
+            | GatewayIntents::AUTO_MODERATION_EXECUTION,
+    )
+    .type_map_insert::<BotStateKey>(Arc::new(state))
+    .event_handler(events::Handler)
+    .register_songbird_with(songbird)
+    .await
+    .expect("Err creating client")
+}
 
-    if &fmt_chunk_header[0..4] != b"fmt " {
-        return None;
+async fn wait_for_shutdown() -> &'static str {
+    #[cfg(unix)]
+    {
+        let mut terminate = match signal::unix::signal(signal::unix::SignalKind::terminate()) {
+            Ok(terminate) => terminate,
+            Err(e) => {
+                error!(error = ?e, "Failed to listen for SIGTERM");
+                let _signal_err = signal::ctrl_c().await;
+                return "Ctrl-C";
+            }
+        };
+        tokio::select! {
+            _ = signal::ctrl_c() => "Ctrl-C",
+            _ = terminate.recv() => "SIGTERM",
+        }
     }
 
-    let fmt_chunk_size = u32::from_le_bytes(fmt_chunk_header[4..8].try_into().ok()?);
-
-    let mut fmt_chunk_data = vec![0; fmt_chunk_size as usize];
-    cursor.read_exact(&mut fmt_chunk_data).await.ok()?;
-
-    let audio_format = u16::from_le_bytes(fmt_chunk_data[0..2].try_into().ok()?);
-    // let num_channels = u16::from_le_bytes(fmt_chunk_data[2..4].try_into().ok()?);
-    let sample_rate = u32::from_le_bytes(fmt_chunk_data[4..8].try_into().ok()?);
-    // let byte_rate = u32::from_le_bytes(fmt_chunk_data[8..12].try_into().ok()?);
-    let block_align = u16::from_le_bytes(fmt_chunk_data[12..14].try_into().ok()?);
-    // let bits_per_sample = u16::from_le_bytes(fmt_chunk_data[14..16].try_into().ok()?);
-
-    if audio_format != 1 {
-        return None;
+    #[cfg(not(unix))]
+    {
+        let _signal_err = signal::ctrl_c().await;
+        "Ctrl-C"
     }
+}
 
-    let mut data_chunk_header = [0; 8];
-    cursor.read_exact(&mut data_chunk_header).await.ok()?;
+#[cfg(unix)]
+async fn reload_on_hangup() {
+    let mut hangup = match signal::unix::signal(signal::unix::SignalKind::hangup()) {
+        Ok(hangup) => hangup,
+        Err(e) => {
+            error!(error = ?e, "Failed to listen for SIGHUP");
+            return;
+        }
+    };
 
-    while &data_chunk_header[0..4] != b"data" {
-        let chunk_size = u32::from_le_bytes(data_chunk_header[4..8].try_into().ok()?);
-        cursor.set_position(cursor.position() + chunk_size as u64);
-        cursor.read_exact(&mut data_chunk_header).await.ok()?;
+    while hangup.recv().await.is_some() {
+        if let Err(e) = config::reload() {
+            error!(error = ?e, "Failed to reload config");
+        }
     }
-
-    let data_chunk_size = u32::from_le_bytes(data_chunk_header[4..8].try_into().ok()?);
-
-    let num_samples = data_chunk_size as f64 / block_align as f64;
-    let duration = num_samples / sample_rate as f64;
-
-    Some(duration)
 }
 
-fn get_requested_roll(content: &str) -> Option<u64> {
-    let re = Regex::new(r"\[:roll\s*(\d+)\s*\]").unwrap();
-    let caps = re.captures(content)?;
-    let roll = caps.get(1)?.as_str().parse::<u64>().ok()?;
-    Some(roll)
+async fn say(
+    text: &str,
+    output: &Path,
+    user: Option<u64>,
+    roll: Option<u64>,
+) -> anyhow::Result<()> {
+    let voice = match user {
+        Some(user) => load_voice(user, roll).await,
+        None => PAUL_VOICE,
+    };
+
+    let pronunciations = PronunciationMap::load(&config::get().data_path("pronunciations.json"))
+        .await
+        .unwrap_or_else(|_| PronunciationMap::new());
+    let content = pronunciations.apply(&process_message(text, &GuildConfig::default()));
+    let tts_bytes = synthesize(&content, &voice, Language::English).await?;
+    let tts_bytes = normalize_wav_volume(&tts_bytes).map_err(BotError::Audio)?;
+    fs::write(output, tts_bytes).await?;
+    println!("Wrote {}", output.display());
+    Ok(())
 }
 
-fn remove_requested_roll(content: &str) -> String {
-    let re = Regex::new(r"\[:roll\s*\d+\s*\]").unwrap();
-    re.replace_all(content, "").to_string()
-}
+/// Speaks each line of input through the same preprocessing as a server with default settings,
+/// printing what DECtalk is actually given.
+async fn local(
+    input: Option<&Path>,
+    output_dir: Option<&Path>,
+    player: &str,
+    user: Option<u64>,
+    roll: Option<u64>,
+) -> anyhow::Result<()> {
+    let voice = match user {
+        Some(user) => load_voice(user, roll).await,
+        None => PAUL_VOICE,
+    };
+    let pronunciations = PronunciationMap::load(&config::get().data_path("pronunciations.json"))
+        .await
+        .unwrap_or_else(|_| PronunciationMap::new());
+    let config = GuildConfig::default();
+    if let Some(output_dir) = output_dir {
+        fs::create_dir_all(output_dir).await?;
+    }
 
-fn process_message(text: &str) -> String {
-    let text = remove_links(text);
-    let text = replace_discord_emojis(&text);
-    text.trim().to_string()
-}
+    let input: Box<dyn tokio::io::AsyncRead + Unpin> = match input {
+        Some(input) => Box::new(fs::File::open(input).await?),
+        None => Box::new(tokio::io::stdin()),
+    };
+    let mut lines = BufReader::new(input).lines();
+    let mut count = 0;
+    while let Some(line) = lines.next_line().await? {
+        let content = match config.apply_profanity_filter(&process_message(&line, &config)) {
+            Some(content) => pronunciations.apply(&content),
+            None => {
+                println!("(filtered)");
+                continue;
+            }
+        };
+        println!("> {}", content);
+        if content.is_empty() {
+            continue;
+        }
 
-fn remove_links(text: &str) -> String {
-    let url_pattern = r"https?://[^\s/$.?#].[^\s]*";
-    let re = Regex::new(url_pattern).unwrap();
-    re.replace_all(text, "").to_string()
+        let tts_bytes = match synthesize(&content, &voice, Language::English).await {
+            Ok(tts_bytes) => normalize_wav_volume(&tts_bytes).map_err(BotError::Audio)?,
+            Err(e) => {
+                error!(error = ?e, "Failed to generate TTS");
+                continue;
+            }
+        };
+        count += 1;
+        match output_dir {
+            Some(output_dir) => {
+                let path = output_dir.join(format!("{:04}.wav", count));
+                fs::write(&path, tts_bytes).await?;
+                println!("Wrote {}", path.display());
+            }
+            None => play_wav(player, &tts_bytes).await?,
+        }
+    }
+    Ok(())
 }
 
-fn replace_discord_emojis(text: &str) -> String {
-    let emoji_pattern = r"<a?:(\w+):\d+>";
-    let re = Regex::new(emoji_pattern).unwrap();
-    let result = re.replace_all(text, |caps: &regex::Captures| {
-        let emoji_name = caps.get(1).unwrap().as_str().to_string();
-        emoji_name
-    });
+async fn play_wav(player: &str, wav: &[u8]) -> anyhow::Result<()> {
+    let path = config::get()
+        .engine
+        .output_dir
+        .join(format!("{}.wav", uuid::Uuid::new_v4()));
+    fs::write(&path, wav).await?;
+
+    let mut args = player.split_whitespace();
+    let program = args.next().context("No player given")?;
+    let status = tokio::process::Command::new(program)
+        .args(args)
+        .arg(&path)
+        .status()
+        .await;
+    fs::remove_file(&path).await?;
+    if !status?.success() {
+        bail!("{} failed", program);
+    }
+    Ok(())
+}
 
-    result.to_string()
+async fn load_voice(user_id: u64, roll: Option<u64>) -> DectalkVoice {
+    let roll = match roll {
+        Some(roll) => roll,
+        None => {
+            let voice_manager = VoiceManager::new();
+            match voice_manager.load_rolls().await {
+                Ok(_) => {}
+                Err(e) if e.is_not_found() => {}
+                Err(e) => error!(error = ?e, "Failed to load rolls"),
+            }
+            voice_manager.get_roll(user_id).await
+        }
+    };
+    DectalkVoice::generate(user_id, roll)
 }
 
-fn normalize_wav_volume(wav_file: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
-    let mut reader = hound::WavReader::new(Cursor::new(wav_file))?;
-    let spec = reader.spec();
-    let samples: Vec<i16> = reader.samples::<i16>().map(|s| s.unwrap_or(0)).collect();
-    let max_sample = samples.iter().cloned().fold(0, i16::max);
-    let min_sample = samples.iter().cloned().fold(0, i16::min);
-    let max_amplitude = i16::max_value();
-    let min_amplitude = i16::min_value();
-    let mut normalized_samples = Vec::with_capacity(samples.len());
-    for sample in samples {
-        let normalized_sample = if sample > 0 {
-            sample as f64 / max_sample as f64 * max_amplitude as f64
-        } else {
-            sample as f64 / min_sample as f64 * min_amplitude as f64
-        };
-        normalized_samples.push(normalized_sample as i16);
-    }
-    let mut buf = Vec::new();
-    let mut writer = hound::WavWriter::new(Cursor::new(&mut buf), spec)?;
-    for sample in normalized_samples {
-        writer.write_sample(sample)?;
-    }
-    writer.finalize()?;
-    Ok(buf)
+async fn migrate() -> anyhow::Result<()> {
+    migrations::run_migrations()?;
+    println!(
+        "Data files are up to date (version {})",
+        migrations::CURRENT_VERSION
+    );
+    Ok(())
 }
@@ -0,0 +1,114 @@
+use std::{io, path::Path, sync::Arc, time::Duration};
+
+use serenity::prelude::{RwLock, TypeMap};
+use tokio::{fs, time};
+use tracing::{debug, error, info, warn};
+
+use crate::{config, state::BotState};
+
+const MAINTENANCE_INTERVAL: Duration = Duration::from_secs(10 * 60);
+// Synthesis deletes its own files within seconds, anything this old was left by a crash
+const STALE_WAV_AGE: Duration = Duration::from_secs(10 * 60);
+const VOICE_IDLE_TIME: Duration = Duration::from_secs(60 * 60);
+// Longer than any sensible rate limit period, so pruned buckets would have been full
+const RATE_LIMIT_IDLE_TIME: Duration = Duration::from_secs(60 * 60);
+// Reports are about recent abuse, a day is plenty
+const READ_HISTORY_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+// The longest `welcome_cooldown` a guild can set
+const WELCOME_MEMORY: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Periodically cleans up after long-running instances: leftover WAVs from crashed runs,
+/// voices nobody has used in a while and empty user sets.
+pub async fn run_maintenance(data: Arc<RwLock<TypeMap>>) {
+    let mut interval = time::interval(MAINTENANCE_INTERVAL);
+    loop {
+        interval.tick().await;
+
+        let state = BotState::get(&data).await;
+
+        let removed_wavs = match remove_stale_wavs(&config::get().engine.output_dir).await {
+            Ok(removed_wavs) => removed_wavs,
+            Err(e) => {
+                warn!(error = ?e, "Failed to prune temporary WAVs");
+                0
+            }
+        };
+
+        if let Err(e) = state.stats.save_stats().await {
+            error!(error = ?e, "Failed to save stats");
+        }
+        state.leaderboard.rotate().await;
+        if let Err(e) = state.leaderboard.save_leaderboard().await {
+            error!(error = ?e, "Failed to save leaderboard");
+        }
+
+        state
+            .user_rate_limits
+            .lock()
+            .await
+            .prune(RATE_LIMIT_IDLE_TIME);
+        state
+            .guild_throughput
+            .lock()
+            .await
+            .prune(RATE_LIMIT_IDLE_TIME);
+        state.recent_messages.lock().await.prune();
+        state.read_history.lock().await.prune(READ_HISTORY_AGE);
+        state.clips.lock().await.prune();
+        state
+            .welcomed
+            .lock()
+            .await
+            .retain(|_, welcomed_at| welcomed_at.elapsed() < WELCOME_MEMORY);
+
+        let evicted_voices = state.voice_manager.evict_idle_voices(VOICE_IDLE_TIME).await;
+
+        let mut guild_users = state.guild_users.lock().await;
+        let before = guild_users.len();
+        guild_users.retain(|_, users| !users.is_empty());
+        let removed_guilds = before - guild_users.len();
+        let tracked_users = guild_users.values().map(|users| users.len()).sum::<usize>();
+        drop(guild_users);
+
+        let cached_voices = state.voice_manager.voices.lock().await.len();
+
+        debug!(
+            removed_wavs,
+            evicted_voices, removed_guilds, "Finished maintenance"
+        );
+        info!(
+            resident_mb = resident_memory().map(|bytes| bytes / (1024 * 1024)),
+            cached_voices, tracked_users, "Memory usage"
+        );
+    }
+}
+
+async fn remove_stale_wavs(dir: &Path) -> io::Result<usize> {
+    let mut removed = 0;
+    let mut entries = fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if path.extension().is_none_or(|extension| extension != "wav") {
+            continue;
+        }
+        // A file from the future is as fresh as it gets
+        let age = entry
+            .metadata()
+            .await?
+            .modified()?
+            .elapsed()
+            .unwrap_or_default();
+        if age > STALE_WAV_AGE {
+            fs::remove_file(&path).await?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+/// Resident set size in bytes, only available on Linux.
+fn resident_memory() -> Option<u64> {
+    let statm = std::fs::read_to_string("/proc/self/statm").ok()?;
+    let pages = statm.split_whitespace().nth(1)?.parse::<u64>().ok()?;
+    Some(pages * 4096)
+}
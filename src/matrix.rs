@@ -0,0 +1,119 @@
+use anyhow::bail;
+use dectalk_bot_core::dectalk::DectalkVoice;
+use matrix_sdk::{
+    config::SyncSettings,
+    ruma::{
+        events::room::message::{MessageType, OriginalSyncRoomMessageEvent},
+        OwnedRoomId, RoomOrAliasId,
+    },
+    Client, Room,
+};
+use serenity::{
+    all::{ChannelId, GuildId},
+    async_trait,
+};
+use tracing::{debug, error, info};
+
+use crate::{
+    config::MatrixConfig,
+    frontend::{self, Frontend, Speaker},
+};
+
+/// Reads the configured Matrix room into the guild's voice channel.
+pub struct MatrixFrontend {
+    matrix: MatrixConfig,
+    // Kept between reconnects so each one doesn't log in as a new device
+    client: Option<(Client, OwnedRoomId)>,
+}
+
+impl MatrixFrontend {
+    pub fn new(matrix: MatrixConfig) -> Self {
+        MatrixFrontend {
+            matrix,
+            client: None,
+        }
+    }
+
+    async fn log_in(&self) -> anyhow::Result<(Client, OwnedRoomId)> {
+        let client = Client::builder()
+            .homeserver_url(&self.matrix.homeserver)
+            .build()
+            .await?;
+        client
+            .matrix_auth()
+            .login_username(&self.matrix.user, &self.matrix.password)
+            .initial_device_display_name("dectalk")
+            .await?;
+        let room = <&RoomOrAliasId>::try_from(self.matrix.room.as_str())?;
+        let room = client.join_room_by_id_or_alias(room, &[]).await?;
+        info!("Joined Matrix room {}", self.matrix.room);
+        Ok((client, room.room_id().to_owned()))
+    }
+}
+
+#[async_trait]
+impl Frontend for MatrixFrontend {
+    fn name(&self) -> &'static str {
+        "Matrix"
+    }
+
+    async fn run(&mut self, speaker: &Speaker) -> anyhow::Result<()> {
+        let (client, room_id) = match &self.client {
+            Some(client) => client.clone(),
+            None => {
+                let client = self.log_in().await?;
+                self.client = Some(client.clone());
+                client
+            }
+        };
+
+        // Only read what's said from now on, not the history the first sync brings
+        let response = client.sync_once(SyncSettings::default()).await?;
+        let guild_id = GuildId::new(self.matrix.guild);
+        let channel_id = self.matrix.voice_channel.map(ChannelId::new);
+        let speaker = speaker.clone();
+        let handle = client.add_event_handler(
+            move |event: OriginalSyncRoomMessageEvent, room: Room, client: Client| {
+                let speaker = speaker.clone();
+                let room_id = room_id.clone();
+                async move {
+                    if room.room_id() != room_id || client.user_id() == Some(&*event.sender) {
+                        return;
+                    }
+                    read_message(&speaker, guild_id, channel_id, event).await;
+                }
+            },
+        );
+        let synced = client
+            .sync(SyncSettings::default().token(response.next_batch))
+            .await;
+        client.remove_event_handler(handle);
+        synced?;
+        bail!("Sync stopped")
+    }
+}
+
+async fn read_message(
+    speaker: &Speaker,
+    guild_id: GuildId,
+    channel_id: Option<ChannelId>,
+    event: OriginalSyncRoomMessageEvent,
+) {
+    let name = event.sender.localpart();
+    // Notices are what other bots send, they aren't meant to be read
+    let text = match &event.content.msgtype {
+        MessageType::Text(text) => text.body.clone(),
+        MessageType::Emote(emote) => format!("{} {}", name, emote.body),
+        _ => return,
+    };
+
+    let voice = DectalkVoice::generate(frontend::name_id(event.sender.as_str()), 0);
+    match speaker
+        .speak(guild_id, channel_id, name, &text, &voice)
+        .await
+    {
+        Ok(()) => {}
+        Err(e) if e.is_expected() => debug!(error = ?e, "Skipping Matrix message from {}", name),
+        Err(e) => error!(error = ?e, "Failed to read Matrix message from {}", name),
+    }
+}
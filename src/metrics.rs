@@ -0,0 +1,84 @@
+use std::sync::{Arc, LazyLock};
+
+use prometheus::{
+    register_gauge, register_histogram, register_int_counter, register_int_gauge, Encoder, Gauge,
+    Histogram, IntCounter, IntGauge, TextEncoder,
+};
+use songbird::Songbird;
+use tracing::error;
+
+pub static MESSAGES_SEEN: LazyLock<IntCounter> = LazyLock::new(|| {
+    register_int_counter!("dectalk_messages_seen_total", "Guild messages received").unwrap()
+});
+
+pub static MESSAGES_SPOKEN: LazyLock<IntCounter> = LazyLock::new(|| {
+    register_int_counter!(
+        "dectalk_messages_spoken_total",
+        "Messages queued for playback"
+    )
+    .unwrap()
+});
+
+pub static MESSAGES_THROTTLED: LazyLock<IntCounter> = LazyLock::new(|| {
+    register_int_counter!(
+        "dectalk_messages_throttled_total",
+        "Messages dropped because their guild used up its synthesis budget"
+    )
+    .unwrap()
+});
+
+pub static SAY_FAILURES: LazyLock<IntCounter> = LazyLock::new(|| {
+    register_int_counter!(
+        "dectalk_say_failures_total",
+        "Failed runs of the say binary"
+    )
+    .unwrap()
+});
+
+pub static SYNTHESIS_SECONDS: LazyLock<Histogram> = LazyLock::new(|| {
+    register_histogram!(
+        "dectalk_synthesis_seconds",
+        "Time spent running the say binary",
+        vec![0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0]
+    )
+    .unwrap()
+});
+
+/// Also shown by `/status`.
+pub static LAST_SYNTHESIS_SECONDS: LazyLock<Gauge> = LazyLock::new(|| {
+    register_gauge!(
+        "dectalk_last_synthesis_seconds",
+        "How long the latest run of the say binary took"
+    )
+    .unwrap()
+});
+
+static QUEUE_DEPTH: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!("dectalk_queue_depth", "Tracks queued across all calls").unwrap()
+});
+
+static VOICE_CONNECTIONS: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!("dectalk_voice_connections", "Connected voice calls").unwrap()
+});
+
+/// Renders every metric in the Prometheus text format. Gauges that describe voice calls are
+/// read from songbird here rather than kept up to date on every change.
+pub async fn gather(songbird: &Arc<Songbird>) -> String {
+    let mut queued = 0;
+    let mut connected = 0;
+    for (_, call) in songbird.iter() {
+        let call = call.lock().await;
+        queued += call.queue().len();
+        if call.current_connection().is_some() {
+            connected += 1;
+        }
+    }
+    QUEUE_DEPTH.set(queued as i64);
+    VOICE_CONNECTIONS.set(connected);
+
+    let mut buffer = Vec::new();
+    if let Err(e) = TextEncoder::new().encode(&prometheus::gather(), &mut buffer) {
+        error!(error = ?e, "Failed to encode metrics");
+    }
+    String::from_utf8_lossy(&buffer).into_owned()
+}
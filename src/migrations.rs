@@ -0,0 +1,211 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::bail;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::info;
+
+use crate::{
+    config::{self, Storage},
+    database::{self, DATABASE_FILE},
+    guild_config::GuildConfig,
+};
+
+type Migration = fn(&Documents) -> anyhow::Result<()>;
+
+// Edited by hand, or only describe the rest, so they stay files either way
+const KEPT_AS_FILES: &[&str] = &["version.json", "pronunciations.json"];
+
+/// The data files, wherever `storage` keeps them, so migrations work on either.
+enum Documents {
+    Files(PathBuf),
+    Database(Connection),
+}
+
+impl Documents {
+    fn read(&self, name: &str) -> anyhow::Result<Option<String>> {
+        match self {
+            Documents::Files(data_dir) => match fs::read_to_string(data_dir.join(name)) {
+                Ok(json) => Ok(Some(json)),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(e.into()),
+            },
+            Documents::Database(connection) => Ok(database::read(connection, name)?),
+        }
+    }
+
+    fn write(&self, name: &str, json: &str) -> anyhow::Result<()> {
+        match self {
+            Documents::Files(data_dir) => fs::write(data_dir.join(name), json)?,
+            Documents::Database(connection) => database::write(connection, name, json)?,
+        }
+        Ok(())
+    }
+}
+
+/// Migrations in order, the one at index `n` upgrades data from version `n` to `n + 1`.
+const MIGRATIONS: &[Migration] = &[fill_guild_defaults, name_timezones];
+pub const CURRENT_VERSION: u32 = MIGRATIONS.len() as u32;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SchemaVersion {
+    version: u32,
+}
+
+/// Brings the data directory up to `CURRENT_VERSION`, backing up each version's files first.
+/// With `storage = "sqlite"`, JSON files left from before the switch are imported into the
+/// database first. Runs before anything else reads the data files, so it sticks to blocking IO.
+pub fn run_migrations() -> anyhow::Result<()> {
+    let data_dir = config::get().data_dir.clone();
+    fs::create_dir_all(&data_dir)?;
+
+    let version_path = data_dir.join("version.json");
+    let version = match fs::read_to_string(&version_path) {
+        Ok(version_string) => serde_json::from_str::<SchemaVersion>(&version_string)?.version,
+        // Data written before versioning existed, or a fresh install with nothing to migrate
+        Err(_) if has_data_files(&data_dir) => 0,
+        Err(_) => CURRENT_VERSION,
+    };
+    if version > CURRENT_VERSION {
+        bail!(
+            "Data is version {} but this build only understands up to {}",
+            version,
+            CURRENT_VERSION
+        );
+    }
+
+    let documents = match config::get().storage {
+        Storage::Json => Documents::Files(data_dir.clone()),
+        Storage::Sqlite => {
+            let connection = database::open(&data_dir)?;
+            import_json(&data_dir, &connection)?;
+            Documents::Database(connection)
+        }
+    };
+
+    for (from, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        info!("Migrating data from version {} to {}", from, from + 1);
+        back_up(&data_dir, &documents, from as u32)?;
+        migration(&documents)?;
+        write_version(&version_path, from as u32 + 1)?;
+    }
+    write_version(&version_path, CURRENT_VERSION)?;
+
+    if let Documents::Database(connection) = documents {
+        database::install(connection);
+    }
+    Ok(())
+}
+
+fn has_data_files(data_dir: &Path) -> bool {
+    ["guilds.json", "rolls.json", DATABASE_FILE]
+        .iter()
+        .any(|name| data_dir.join(name).exists())
+}
+
+/// Moves every JSON data file into the database, keeping the originals in `imported-json`.
+/// A file replaces the row it matches, since it was written while `storage` was still `json`.
+fn import_json(data_dir: &Path, connection: &Connection) -> anyhow::Result<()> {
+    let import_dir = data_dir.join("imported-json");
+    let mut imported = 0;
+    for entry in fs::read_dir(data_dir)? {
+        let path = entry?.path();
+        let name = match path.file_name().and_then(|name| name.to_str()) {
+            Some(name) if name.ends_with(".json") && !KEPT_AS_FILES.contains(&name) => name,
+            _ => continue,
+        };
+        if !path.is_file() {
+            continue;
+        }
+
+        database::write(connection, name, &fs::read_to_string(&path)?)?;
+        fs::create_dir_all(&import_dir)?;
+        fs::rename(&path, import_dir.join(name))?;
+        imported += 1;
+    }
+    if imported > 0 {
+        info!("Imported {} data files into {}", imported, DATABASE_FILE);
+    }
+    Ok(())
+}
+
+fn write_version(path: &Path, version: u32) -> anyhow::Result<()> {
+    fs::write(path, serde_json::to_string(&SchemaVersion { version })?)?;
+    Ok(())
+}
+
+fn back_up(data_dir: &Path, documents: &Documents, version: u32) -> anyhow::Result<()> {
+    let backup_dir = data_dir.join(format!("backup-v{}", version));
+    fs::create_dir_all(&backup_dir)?;
+    if let Documents::Database(connection) = documents {
+        let backup_path = backup_dir.join(DATABASE_FILE);
+        // VACUUM INTO refuses to overwrite, and a leftover copy is from an interrupted run
+        if backup_path.exists() {
+            fs::remove_file(&backup_path)?;
+        }
+        connection.execute("VACUUM INTO ?1", [backup_path.to_string_lossy()])?;
+        return Ok(());
+    }
+    for entry in fs::read_dir(data_dir)? {
+        let path = entry?.path();
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            if let Some(name) = path.file_name() {
+                fs::copy(&path, backup_dir.join(name))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn fill_guild_defaults(documents: &Documents) -> anyhow::Result<()> {
+    let configs_string = match documents.read("guilds.json")? {
+        Some(configs_string) => configs_string,
+        None => return Ok(()),
+    };
+    let configs: HashMap<u64, GuildConfig> = serde_json::from_str(&configs_string)?;
+    documents.write("guilds.json", &serde_json::to_string(&configs)?)
+}
+
+/// Timezones used to be a UTC offset in minutes. Whole hours become the matching `Etc/GMT` zone
+/// (whose sign is reversed), other offsets the best known zone for them.
+fn name_timezones(documents: &Documents) -> anyhow::Result<()> {
+    let configs_string = match documents.read("guilds.json")? {
+        Some(configs_string) => configs_string,
+        None => return Ok(()),
+    };
+    let mut configs: HashMap<String, Value> = serde_json::from_str(&configs_string)?;
+    for config in configs.values_mut() {
+        let minutes = match config.get("timezone").and_then(Value::as_i64) {
+            Some(minutes) => minutes,
+            None => continue,
+        };
+        let name = match minutes {
+            0 => "UTC".to_string(),
+            minutes if minutes % 60 == 0 && (-720..=840).contains(&minutes) => {
+                format!("Etc/GMT{:+}", -minutes / 60)
+            }
+            -570 => "Pacific/Marquesas".to_string(),
+            -210 => "America/St_Johns".to_string(),
+            210 => "Asia/Tehran".to_string(),
+            270 => "Asia/Kabul".to_string(),
+            330 => "Asia/Kolkata".to_string(),
+            345 => "Asia/Kathmandu".to_string(),
+            390 => "Asia/Yangon".to_string(),
+            525 => "Australia/Eucla".to_string(),
+            570 => "Australia/Darwin".to_string(),
+            630 => "Australia/Lord_Howe".to_string(),
+            765 => "Pacific/Chatham".to_string(),
+            _ => "UTC".to_string(),
+        };
+        config["timezone"] = Value::String(name);
+    }
+    documents.write("guilds.json", &serde_json::to_string(&configs)?)
+}
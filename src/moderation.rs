@@ -0,0 +1,30 @@
+use std::{collections::HashMap, time::Duration};
+
+use serenity::all::MessageId;
+use tokio::time::Instant;
+
+// Comfortably longer than any delay before speaking plus synthesis time
+const MODERATED_RETENTION: Duration = Duration::from_secs(5 * 60);
+
+/// Messages AutoMod flagged or someone deleted, so they aren't read if they're still on their
+/// way through the pipeline.
+pub struct ModeratedMessages {
+    ids: HashMap<MessageId, Instant>,
+}
+
+impl ModeratedMessages {
+    pub fn new() -> Self {
+        ModeratedMessages {
+            ids: HashMap::new(),
+        }
+    }
+
+    pub fn insert(&mut self, id: MessageId) {
+        self.ids.retain(|_, at| at.elapsed() < MODERATED_RETENTION);
+        self.ids.insert(id, Instant::now());
+    }
+
+    pub fn contains(&self, id: MessageId) -> bool {
+        self.ids.contains_key(&id)
+    }
+}
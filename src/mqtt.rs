@@ -0,0 +1,121 @@
+use std::{sync::LazyLock, time::Duration};
+
+use dectalk_bot_core::dectalk::PAUL_VOICE;
+use regex::Regex;
+use rumqttc::{AsyncClient, Event, MqttOptions, Packet, QoS};
+use serde_json::Value;
+use serenity::{
+    all::{ChannelId, GuildId},
+    async_trait,
+};
+use tracing::{debug, error, info};
+
+use crate::{
+    config::{MqttConfig, MqttSubscription},
+    error::BotError,
+    frontend::{Frontend, Speaker},
+};
+
+static PLACEHOLDER: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\{([\w.]+)\}").unwrap());
+
+/// Reads messages on the configured MQTT topics, e.g. Home Assistant events, into voice
+/// channels.
+pub struct MqttFrontend {
+    mqtt: MqttConfig,
+}
+
+impl MqttFrontend {
+    pub fn new(mqtt: MqttConfig) -> Self {
+        MqttFrontend { mqtt }
+    }
+}
+
+#[async_trait]
+impl Frontend for MqttFrontend {
+    fn name(&self) -> &'static str {
+        "MQTT"
+    }
+
+    async fn run(&mut self, speaker: &Speaker) -> anyhow::Result<()> {
+        let mut options = MqttOptions::new(&self.mqtt.client_id, &self.mqtt.host, self.mqtt.port);
+        options.set_keep_alive(Duration::from_secs(30));
+        if let (Some(username), Some(password)) = (&self.mqtt.username, &self.mqtt.password) {
+            options.set_credentials(username, password);
+        }
+
+        let (client, mut event_loop) = AsyncClient::new(options, 16);
+        for subscription in &self.mqtt.subscriptions {
+            client
+                .subscribe(&subscription.topic, QoS::AtMostOnce)
+                .await?;
+        }
+        info!("Connected to MQTT at {}", self.mqtt.host);
+
+        loop {
+            let publish = match event_loop.poll().await? {
+                Event::Incoming(Packet::Publish(publish)) => publish,
+                _ => continue,
+            };
+            // Retained messages are old state, not something that just happened
+            if publish.retain {
+                continue;
+            }
+
+            let payload = String::from_utf8_lossy(&publish.payload);
+            for subscription in &self.mqtt.subscriptions {
+                if !rumqttc::matches(&publish.topic, &subscription.topic) {
+                    continue;
+                }
+                let text = render(&subscription.template, &publish.topic, &payload);
+                match speak(speaker, subscription, &publish.topic, &text).await {
+                    Ok(()) => {}
+                    Err(e) if e.is_expected() => {
+                        debug!(error = ?e, "Skipping MQTT message on {}", publish.topic)
+                    }
+                    Err(e) => {
+                        error!(error = ?e, "Failed to read MQTT message on {}", publish.topic)
+                    }
+                }
+            }
+        }
+    }
+}
+
+async fn speak(
+    speaker: &Speaker,
+    subscription: &MqttSubscription,
+    topic: &str,
+    text: &str,
+) -> Result<(), BotError> {
+    speaker
+        .speak(
+            GuildId::new(subscription.guild),
+            subscription.voice_channel.map(ChannelId::new),
+            topic,
+            text,
+            &PAUL_VOICE,
+        )
+        .await
+}
+
+/// Fills `{payload}`, `{topic}` and JSON fields like `{new_state.attributes.friendly_name}`
+/// into the template. Anything missing is left out.
+fn render(template: &str, topic: &str, payload: &str) -> String {
+    let json = serde_json::from_str::<Value>(payload).ok();
+    PLACEHOLDER
+        .replace_all(template, |captures: &regex::Captures| match &captures[1] {
+            "payload" => payload.to_string(),
+            "topic" => topic.to_string(),
+            path => {
+                let value = json
+                    .as_ref()
+                    .and_then(|json| path.split('.').try_fold(json, |value, key| value.get(key)));
+                match value {
+                    Some(Value::String(value)) => value.clone(),
+                    Some(Value::Null) | None => String::new(),
+                    Some(value) => value.to_string(),
+                }
+            }
+        })
+        .to_string()
+}
@@ -0,0 +1,141 @@
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Write},
+    sync::{Arc, OnceLock},
+    time::Duration,
+};
+
+use serenity::{all::ChannelId, http::Http};
+use tokio::{
+    sync::mpsc::{self, UnboundedSender},
+    time::Instant,
+};
+use tracing::{
+    field::{Field, Visit},
+    span::{Attributes, Id},
+    warn, Event, Level, Subscriber,
+};
+use tracing_subscriber::{filter::Targets, layer::Context, registry::LookupSpan, Layer};
+
+use crate::config;
+
+const REPORT_COOLDOWN: Duration = Duration::from_secs(60);
+const MAX_REPORT_LENGTH: usize = 1900;
+
+/// A report and the key its repeats are recognized by.
+struct Report {
+    key: String,
+    text: String,
+}
+
+static REPORTS: OnceLock<UnboundedSender<Report>> = OnceLock::new();
+
+/// Forwards our own error events, along with the fields of the spans they happened in, to
+/// `ops_channel` once `start` has been called.
+struct OpsLayer;
+
+struct SpanFields(String);
+
+#[derive(Default)]
+struct FieldWriter {
+    message: String,
+    fields: String,
+}
+
+impl Visit for FieldWriter {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+impl<S> Layer<S> for OpsLayer
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut writer = FieldWriter::default();
+        attrs.record(&mut writer);
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(SpanFields(writer.fields));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        if *metadata.level() != Level::ERROR {
+            return;
+        }
+        let sender = match REPORTS.get() {
+            Some(sender) => sender,
+            None => return,
+        };
+
+        let mut writer = FieldWriter::default();
+        event.record(&mut writer);
+        let mut report = format!("**{}**{}", writer.message, writer.fields);
+        if let Some(scope) = ctx.event_scope(event) {
+            for span in scope.from_root() {
+                let _ = write!(report, "\n`{}`", span.name());
+                if let Some(fields) = span.extensions().get::<SpanFields>() {
+                    report.push_str(&fields.0);
+                }
+            }
+        }
+        // Fields and spans carry ids and durations that differ every time, so only the
+        // message decides what counts as a repeat
+        let key = format!(
+            "{} {} {}",
+            metadata.level(),
+            metadata.target(),
+            writer.message
+        );
+        let _ = sender.send(Report { key, text: report });
+    }
+}
+
+/// Only looks at our own spans, so library spans stay disabled when nothing else wants them.
+pub fn layer<S>() -> impl Layer<S>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    OpsLayer.with_filter(Targets::new().with_target("dectalk", Level::TRACE))
+}
+
+/// Starts posting error reports. Repeats of the same message are dropped for a minute so a
+/// broken install doesn't flood the channel.
+pub fn start(http: Arc<Http>) {
+    let (sender, mut receiver) = mpsc::unbounded_channel::<Report>();
+    if REPORTS.set(sender).is_err() {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let mut last_sent: HashMap<String, Instant> = HashMap::new();
+        while let Some(report) = receiver.recv().await {
+            let channel_id = match config::get().ops_channel {
+                Some(channel_id) => ChannelId::new(channel_id),
+                None => continue,
+            };
+
+            let now = Instant::now();
+            last_sent.retain(|_, sent| now.duration_since(*sent) < REPORT_COOLDOWN);
+            if last_sent.contains_key(&report.key) {
+                continue;
+            }
+            last_sent.insert(report.key, now);
+
+            let content = match report.text.char_indices().nth(MAX_REPORT_LENGTH) {
+                Some((end, _)) => format!("{}…", &report.text[..end]),
+                None => report.text,
+            };
+            // Logged as a warning, an error here would just be reported again
+            if let Err(e) = channel_id.say(&http, content).await {
+                warn!(error = ?e, "Failed to post to the ops channel");
+            }
+        }
+    });
+}
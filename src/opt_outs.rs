@@ -0,0 +1,60 @@
+use std::{collections::HashSet, sync::Arc};
+
+use tokio::sync::Mutex;
+use tracing::debug;
+
+use crate::{
+    config,
+    error::{self, BotError},
+};
+
+/// Users who turned off speech transcription with `/transcription`, in every server.
+pub struct TranscriptionOptOuts {
+    pub users: Arc<Mutex<HashSet<u64>>>,
+}
+
+impl TranscriptionOptOuts {
+    pub fn new() -> Self {
+        TranscriptionOptOuts {
+            users: Arc::new(Mutex::new(HashSet::new())),
+        }
+    }
+
+    pub async fn contains(&self, id: u64) -> bool {
+        self.users.lock().await.contains(&id)
+    }
+
+    /// Returns false if nothing changed.
+    pub async fn set(&self, id: u64, opted_out: bool) -> Result<bool, BotError> {
+        let changed = {
+            let mut users = self.users.lock().await;
+            if opted_out {
+                users.insert(id)
+            } else {
+                users.remove(&id)
+            }
+        };
+        if changed {
+            self.save_opt_outs().await?;
+        }
+        Ok(changed)
+    }
+
+    pub async fn load_opt_outs(&self) -> Result<(), BotError> {
+        debug!("Loading transcription opt-outs...");
+        let users =
+            error::read_json(config::get().data_path("transcription_opt_outs.json")).await?;
+        *self.users.lock().await = users;
+        Ok(())
+    }
+
+    pub async fn save_opt_outs(&self) -> Result<(), BotError> {
+        debug!("Saving transcription opt-outs...");
+        let users = self.users.lock().await;
+        error::write_json(
+            config::get().data_path("transcription_opt_outs.json"),
+            &*users,
+        )
+        .await
+    }
+}
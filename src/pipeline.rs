@@ -0,0 +1,574 @@
+use std::error::Error;
+
+use dectalk_bot_core::{
+    audio::{concat_wavs, get_wav_duration, normalize_wav_volume},
+    dectalk::{DectalkVoice, Language},
+    engine::TtsEngine,
+    preprocess::{
+        self, chunk_text, detect_foreign_language, remove_requested_roll, spell_out,
+        to_dectalk_language,
+    },
+    pronunciation::PronunciationMap,
+};
+use serenity::all::ChannelId;
+use tokio::time::Instant;
+use tracing::{debug, error};
+
+use crate::{
+    guild_config::{ChannelMode, ForeignLanguageMode, GuildConfig, Limits},
+    preprocess::ESTIMATE_MARGIN,
+    state::Binding,
+};
+
+// Text attachments are read in pieces so no single say run gets too long
+const ATTACHMENT_CHUNK_LENGTH: usize = 256;
+
+/// Where time comes from, so tests don't have to wait on a real one.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+#[derive(Debug)]
+pub struct Prepared {
+    /// What to synthesize, including any voice tag.
+    pub content: String,
+    /// What to show in transcripts and history.
+    pub caption: String,
+    pub language: Language,
+}
+
+pub struct RenderOptions<'a> {
+    pub voice: &'a DectalkVoice,
+    pub language: Language,
+    /// None skips every duration limit, for operators.
+    pub limits: Option<Limits>,
+    pub max_attachment_duration: f64,
+    pub words_per_minute: f64,
+    /// Also collect the phonemes the content was spoken as.
+    pub phonemes: bool,
+}
+
+pub struct Rendered {
+    /// Normalized audio, or None if there was nothing to play or it was too long.
+    pub wav: Option<Vec<u8>>,
+    /// How long synthesis took, charged against the guild's budget.
+    pub synthesis_seconds: f64,
+    /// What the content was spoken as, when asked for and the engine could tell.
+    pub phonemes: Option<String>,
+}
+
+/// Turns a message's text into what DECtalk should say, or None if it shouldn't be read.
+/// `repeats` replaces the message with a count for duplicates.
+pub fn prepare(
+    text: &str,
+    voice_tag: Option<&str>,
+    repeats: u32,
+    config: &GuildConfig,
+    pronunciations: &PronunciationMap,
+    allow_voice_tag: bool,
+) -> Option<Prepared> {
+    let content = preprocess::process_message(text, &config.preprocess_options());
+    let content = match config.apply_profanity_filter(&remove_requested_roll(&content)) {
+        Some(content) => content,
+        None => {
+            debug!("Skipping filtered message");
+            return None;
+        }
+    };
+    let mut content = pronunciations.apply(&content);
+
+    let mut language = Language::English;
+    if let Some(detected) = detect_foreign_language(&content) {
+        match config.foreign_language {
+            ForeignLanguageMode::Read => {}
+            ForeignLanguageMode::Skip => {
+                debug!("Skipping {:?} message", detected);
+                return None;
+            }
+            ForeignLanguageMode::Spell => content = spell_out(&content),
+            ForeignLanguageMode::Route => match to_dectalk_language(detected) {
+                Some(detected) => language = detected,
+                None => {
+                    debug!("No DECtalk language for {:?}, skipping", detected);
+                    return None;
+                }
+            },
+        }
+    }
+
+    if repeats > 1 {
+        content = format!("times {}", repeats);
+    }
+
+    let caption = content.clone();
+    if let Some(voice_tag) = voice_tag {
+        if !content.is_empty() && allow_voice_tag {
+            content = format!("[:name {}] {}", voice_tag, content);
+        }
+    }
+
+    Some(Prepared {
+        content,
+        caption,
+        language,
+    })
+}
+
+/// Runs a text attachment through the same filters as messages.
+pub fn prepare_attachment(
+    text: &str,
+    config: &GuildConfig,
+    pronunciations: &PronunciationMap,
+) -> Option<String> {
+    let content = preprocess::process_message(text, &config.preprocess_options());
+    config
+        .apply_profanity_filter(&content)
+        .map(|content| pronunciations.apply(&content))
+}
+
+/// Picks the voice channel a message from `message_channel_id` should be read in, given the
+/// author's voice channel, or None if it shouldn't be read at all.
+pub fn route(
+    config: &GuildConfig,
+    binding: Option<Binding>,
+    message_channel_id: ChannelId,
+    user_channel_id: Option<ChannelId>,
+) -> Option<ChannelId> {
+    let channel_id = match binding.filter(|_| config.sticky) {
+        Some(binding) => {
+            if message_channel_id != binding.text_channel_id {
+                return None;
+            }
+            binding.voice_channel_id
+        }
+        None => {
+            let user_channel_id = match user_channel_id {
+                Some(user_channel_id) => user_channel_id,
+                None => {
+                    debug!("Author isn't in a voice channel");
+                    return None;
+                }
+            };
+
+            let is_routed = match config.channel_mode {
+                ChannelMode::VoiceChat => message_channel_id == user_channel_id,
+                ChannelMode::Bound => binding.is_some_and(|binding| {
+                    message_channel_id == binding.text_channel_id
+                        && user_channel_id == binding.voice_channel_id
+                }),
+                ChannelMode::Any => true,
+            };
+            if !is_routed {
+                return None;
+            }
+
+            user_channel_id
+        }
+    };
+
+    if !config.voice_channels.permits(channel_id.get()) {
+        return None;
+    }
+    Some(channel_id)
+}
+
+/// Synthesizes the message and its prepared attachments into one normalized WAV.
+pub async fn render(
+    engine: &dyn TtsEngine,
+    clock: &dyn Clock,
+    content: &str,
+    attachments: &[String],
+    options: &RenderOptions<'_>,
+) -> Result<Rendered, Box<dyn Error + Send + Sync>> {
+    let started = clock.now();
+    let synthesis_seconds = || clock.now().duration_since(started).as_secs_f64();
+    let too_long = |synthesis_seconds| Rendered {
+        wav: None,
+        synthesis_seconds,
+        phonemes: None,
+    };
+
+    let mut wavs = Vec::new();
+    let mut phonemes = None;
+    if !content.is_empty() {
+        if let Some(limits) = options.limits {
+            let estimate = preprocess::estimate_duration(content, options.words_per_minute);
+            if estimate > limits.max_duration * ESTIMATE_MARGIN {
+                debug!("Estimated TTS duration is too long");
+                return Ok(too_long(0.0));
+            }
+        }
+
+        let tts_bytes = if options.phonemes {
+            let (tts_bytes, spoken) = engine
+                .synthesize_with_phonemes(content, options.voice, options.language)
+                .await?;
+            phonemes = Some(spoken).filter(|spoken| !spoken.is_empty());
+            tts_bytes
+        } else {
+            engine
+                .synthesize(content, options.voice, options.language)
+                .await?
+        };
+        let duration = match get_wav_duration(&tts_bytes).await {
+            Some(duration) => duration,
+            None => return Err("Failed to get duration".into()),
+        };
+        if options
+            .limits
+            .is_some_and(|limits| duration > limits.max_duration)
+        {
+            debug!("TTS duration is too long");
+            return Ok(too_long(synthesis_seconds()));
+        }
+
+        wavs.push(tts_bytes);
+    }
+
+    let mut attachment_duration = 0.0;
+    'attachments: for attachment in attachments {
+        for chunk in chunk_text(attachment, ATTACHMENT_CHUNK_LENGTH) {
+            let tts_bytes = match engine
+                .synthesize(&chunk, options.voice, Language::English)
+                .await
+            {
+                Ok(tts_bytes) => tts_bytes,
+                Err(e) => {
+                    error!(error = ?e, "Failed to generate attachment TTS");
+                    break 'attachments;
+                }
+            };
+
+            attachment_duration += get_wav_duration(&tts_bytes).await.unwrap_or(0.0);
+            if options.limits.is_some() && attachment_duration > options.max_attachment_duration {
+                debug!("Text attachment duration limit reached");
+                break 'attachments;
+            }
+
+            wavs.push(tts_bytes);
+        }
+    }
+
+    let synthesis_seconds = synthesis_seconds();
+    if wavs.is_empty() {
+        return Ok(Rendered {
+            wav: None,
+            synthesis_seconds,
+            phonemes,
+        });
+    }
+
+    let wav = concat_wavs(&wavs).and_then(|wav| normalize_wav_volume(&wav))?;
+    Ok(Rendered {
+        wav: Some(wav),
+        synthesis_seconds,
+        phonemes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::Cursor,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Mutex,
+        },
+        time::Duration,
+    };
+
+    use dectalk_bot_core::{dectalk::PAUL_VOICE, profanity::ProfanityAction};
+    use serenity::async_trait;
+
+    use super::*;
+
+    const SAMPLE_RATE: u32 = 8000;
+
+    /// Returns silence lasting `seconds_per_word` for every word, without running DECtalk.
+    struct MockEngine {
+        seconds_per_word: f64,
+        calls: AtomicUsize,
+    }
+
+    impl MockEngine {
+        fn new(seconds_per_word: f64) -> Self {
+            MockEngine {
+                seconds_per_word,
+                calls: AtomicUsize::new(0),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl TtsEngine for MockEngine {
+        async fn synthesize(
+            &self,
+            text: &str,
+            _voice: &DectalkVoice,
+            _language: Language,
+        ) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if text.contains("explode") {
+                return Err("say crashed".into());
+            }
+            let seconds = text.split_whitespace().count() as f64 * self.seconds_per_word;
+            Ok(silence(seconds))
+        }
+    }
+
+    /// Moves forward by `step` every time it's read.
+    struct SteppingClock {
+        now: Mutex<Instant>,
+        step: Duration,
+    }
+
+    impl Clock for SteppingClock {
+        fn now(&self) -> Instant {
+            let mut now = self.now.lock().unwrap();
+            *now += self.step;
+            *now
+        }
+    }
+
+    fn silence(seconds: f64) -> Vec<u8> {
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: SAMPLE_RATE,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut buf = Vec::new();
+        let mut writer = hound::WavWriter::new(Cursor::new(&mut buf), spec).unwrap();
+        for _ in 0..(seconds * SAMPLE_RATE as f64) as usize {
+            writer.write_sample(0i16).unwrap();
+        }
+        writer.finalize().unwrap();
+        buf
+    }
+
+    fn options(limits: Option<Limits>) -> RenderOptions<'static> {
+        RenderOptions {
+            voice: &PAUL_VOICE,
+            language: Language::English,
+            limits,
+            max_attachment_duration: 5.0,
+            words_per_minute: 200.0,
+            phonemes: false,
+        }
+    }
+
+    fn limits(max_duration: f64) -> Option<Limits> {
+        Some(Limits {
+            max_message_length: 256,
+            max_duration,
+        })
+    }
+
+    fn binding(text: u64, voice: u64) -> Option<Binding> {
+        Some(Binding {
+            voice_channel_id: ChannelId::new(voice),
+            text_channel_id: ChannelId::new(text),
+        })
+    }
+
+    #[test]
+    fn prepare_skips_profanity() {
+        let config = GuildConfig {
+            profanity_words: vec!["heck".to_string()],
+            profanity_action: ProfanityAction::Skip,
+            ..GuildConfig::default()
+        };
+        let prepared = prepare(
+            "what the heck",
+            None,
+            1,
+            &config,
+            &PronunciationMap::new(),
+            false,
+        );
+        assert!(prepared.is_none());
+    }
+
+    #[test]
+    fn prepare_counts_repeats() {
+        let prepared = prepare(
+            "spam",
+            None,
+            3,
+            &GuildConfig::default(),
+            &PronunciationMap::new(),
+            false,
+        )
+        .unwrap();
+        assert_eq!(prepared.content, "times 3");
+    }
+
+    #[test]
+    fn prepare_only_tags_voice_when_allowed() {
+        let config = GuildConfig::default();
+        let pronunciations = PronunciationMap::new();
+        let prepared = prepare("hello", Some("betty"), 1, &config, &pronunciations, true).unwrap();
+        assert_eq!(prepared.content, "[:name betty] hello");
+        assert_eq!(prepared.caption, "hello");
+
+        let prepared = prepare("hello", Some("betty"), 1, &config, &pronunciations, false).unwrap();
+        assert_eq!(prepared.content, "hello");
+    }
+
+    #[test]
+    fn route_voice_chat_needs_matching_channel() {
+        let config = GuildConfig::default();
+        let voice = ChannelId::new(1);
+        assert_eq!(route(&config, None, voice, Some(voice)), Some(voice));
+        assert_eq!(route(&config, None, ChannelId::new(2), Some(voice)), None);
+        assert_eq!(route(&config, None, voice, None), None);
+    }
+
+    #[test]
+    fn route_bound_needs_binding() {
+        let config = GuildConfig {
+            channel_mode: ChannelMode::Bound,
+            ..GuildConfig::default()
+        };
+        let (text, voice) = (ChannelId::new(2), ChannelId::new(1));
+        assert_eq!(
+            route(&config, binding(2, 1), text, Some(voice)),
+            Some(voice)
+        );
+        assert_eq!(route(&config, None, text, Some(voice)), None);
+        assert_eq!(route(&config, binding(3, 1), text, Some(voice)), None);
+    }
+
+    #[test]
+    fn route_sticky_ignores_author_channel() {
+        let config = GuildConfig {
+            sticky: true,
+            ..GuildConfig::default()
+        };
+        let text = ChannelId::new(2);
+        assert_eq!(
+            route(&config, binding(2, 1), text, None),
+            Some(ChannelId::new(1))
+        );
+        assert_eq!(route(&config, binding(3, 1), text, None), None);
+    }
+
+    #[tokio::test]
+    async fn render_skips_long_estimates_without_synthesizing() {
+        let engine = MockEngine::new(0.1);
+        let text = "word ".repeat(100);
+        let rendered = render(&engine, &SystemClock, &text, &[], &options(limits(5.0)))
+            .await
+            .unwrap();
+        assert!(rendered.wav.is_none());
+        assert_eq!(engine.calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn render_checks_real_duration() {
+        let engine = MockEngine::new(2.0);
+        let rendered = render(
+            &engine,
+            &SystemClock,
+            "one two three four",
+            &[],
+            &options(limits(5.0)),
+        )
+        .await
+        .unwrap();
+        assert!(rendered.wav.is_none());
+
+        let rendered = render(&engine, &SystemClock, "one two", &[], &options(limits(5.0)))
+            .await
+            .unwrap();
+        let duration = get_wav_duration(&rendered.wav.unwrap()).await.unwrap();
+        assert!((duration - 4.0).abs() < 0.01);
+    }
+
+    #[tokio::test]
+    async fn render_lets_operators_skip_limits() {
+        let engine = MockEngine::new(2.0);
+        let rendered = render(
+            &engine,
+            &SystemClock,
+            "one two three four",
+            &[],
+            &options(None),
+        )
+        .await
+        .unwrap();
+        assert!(rendered.wav.is_some());
+    }
+
+    #[tokio::test]
+    async fn render_stops_attachments_at_the_limit() {
+        let engine = MockEngine::new(1.0);
+        let attachment = "word ".repeat(60 * 3);
+        let rendered = render(
+            &engine,
+            &SystemClock,
+            "hi",
+            &[attachment],
+            &options(limits(5.0)),
+        )
+        .await
+        .unwrap();
+        // Each chunk is about 50 words, so the first one is already over the 5 second limit
+        let duration = get_wav_duration(&rendered.wav.unwrap()).await.unwrap();
+        assert!((duration - 1.0).abs() < 0.01);
+        assert_eq!(engine.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn render_keeps_the_message_when_an_attachment_fails() {
+        let engine = MockEngine::new(0.5);
+        let rendered = render(
+            &engine,
+            &SystemClock,
+            "hello there",
+            &["explode".to_string()],
+            &options(limits(5.0)),
+        )
+        .await
+        .unwrap();
+        assert!(rendered.wav.is_some());
+    }
+
+    #[tokio::test]
+    async fn render_fails_when_the_engine_does() {
+        let engine = MockEngine::new(0.5);
+        let result = render(&engine, &SystemClock, "explode", &[], &options(None)).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn render_reports_synthesis_time() {
+        let engine = MockEngine::new(0.5);
+        let clock = SteppingClock {
+            now: Mutex::new(Instant::now()),
+            step: Duration::from_secs(2),
+        };
+        let rendered = render(&engine, &clock, "hello", &[], &options(None))
+            .await
+            .unwrap();
+        assert_eq!(rendered.synthesis_seconds, 2.0);
+    }
+
+    #[tokio::test]
+    async fn render_returns_nothing_for_empty_messages() {
+        let engine = MockEngine::new(0.5);
+        let rendered = render(&engine, &SystemClock, "", &[], &options(None))
+            .await
+            .unwrap();
+        assert!(rendered.wav.is_none());
+        assert_eq!(engine.calls.load(Ordering::SeqCst), 0);
+    }
+}
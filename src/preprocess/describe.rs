@@ -0,0 +1,110 @@
+use dectalk_bot_core::preprocess::truncate;
+use serenity::model::{
+    channel::{Attachment, Embed, Message, Poll, PollMedia, PollMediaEmoji},
+    sticker::StickerItem,
+};
+
+use crate::config;
+
+pub fn get_author_name(message: &Message) -> String {
+    message
+        .member
+        .as_ref()
+        .and_then(|member| member.nick.clone())
+        .or_else(|| message.author.global_name.clone())
+        .unwrap_or_else(|| message.author.name.clone())
+}
+
+pub fn describe_attachments(author_name: &str, attachments: &[Attachment]) -> Option<String> {
+    if attachments.is_empty() {
+        return None;
+    }
+
+    let descriptions = attachments
+        .iter()
+        .map(|attachment| {
+            let kind = match attachment.content_type.as_deref() {
+                Some(content_type) if content_type.starts_with("image/") => "an image",
+                Some(content_type) if content_type.starts_with("video/") => "a video",
+                Some(content_type) if content_type.starts_with("audio/") => "an audio clip",
+                _ => "a file",
+            };
+            let name = attachment
+                .description
+                .as_deref()
+                .unwrap_or(&attachment.filename);
+            format!("{}: {}", kind, name)
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    Some(format!("{} sent {}", author_name, descriptions))
+}
+
+pub fn describe_stickers(author_name: &str, stickers: &[StickerItem]) -> Option<String> {
+    if stickers.is_empty() {
+        return None;
+    }
+
+    let names = stickers
+        .iter()
+        .map(|sticker| sticker.name.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+    Some(format!("{} sent a sticker: {}", author_name, names))
+}
+
+pub fn describe_embeds(embeds: &[Embed]) -> Option<String> {
+    // Only bot-authored embeds, link previews would just repeat the link
+    let descriptions = embeds
+        .iter()
+        .filter(|embed| embed.kind.as_deref() == Some("rich"))
+        .filter_map(|embed| {
+            let parts = [embed.title.as_deref(), embed.description.as_deref()]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>();
+            if parts.is_empty() {
+                return None;
+            }
+
+            Some(truncate(
+                &parts.join(". "),
+                config::get().limits.max_embed_length,
+            ))
+        })
+        .collect::<Vec<_>>();
+
+    if descriptions.is_empty() {
+        return None;
+    }
+    Some(descriptions.join(". "))
+}
+
+pub fn describe_poll(author_name: &str, poll: &Poll) -> Option<String> {
+    let question = poll_media_text(&poll.question)?;
+    let options = poll
+        .answers
+        .iter()
+        .filter_map(|answer| poll_media_text(&answer.poll_media))
+        .collect::<Vec<_>>();
+
+    let options = match options.as_slice() {
+        [] => return Some(format!("{} started a poll: {}", author_name, question)),
+        [only] => only.clone(),
+        [rest @ .., last] => format!("{}, or {}", rest.join(", "), last),
+    };
+    Some(format!(
+        "{} started a poll: {}. The options are: {}",
+        author_name, question, options
+    ))
+}
+
+fn poll_media_text(media: &PollMedia) -> Option<String> {
+    match (&media.text, &media.emoji) {
+        (Some(text), Some(PollMediaEmoji::Name(emoji))) => Some(format!("{} {}", emoji, text)),
+        (Some(text), _) => Some(text.clone()),
+        (None, Some(PollMediaEmoji::Name(emoji))) => Some(emoji.clone()),
+        _ => None,
+    }
+}
@@ -0,0 +1,20 @@
+use dectalk_bot_core::preprocess;
+
+use crate::{config, guild_config::GuildConfig};
+
+mod describe;
+
+pub use describe::{
+    describe_attachments, describe_embeds, describe_poll, describe_stickers, get_author_name,
+};
+
+// Estimates are only used to skip obviously long messages, the real duration is checked later
+pub const ESTIMATE_MARGIN: f64 = 1.5;
+
+pub fn estimate_duration(text: &str) -> f64 {
+    preprocess::estimate_duration(text, config::get().engine.words_per_minute)
+}
+
+pub fn process_message(text: &str, config: &GuildConfig) -> String {
+    preprocess::process_message(text, &config.preprocess_options())
+}
@@ -0,0 +1,61 @@
+use std::{collections::HashMap, hash::Hash, time::Duration};
+
+use tokio::time::Instant;
+
+/// Allows bursts of up to `capacity` while refilling at `capacity` per `period`.
+#[derive(Debug, Clone, Copy)]
+struct TokenBucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+pub struct RateLimiter<K> {
+    buckets: HashMap<K, TokenBucket>,
+}
+
+impl<K: Eq + Hash> RateLimiter<K> {
+    pub fn new() -> Self {
+        RateLimiter {
+            buckets: HashMap::new(),
+        }
+    }
+
+    fn refill(&mut self, key: K, capacity: f64, period: Duration) -> &mut TokenBucket {
+        let now = Instant::now();
+        let bucket = self.buckets.entry(key).or_insert(TokenBucket {
+            tokens: capacity,
+            updated: now,
+        });
+
+        let refill = now.duration_since(bucket.updated).as_secs_f64() / period.as_secs_f64();
+        bucket.tokens = (bucket.tokens + refill * capacity).min(capacity);
+        bucket.updated = now;
+        bucket
+    }
+
+    /// Takes `cost` tokens from `key`'s bucket, or returns false if it doesn't have enough.
+    pub fn try_take(&mut self, key: K, cost: f64, capacity: f64, period: Duration) -> bool {
+        let bucket = self.refill(key, capacity, period);
+        if bucket.tokens < cost {
+            return false;
+        }
+        bucket.tokens -= cost;
+        true
+    }
+
+    /// Whether `key` has anything left, for costs that are only known afterwards.
+    pub fn has_tokens(&mut self, key: K, capacity: f64, period: Duration) -> bool {
+        self.refill(key, capacity, period).tokens > 0.0
+    }
+
+    /// Takes `cost` tokens even if that leaves the bucket in debt.
+    pub fn charge(&mut self, key: K, cost: f64, capacity: f64, period: Duration) {
+        self.refill(key, capacity, period).tokens -= cost;
+    }
+
+    /// Forgets buckets untouched for `period`, they'd be full again anyway.
+    pub fn prune(&mut self, period: Duration) {
+        self.buckets
+            .retain(|_, bucket| bucket.updated.elapsed() < period);
+    }
+}
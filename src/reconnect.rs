@@ -0,0 +1,92 @@
+use std::{sync::Arc, time::Duration};
+
+use serenity::{all::GuildId, async_trait};
+use songbird::{
+    events::context_data::DisconnectReason, model::CloseCode, Call, CoreEvent, Event, EventContext,
+    EventHandler, Songbird,
+};
+use tokio::{sync::Mutex, time};
+use tracing::{error, info, warn};
+
+const MAX_RECONNECT_ATTEMPTS: u32 = 5;
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+struct Reconnect {
+    manager: Arc<Songbird>,
+}
+
+#[async_trait]
+impl EventHandler for Reconnect {
+    async fn act(&self, ctx: &EventContext<'_>) -> Option<Event> {
+        let data = match ctx {
+            EventContext::DriverDisconnect(data) => data,
+            _ => return None,
+        };
+
+        // Leaving on purpose or being kicked from the channel shouldn't bring the bot back
+        let is_transient = match data.reason {
+            Some(DisconnectReason::WsClosed(Some(CloseCode::Disconnected))) => false,
+            Some(DisconnectReason::Requested) | None => false,
+            Some(_) => true,
+        };
+        let channel_id = match data.channel_id {
+            Some(channel_id) if is_transient => channel_id,
+            _ => return None,
+        };
+
+        info!(
+            "Voice connection in {} dropped ({:?}), reconnecting",
+            data.guild_id, data.reason
+        );
+        let manager = self.manager.clone();
+        let guild_id = data.guild_id;
+        tokio::spawn(async move {
+            let mut delay = Duration::from_secs(1);
+            for attempt in 1..=MAX_RECONNECT_ATTEMPTS {
+                time::sleep(delay).await;
+
+                // Someone may have told the bot to leave while it was waiting
+                if manager.get(guild_id).is_none() {
+                    return;
+                }
+
+                match manager.join(guild_id, channel_id).await {
+                    Ok(_) => {
+                        info!("Reconnected to {} after {} attempts", guild_id, attempt);
+                        return;
+                    }
+                    Err(e) => error!(error = ?e, "Failed to reconnect to {}", guild_id),
+                }
+                delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+            }
+
+            warn!("Giving up on reconnecting to {}", guild_id);
+            if let Err(e) = manager.remove(guild_id).await {
+                warn!(error = ?e, "Failed to leave channel");
+            }
+        });
+
+        None
+    }
+}
+
+pub async fn get_or_insert_call(manager: &Arc<Songbird>, guild_id: GuildId) -> Arc<Mutex<Call>> {
+    if let Some(handler_lock) = manager.get(guild_id) {
+        return handler_lock;
+    }
+
+    let handler_lock = manager.get_or_insert(guild_id);
+    let mut handler = handler_lock.lock().await;
+    handler.add_global_event(
+        CoreEvent::DriverDisconnect.into(),
+        Reconnect {
+            manager: manager.clone(),
+        },
+    );
+    #[cfg(feature = "stt")]
+    if crate::config::get().stt.is_some() {
+        crate::stt::listen(&mut handler, guild_id);
+    }
+    drop(handler);
+    handler_lock
+}
@@ -0,0 +1,191 @@
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use serenity::{
+    all::{CreateAllowedMentions, CreateMessage, GuildId, UserId},
+    cache::Cache,
+    http::Http,
+    prelude::{RwLock, TypeMap},
+};
+use songbird::Songbird;
+use tokio::{sync::Mutex, time};
+use tracing::{debug, error, warn};
+
+use crate::{
+    audio::speak_in_guild,
+    config,
+    error::{self, BotError},
+    state::BotState,
+};
+
+pub const MAX_REMINDERS: usize = 10;
+pub const MAX_DELAY: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Reminder {
+    pub user_id: u64,
+    pub guild_id: u64,
+    /// Seconds since the Unix epoch, so reminders survive restarts.
+    pub due: u64,
+    pub text: String,
+    /// Timers from `/timer` are read in whatever call the bot is in, not just with the user.
+    #[serde(default)]
+    pub timer: bool,
+}
+
+/// Reminders set with `/remindme`, saved whenever one is added or delivered.
+pub struct Reminders {
+    pub pending: Arc<Mutex<Vec<Reminder>>>,
+}
+
+impl Reminders {
+    pub fn new() -> Self {
+        Reminders {
+            pending: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Returns false if the user already has `MAX_REMINDERS` pending.
+    pub async fn add(&self, reminder: Reminder) -> Result<bool, BotError> {
+        {
+            let mut pending = self.pending.lock().await;
+            let count = pending
+                .iter()
+                .filter(|pending| pending.user_id == reminder.user_id)
+                .count();
+            if count >= MAX_REMINDERS {
+                return Ok(false);
+            }
+            pending.push(reminder);
+        }
+        self.save_reminders().await?;
+        Ok(true)
+    }
+
+    /// Removes and returns every reminder due by `now`.
+    pub async fn take_due(&self, now: u64) -> Vec<Reminder> {
+        let mut pending = self.pending.lock().await;
+        let (due, rest) = pending.drain(..).partition(|reminder| reminder.due <= now);
+        *pending = rest;
+        due
+    }
+
+    pub async fn load_reminders(&self) -> Result<(), BotError> {
+        debug!("Loading reminders...");
+        let pending = error::read_json(config::get().data_path("reminders.json")).await?;
+        *self.pending.lock().await = pending;
+        Ok(())
+    }
+
+    pub async fn save_reminders(&self) -> Result<(), BotError> {
+        debug!("Saving reminders...");
+        let pending = self.pending.lock().await;
+        error::write_json(config::get().data_path("reminders.json"), &*pending).await
+    }
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Parses delays like `90s`, `10m` or `1h30m`.
+pub fn parse_delay(text: &str) -> Option<Duration> {
+    let mut seconds = 0u64;
+    let mut number = String::new();
+    for c in text.trim().to_lowercase().chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => 24 * 60 * 60,
+            _ => return None,
+        };
+        let value = number.parse::<u64>().ok()?;
+        seconds = seconds.checked_add(value.checked_mul(unit)?)?;
+        number.clear();
+    }
+    if !number.is_empty() || seconds == 0 {
+        return None;
+    }
+    Some(Duration::from_secs(seconds))
+}
+
+/// Speaks reminders in the user's voice when they're in a call with the bot, and sends them
+/// as a DM otherwise or when reading them fails. Timers are spoken in any call the bot is in.
+pub async fn deliver_reminders(
+    http: Arc<Http>,
+    cache: Arc<Cache>,
+    data: Arc<RwLock<TypeMap>>,
+    songbird: Arc<Songbird>,
+) {
+    let state = BotState::get(&data).await;
+
+    let mut interval = time::interval(CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+
+        let due = state.reminders.take_due(unix_now()).await;
+        if due.is_empty() {
+            continue;
+        }
+        if let Err(e) = state.reminders.save_reminders().await {
+            error!(error = ?e, "Failed to save reminders");
+        }
+
+        for reminder in due {
+            let guild_id = GuildId::new(reminder.guild_id);
+            let user_id = UserId::new(reminder.user_id);
+            let in_call = match reminder.timer {
+                true => songbird.get(guild_id).is_some(),
+                false => state
+                    .guild_users
+                    .lock()
+                    .await
+                    .get(&guild_id)
+                    .is_some_and(|users| users.contains(&user_id)),
+            };
+            if in_call {
+                let voice = state.voice_manager.get_voice(reminder.user_id).await;
+                match speak_in_guild(
+                    &data,
+                    &cache,
+                    &songbird,
+                    guild_id,
+                    None,
+                    "Reminder",
+                    &reminder.text,
+                    &voice,
+                )
+                .await
+                {
+                    Ok(()) => continue,
+                    Err(e) if e.is_expected() => debug!(error = ?e, "Not reading reminder"),
+                    Err(e) => error!(error = ?e, "Failed to read reminder"),
+                }
+            }
+
+            let content = match reminder.timer {
+                true => format!("⏰ {}", reminder.text),
+                false => format!("⏰ Reminder: {}", reminder.text),
+            };
+            let message = CreateMessage::new()
+                .content(content)
+                .allowed_mentions(CreateAllowedMentions::new());
+            if let Err(e) = user_id.direct_message(&http, message).await {
+                warn!(error = ?e, "Failed to send reminder");
+            }
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use serenity::{
+    all::{ChannelId, GuildId},
+    client::Context,
+    prelude::{RwLock, TypeMap},
+};
+use songbird::{Call, Songbird};
+use tokio::sync::Mutex;
+use tracing::{debug, error, info};
+
+use crate::{
+    config,
+    error::{self, BotError},
+    events::sync_guild_users,
+    idle, reconnect,
+    state::{Binding, BotState},
+};
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Session {
+    guild_id: u64,
+    voice_channel_id: u64,
+    text_channel_id: Option<u64>,
+}
+
+pub async fn save_sessions(data: &RwLock<TypeMap>, manager: &Songbird) -> Result<(), BotError> {
+    debug!("Saving sessions...");
+    let bindings = BotState::get(data).await.bindings.lock().await.clone();
+
+    let calls = manager.iter().collect::<Vec<_>>();
+    let mut sessions = Vec::new();
+    for (guild_id, handler_lock) in calls {
+        let voice_channel_id = match handler_lock.lock().await.current_channel() {
+            Some(channel_id) => channel_id.0.get(),
+            None => continue,
+        };
+
+        let guild_id = GuildId::new(guild_id.0.get());
+        sessions.push(Session {
+            guild_id: guild_id.get(),
+            voice_channel_id,
+            text_channel_id: bindings
+                .get(&guild_id)
+                .map(|binding| binding.text_channel_id.get()),
+        });
+    }
+
+    error::write_json(config::get().data_path("sessions.json"), &sessions).await
+}
+
+pub async fn restore_sessions(ctx: &Context) -> Result<(), BotError> {
+    info!("Restoring sessions...");
+    let path = config::get().data_path("sessions.json");
+    let sessions: Vec<Session> = error::read_json(path.clone()).await?;
+
+    // Only restore once, a crash shouldn't replay sessions from an older shutdown
+    error::remove_json(path).await?;
+
+    let manager = match songbird::get(ctx).await {
+        Some(manager) => manager,
+        None => return Err(BotError::MissingState("songbird manager")),
+    };
+    let state = BotState::get(&ctx.data).await;
+
+    for session in sessions {
+        let guild_id = GuildId::new(session.guild_id);
+        let voice_channel_id = ChannelId::new(session.voice_channel_id);
+        let handler_lock = match rejoin(&manager, guild_id, voice_channel_id).await {
+            Ok(handler_lock) => handler_lock,
+            Err(e) => {
+                error!(error = ?e, "Failed to rejoin {}", guild_id);
+                continue;
+            }
+        };
+
+        if let Some(text_channel_id) = session.text_channel_id {
+            state.bindings.lock().await.insert(
+                guild_id,
+                Binding {
+                    voice_channel_id,
+                    text_channel_id: ChannelId::new(text_channel_id),
+                },
+            );
+        }
+
+        sync_guild_users(
+            &ctx.cache,
+            &ctx.data,
+            guild_id,
+            Some((voice_channel_id, &mut *handler_lock.lock().await)),
+        )
+        .await;
+        idle::mark_played(&ctx.data, guild_id).await;
+    }
+    Ok(())
+}
+
+async fn rejoin(
+    manager: &Arc<Songbird>,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+) -> Result<Arc<Mutex<Call>>, BotError> {
+    debug!("Rejoining {} in {}", channel_id, guild_id);
+    let handler_lock = reconnect::get_or_insert_call(manager, guild_id).await;
+    handler_lock.lock().await.join(channel_id).await?;
+    Ok(handler_lock)
+}
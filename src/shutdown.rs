@@ -0,0 +1,132 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use serenity::{
+    gateway::ShardManager,
+    prelude::{RwLock, TypeMap},
+};
+use songbird::Songbird;
+use tokio::time::{self, Instant};
+use tracing::{error, info, warn};
+
+use crate::{error::BotError, sessions, state::BotState};
+
+// Long enough to finish a message at the default duration limit
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(15);
+
+static SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
+static PANICKED: AtomicBool = AtomicBool::new(false);
+
+/// Set once shutdown starts, new messages are ignored from then on.
+pub fn is_shutting_down() -> bool {
+    SHUTTING_DOWN.load(Ordering::Relaxed)
+}
+
+/// Set by `/panic`, nothing is read and no calls are joined until `/unpanic`.
+pub fn is_panicked() -> bool {
+    PANICKED.load(Ordering::Relaxed)
+}
+
+/// Stops every queue and leaves every call straight away, without draining or saving
+/// sessions, and stays quiet until `unpanic`. Returns how many calls were left.
+pub async fn panic(manager: &Songbird) -> usize {
+    PANICKED.store(true, Ordering::Relaxed);
+    warn!("Panic switch pulled, leaving every call");
+
+    let calls = manager.iter().collect::<Vec<_>>();
+    for (_, call) in &calls {
+        call.lock().await.queue().stop();
+    }
+    for (guild_id, _) in &calls {
+        if let Err(e) = manager.remove(*guild_id).await {
+            warn!(error = ?e, "Failed to leave {}", guild_id.0);
+        }
+    }
+    calls.len()
+}
+
+/// Returns false if the bot wasn't panicked.
+pub fn unpanic() -> bool {
+    let was_panicked = PANICKED.swap(false, Ordering::Relaxed);
+    if was_panicked {
+        info!("Panic switch reset");
+    }
+    was_panicked
+}
+
+/// Stops taking messages, lets queued tracks play out, leaves every call and saves state
+/// before disconnecting from the gateway.
+pub async fn shut_down(
+    data: &RwLock<TypeMap>,
+    manager: &Arc<Songbird>,
+    shard_manager: &ShardManager,
+) {
+    SHUTTING_DOWN.store(true, Ordering::Relaxed);
+
+    // Calls are gone once we leave, so record them first
+    if let Err(e) = sessions::save_sessions(data, manager).await {
+        error!(error = ?e, "Failed to save sessions");
+    }
+
+    drain_queues(manager).await;
+    leave_calls(manager).await;
+
+    if let Err(e) = flush(data).await {
+        error!(error = ?e, "Failed to save data");
+    }
+
+    shard_manager.shutdown_all().await;
+    info!("Shut down cleanly");
+}
+
+/// Lets a helper bot's queued tracks play out and leaves its calls. Its sessions aren't saved and
+/// the stores it shares are saved by `shut_down`.
+pub async fn shut_down_helper(manager: &Songbird, shard_manager: &ShardManager) {
+    drain_queues(manager).await;
+    leave_calls(manager).await;
+    shard_manager.shutdown_all().await;
+}
+
+async fn leave_calls(manager: &Songbird) {
+    for (guild_id, _) in manager.iter().collect::<Vec<_>>() {
+        if let Err(e) = manager.remove(guild_id).await {
+            warn!(error = ?e, "Failed to leave {}", guild_id.0);
+        }
+    }
+}
+
+async fn drain_queues(manager: &Songbird) {
+    let started = Instant::now();
+    loop {
+        let mut queued = 0;
+        for (_, call) in manager.iter() {
+            queued += call.lock().await.queue().len();
+        }
+        if queued == 0 {
+            return;
+        }
+        if started.elapsed() >= DRAIN_TIMEOUT {
+            info!("Stopping {} unfinished tracks", queued);
+            for (_, call) in manager.iter() {
+                call.lock().await.queue().stop();
+            }
+            return;
+        }
+        time::sleep(Duration::from_millis(250)).await;
+    }
+}
+
+async fn flush(data: &RwLock<TypeMap>) -> Result<(), BotError> {
+    let state = BotState::get(data).await;
+    state.voice_manager.save_rolls().await?;
+    state.voice_manager.save_presets().await?;
+    state.guild_configs.save_configs().await?;
+    state.stats.save_stats().await?;
+    state.leaderboard.save_leaderboard().await?;
+    Ok(())
+}
@@ -0,0 +1,65 @@
+use std::path::PathBuf;
+
+use tokio::fs;
+
+use crate::config;
+
+/// Phoneme scripts for DECtalk's classic songs, sung in the requester's voice. Operators can add
+/// more, or replace these, with `.txt` files in the data directory's `songs` folder.
+const BUILTIN_SONGS: &[(&str, &str)] = &[
+    ("daisy-bell", include_str!("../songs/daisy-bell.txt")),
+    (
+        "happy-birthday",
+        include_str!("../songs/happy-birthday.txt"),
+    ),
+    (
+        "twinkle-twinkle",
+        include_str!("../songs/twinkle-twinkle.txt"),
+    ),
+];
+
+fn song_dir() -> PathBuf {
+    config::get().data_path("songs")
+}
+
+fn is_valid_title(title: &str) -> bool {
+    !title.is_empty()
+        && title
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+pub async fn load_song(title: &str) -> Option<String> {
+    if !is_valid_title(title) {
+        return None;
+    }
+    if let Ok(script) = fs::read_to_string(song_dir().join(format!("{}.txt", title))).await {
+        return Some(script);
+    }
+    BUILTIN_SONGS
+        .iter()
+        .find(|(name, _)| *name == title)
+        .map(|(_, script)| script.to_string())
+}
+
+pub async fn list_songs() -> Vec<String> {
+    let mut titles = BUILTIN_SONGS
+        .iter()
+        .map(|(name, _)| name.to_string())
+        .collect::<Vec<_>>();
+    if let Ok(mut entries) = fs::read_dir(song_dir()).await {
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let path = entry.path();
+            if path.extension().is_some_and(|extension| extension == "txt") {
+                if let Some(title) = path.file_stem().and_then(|stem| stem.to_str()) {
+                    if is_valid_title(title) {
+                        titles.push(title.to_string());
+                    }
+                }
+            }
+        }
+    }
+    titles.sort();
+    titles.dedup();
+    titles
+}
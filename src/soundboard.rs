@@ -0,0 +1,75 @@
+use std::{io, path::PathBuf};
+
+use serenity::all::GuildId;
+use tokio::fs;
+use tracing::{debug, info};
+
+use crate::{config, error::BotError};
+
+const MAX_SOUND_NAME_LENGTH: usize = 32;
+
+fn sound_dir(guild_id: GuildId) -> PathBuf {
+    config::get().data_path("sounds").join(guild_id.to_string())
+}
+
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_SOUND_NAME_LENGTH
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+pub async fn save_sound(guild_id: GuildId, name: &str, bytes: &[u8]) -> Result<(), BotError> {
+    debug!("Saving sound {} for {}", name, guild_id);
+    let dir = sound_dir(guild_id);
+    fs::create_dir_all(&dir)
+        .await
+        .map_err(BotError::io("create", &dir))?;
+    let path = dir.join(format!("{}.wav", name));
+    fs::write(&path, bytes)
+        .await
+        .map_err(BotError::io("write", path))
+}
+
+pub async fn load_sound(guild_id: GuildId, name: &str) -> Option<Vec<u8>> {
+    if !is_valid_name(name) {
+        return None;
+    }
+    fs::read(sound_dir(guild_id).join(format!("{}.wav", name)))
+        .await
+        .ok()
+}
+
+pub async fn remove_sound(guild_id: GuildId, name: &str) -> Result<bool, BotError> {
+    if !is_valid_name(name) {
+        return Ok(false);
+    }
+
+    info!("Removing sound {} for {}", name, guild_id);
+    let path = sound_dir(guild_id).join(format!("{}.wav", name));
+    match fs::remove_file(&path).await {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(BotError::io("remove", path)(e)),
+    }
+}
+
+pub async fn list_sounds(guild_id: GuildId) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut entries = match fs::read_dir(sound_dir(guild_id)).await {
+        Ok(entries) => entries,
+        Err(_) => return names,
+    };
+
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+        if path.extension().is_some_and(|extension| extension == "wav") {
+            if let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) {
+                names.push(name.to_string());
+            }
+        }
+    }
+    names.sort();
+    names
+}
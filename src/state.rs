@@ -0,0 +1,145 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use dectalk_bot_core::{engine::TtsEngine, pronunciation::PronunciationMap};
+use serenity::{
+    all::{ChannelId, GuildId, UserId},
+    prelude::{RwLock, TypeMap, TypeMapKey},
+};
+use tokio::{sync::Mutex, time::Instant};
+
+use crate::{
+    blacklist::Blacklist, clips::ClipBuffer, coordinator::Coordinator, duels::DuelRecords,
+    duplicates::DuplicateTracker, guild_config::GuildConfigManager, history::ReadHistory,
+    leaderboard::Leaderboard, macros::UserMacros, moderation::ModeratedMessages,
+    opt_outs::TranscriptionOptOuts, rate_limit::RateLimiter, reminders::Reminders,
+    stats::StatsManager, voice_manager::VoiceManager,
+};
+
+/// The voice channel the bot was asked to join with `/join` and the text channel it reads there.
+#[derive(Debug, Clone, Copy)]
+pub struct Binding {
+    pub voice_channel_id: ChannelId,
+    pub text_channel_id: ChannelId,
+}
+
+/// Everything the handlers share. It's stored once in the client's type map, so new state is a
+/// new field here rather than another key. Helper bots get their own copy from `for_helper`,
+/// sharing the saved stores, bindings and limits but not their calls.
+pub struct BotState {
+    pub voice_manager: Arc<VoiceManager>,
+    pub guild_configs: Arc<GuildConfigManager>,
+    pub pronunciations: Arc<PronunciationMap>,
+    pub blacklist: Arc<Blacklist>,
+    pub stats: Arc<StatsManager>,
+    pub reminders: Arc<Reminders>,
+    pub duels: Arc<DuelRecords>,
+    pub leaderboard: Arc<Leaderboard>,
+    pub macros: Arc<UserMacros>,
+    pub transcription_opt_outs: Arc<TranscriptionOptOuts>,
+    pub engine: Arc<dyn TtsEngine>,
+    pub coordinator: Arc<Coordinator>,
+    /// 0 for the main bot, helpers count up from 1.
+    pub instance: usize,
+    pub started: Instant,
+    pub guild_users: Mutex<HashMap<GuildId, HashSet<UserId>>>,
+    pub last_played: Mutex<HashMap<GuildId, Instant>>,
+    pub serving: Mutex<HashMap<GuildId, UserId>>,
+    pub bindings: Arc<Mutex<HashMap<GuildId, Binding>>>,
+    pub announced: Mutex<HashMap<GuildId, Instant>>,
+    pub sung: Mutex<HashMap<GuildId, Instant>>,
+    pub duels_started: Mutex<HashMap<(GuildId, UserId), Instant>>,
+    pub reports_sent: Mutex<HashMap<UserId, Instant>>,
+    pub welcomed: Mutex<HashMap<(GuildId, UserId), Instant>>,
+    pub user_rate_limits: Arc<Mutex<RateLimiter<(GuildId, UserId)>>>,
+    pub guild_throughput: Arc<Mutex<RateLimiter<GuildId>>>,
+    pub recent_messages: Arc<Mutex<DuplicateTracker<(GuildId, UserId)>>>,
+    pub moderated: Mutex<ModeratedMessages>,
+    pub clips: Mutex<ClipBuffer>,
+    pub read_history: Mutex<ReadHistory>,
+}
+
+pub struct BotStateKey;
+
+impl TypeMapKey for BotStateKey {
+    type Value = Arc<BotState>;
+}
+
+impl BotState {
+    /// Everything starts out empty, the saved stores are loaded into it before the client starts.
+    pub fn new(
+        pronunciations: PronunciationMap,
+        engine: Arc<dyn TtsEngine>,
+        coordinator: Arc<Coordinator>,
+    ) -> Self {
+        BotState {
+            voice_manager: Arc::new(VoiceManager::new()),
+            guild_configs: Arc::new(GuildConfigManager::new()),
+            pronunciations: Arc::new(pronunciations),
+            blacklist: Arc::new(Blacklist::new()),
+            stats: Arc::new(StatsManager::new()),
+            reminders: Arc::new(Reminders::new()),
+            duels: Arc::new(DuelRecords::new()),
+            leaderboard: Arc::new(Leaderboard::new()),
+            macros: Arc::new(UserMacros::new()),
+            transcription_opt_outs: Arc::new(TranscriptionOptOuts::new()),
+            engine,
+            coordinator,
+            instance: 0,
+            started: Instant::now(),
+            guild_users: Mutex::new(HashMap::new()),
+            last_played: Mutex::new(HashMap::new()),
+            serving: Mutex::new(HashMap::new()),
+            bindings: Arc::new(Mutex::new(HashMap::new())),
+            announced: Mutex::new(HashMap::new()),
+            sung: Mutex::new(HashMap::new()),
+            duels_started: Mutex::new(HashMap::new()),
+            reports_sent: Mutex::new(HashMap::new()),
+            welcomed: Mutex::new(HashMap::new()),
+            user_rate_limits: Arc::new(Mutex::new(RateLimiter::new())),
+            guild_throughput: Arc::new(Mutex::new(RateLimiter::new())),
+            recent_messages: Arc::new(Mutex::new(DuplicateTracker::new())),
+            moderated: Mutex::new(ModeratedMessages::new()),
+            clips: Mutex::new(ClipBuffer::new()),
+            read_history: Mutex::new(ReadHistory::new()),
+        }
+    }
+
+    /// State for helper `instance`, sharing everything that's saved and the limits with this one.
+    pub fn for_helper(&self, instance: usize) -> Self {
+        BotState {
+            voice_manager: self.voice_manager.clone(),
+            guild_configs: self.guild_configs.clone(),
+            pronunciations: self.pronunciations.clone(),
+            blacklist: self.blacklist.clone(),
+            stats: self.stats.clone(),
+            reminders: self.reminders.clone(),
+            duels: self.duels.clone(),
+            leaderboard: self.leaderboard.clone(),
+            macros: self.macros.clone(),
+            transcription_opt_outs: self.transcription_opt_outs.clone(),
+            // Limits are per guild and user however many accounts are reading
+            bindings: self.bindings.clone(),
+            user_rate_limits: self.user_rate_limits.clone(),
+            guild_throughput: self.guild_throughput.clone(),
+            recent_messages: self.recent_messages.clone(),
+            instance,
+            ..BotState::new(
+                PronunciationMap::new(),
+                self.engine.clone(),
+                self.coordinator.clone(),
+            )
+        }
+    }
+
+    /// The state is inserted while the client is built, before any handler can run.
+    pub async fn get(data: &RwLock<TypeMap>) -> Arc<BotState> {
+        data.read()
+            .await
+            .get::<BotStateKey>()
+            .expect("BotState is inserted when the client is built")
+            .clone()
+    }
+}
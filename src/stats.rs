@@ -0,0 +1,84 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use serenity::prelude::{RwLock, TypeMap};
+use tokio::sync::Mutex;
+use tracing::debug;
+
+use crate::{
+    config,
+    error::{self, BotError},
+    state::BotState,
+};
+
+/// Counts how often each command and feature is used per guild. Counting happens on every
+/// message, so changes are saved by the maintenance task and on shutdown rather than right away.
+pub struct StatsManager {
+    pub counts: Arc<Mutex<HashMap<u64, BTreeMap<String, u64>>>>,
+    dirty: AtomicBool,
+}
+
+impl StatsManager {
+    pub fn new() -> Self {
+        StatsManager {
+            counts: Arc::new(Mutex::new(HashMap::new())),
+            dirty: AtomicBool::new(false),
+        }
+    }
+
+    pub async fn record(&self, guild_id: u64, feature: &str) {
+        let mut counts = self.counts.lock().await;
+        *counts
+            .entry(guild_id)
+            .or_default()
+            .entry(feature.to_string())
+            .or_default() += 1;
+        self.dirty.store(true, Ordering::Relaxed);
+    }
+
+    pub async fn guild_counts(&self, guild_id: u64) -> BTreeMap<String, u64> {
+        self.counts
+            .lock()
+            .await
+            .get(&guild_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub async fn total_counts(&self) -> BTreeMap<String, u64> {
+        let mut totals = BTreeMap::new();
+        for counts in self.counts.lock().await.values() {
+            for (feature, count) in counts {
+                *totals.entry(feature.clone()).or_default() += count;
+            }
+        }
+        totals
+    }
+
+    pub async fn load_stats(&self) -> Result<(), BotError> {
+        debug!("Loading stats...");
+        let counts = error::read_json(config::get().data_path("stats.json")).await?;
+        *self.counts.lock().await = counts;
+        Ok(())
+    }
+
+    /// Writes the counts if anything changed since the last save.
+    pub async fn save_stats(&self) -> Result<(), BotError> {
+        if !self.dirty.swap(false, Ordering::Relaxed) {
+            return Ok(());
+        }
+        debug!("Saving stats...");
+        let counts = self.counts.lock().await;
+        error::write_json(config::get().data_path("stats.json"), &*counts).await
+    }
+}
+
+pub async fn record(data: &RwLock<TypeMap>, guild_id: u64, feature: &str) {
+    let state = BotState::get(data).await;
+    state.stats.record(guild_id, feature).await;
+}
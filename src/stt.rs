@@ -0,0 +1,399 @@
+use std::{
+    collections::HashMap,
+    io::Cursor,
+    sync::{Arc, LazyLock, Mutex},
+    time::Duration,
+};
+
+use hound::{SampleFormat, WavSpec, WavWriter};
+use reqwest::{
+    multipart::{Form, Part},
+    Client,
+};
+use serde::Deserialize;
+use serenity::{
+    all::{ChannelId, CreateAllowedMentions, CreateMessage, GuildId, UserId},
+    async_trait,
+    http::Http,
+    prelude::{RwLock, TypeMap},
+};
+use songbird::{Call, CoreEvent, Event, EventContext, EventHandler, Songbird};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::{debug, error, info, warn};
+
+use crate::{
+    config::{self, SttConfig},
+    events::leave_guild,
+    guild_config::GuildConfig,
+    shutdown,
+    state::BotState,
+};
+
+const SAMPLE_RATE: u32 = 16_000;
+// Songbird ticks every 20ms
+const PAUSE_TICKS: u32 = 40;
+const MIN_SAMPLES: usize = SAMPLE_RATE as usize / 2;
+// Whisper works on 30 second windows
+const MAX_SAMPLES: usize = SAMPLE_RATE as usize * 30;
+
+static UTTERANCES: LazyLock<broadcast::Sender<Utterance>> =
+    LazyLock::new(|| broadcast::channel(16).0);
+static CLIENT: LazyLock<Client> = LazyLock::new(Client::new);
+
+/// One person's speech up to a pause, as 16kHz mono audio.
+#[derive(Debug, Clone)]
+pub struct Utterance {
+    pub guild_id: u64,
+    pub user_id: u64,
+    samples: Arc<Vec<i16>>,
+}
+
+#[derive(Default)]
+struct Speech {
+    samples: Vec<i16>,
+    silent_ticks: u32,
+}
+
+#[derive(Default)]
+struct Speakers {
+    users: HashMap<u32, u64>,
+    speech: HashMap<u32, Speech>,
+}
+
+/// Collects what each user in a call says until they pause, then hands it to
+/// `transcribe_speech`.
+#[derive(Clone)]
+struct Listener {
+    guild_id: u64,
+    speakers: Arc<Mutex<Speakers>>,
+}
+
+#[async_trait]
+impl EventHandler for Listener {
+    async fn act(&self, ctx: &EventContext<'_>) -> Option<Event> {
+        let mut speakers = self.speakers.lock().unwrap_or_else(|e| e.into_inner());
+        match ctx {
+            EventContext::SpeakingStateUpdate(speaking) => {
+                if let Some(user_id) = speaking.user_id {
+                    speakers.users.insert(speaking.ssrc, user_id.0);
+                }
+            }
+            EventContext::ClientDisconnect(disconnect) => {
+                let user_id = disconnect.user_id.0;
+                speakers.users.retain(|_, user| *user != user_id);
+            }
+            EventContext::VoiceTick(tick) => {
+                let mut finished = Vec::new();
+                for (ssrc, data) in &tick.speaking {
+                    let voice = match &data.decoded_voice {
+                        Some(voice) => voice,
+                        None => continue,
+                    };
+                    let speech = speakers.speech.entry(*ssrc).or_default();
+                    speech.silent_ticks = 0;
+                    speech.samples.extend(downsample(voice));
+                    if speech.samples.len() >= MAX_SAMPLES {
+                        finished.push(*ssrc);
+                    }
+                }
+                for ssrc in &tick.silent {
+                    if let Some(speech) = speakers.speech.get_mut(ssrc) {
+                        speech.silent_ticks += 1;
+                        if speech.silent_ticks >= PAUSE_TICKS {
+                            finished.push(*ssrc);
+                        }
+                    }
+                }
+
+                for ssrc in finished {
+                    let speech = match speakers.speech.remove(&ssrc) {
+                        Some(speech) if speech.samples.len() >= MIN_SAMPLES => speech,
+                        _ => continue,
+                    };
+                    let user_id = match speakers.users.get(&ssrc) {
+                        Some(user_id) => *user_id,
+                        None => continue,
+                    };
+                    // Sending only fails when nobody is transcribing
+                    let _ = UTTERANCES.send(Utterance {
+                        guild_id: self.guild_id,
+                        user_id,
+                        samples: Arc::new(speech.samples),
+                    });
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+}
+
+/// Starts listening to everyone in the call. Songbird only decodes what it receives when it's
+/// created with `DecodeMode::Decode`.
+pub fn listen(call: &mut Call, guild_id: GuildId) {
+    let listener = Listener {
+        guild_id: guild_id.get(),
+        speakers: Arc::new(Mutex::new(Speakers::default())),
+    };
+    call.add_global_event(CoreEvent::SpeakingStateUpdate.into(), listener.clone());
+    call.add_global_event(CoreEvent::ClientDisconnect.into(), listener.clone());
+    call.add_global_event(CoreEvent::VoiceTick.into(), listener);
+}
+
+pub fn subscribe() -> broadcast::Receiver<Utterance> {
+    UTTERANCES.subscribe()
+}
+
+/// Turns Discord's 48kHz stereo into the 16kHz mono whisper expects, averaging each group of
+/// samples so the decimation doesn't alias too badly.
+fn downsample(voice: &[i16]) -> impl Iterator<Item = i16> + '_ {
+    voice
+        .chunks_exact(6)
+        .map(|chunk| (chunk.iter().map(|&sample| sample as i32).sum::<i32>() / 6) as i16)
+}
+
+#[derive(Deserialize)]
+struct Inference {
+    text: String,
+}
+
+/// Sends the audio to a whisper.cpp server's `/inference` endpoint.
+async fn transcribe(stt: &SttConfig, samples: &[i16]) -> anyhow::Result<String> {
+    let mut wav = Cursor::new(Vec::new());
+    let spec = WavSpec {
+        channels: 1,
+        sample_rate: SAMPLE_RATE,
+        bits_per_sample: 16,
+        sample_format: SampleFormat::Int,
+    };
+    let mut writer = WavWriter::new(&mut wav, spec)?;
+    for sample in samples {
+        writer.write_sample(*sample)?;
+    }
+    writer.finalize()?;
+
+    let mut form = Form::new()
+        .part(
+            "file",
+            Part::bytes(wav.into_inner())
+                .file_name("speech.wav")
+                .mime_str("audio/wav")?,
+        )
+        .text("response_format", "json");
+    if let Some(language) = &stt.language {
+        form = form.text("language", language.clone());
+    }
+    let inference = CLIENT
+        .post(&stt.endpoint)
+        .timeout(Duration::from_secs(stt.timeout))
+        .multipart(form)
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<Inference>()
+        .await?;
+    Ok(inference.text.trim().to_string())
+}
+
+/// Whisper marks silence and noise like `[BLANK_AUDIO]` or `(wind blowing)` rather than
+/// returning nothing.
+fn is_speech(text: &str) -> bool {
+    let text = text.trim();
+    let is_marker = (text.starts_with('[') && text.ends_with(']'))
+        || (text.starts_with('(') && text.ends_with(')'));
+    !text.is_empty() && !is_marker
+}
+
+/// Spoken as "hey dectalk skip".
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum VoiceCommand {
+    Skip,
+    Stop,
+    Leave,
+}
+
+// Whisper tends to split or respell the name
+const WAKE_NAMES: &[&str] = &["dectalk", "decktalk", "dectalks", "dectok", "decktok"];
+
+fn parse_voice_command(text: &str) -> Option<VoiceCommand> {
+    let words = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>();
+    for (index, word) in words.iter().enumerate() {
+        if word != "hey" {
+            continue;
+        }
+        for name_length in 1..=2 {
+            let name_end = index + 1 + name_length;
+            let name = match words.get(index + 1..name_end) {
+                Some(name) => name.concat(),
+                None => continue,
+            };
+            if !WAKE_NAMES.contains(&name.as_str()) {
+                continue;
+            }
+            return match words.get(name_end).map(String::as_str) {
+                Some("skip") => Some(VoiceCommand::Skip),
+                Some("stop") => Some(VoiceCommand::Stop),
+                Some("leave") => Some(VoiceCommand::Leave),
+                _ => None,
+            };
+        }
+    }
+    None
+}
+
+/// The same rule as `/skip` and `/stop`: Mute Members, Manage Server or the server's admin role.
+async fn can_control_playback(
+    http: &Http,
+    config: &GuildConfig,
+    guild_id: GuildId,
+    user_id: UserId,
+) -> bool {
+    if config::get().is_operator(user_id.get()) {
+        return true;
+    }
+    let member = match http.get_member(guild_id, user_id).await {
+        Ok(member) => member,
+        Err(e) => {
+            debug!(error = ?e, "Failed to get member");
+            return false;
+        }
+    };
+    if config.admin_role.is_some_and(|role| {
+        member
+            .roles
+            .iter()
+            .any(|member_role| member_role.get() == role)
+    }) {
+        return true;
+    }
+    match http.get_guild(guild_id).await {
+        Ok(guild) => {
+            let permissions = guild.member_permissions(&member);
+            permissions.mute_members() || permissions.manage_guild()
+        }
+        Err(e) => {
+            debug!(error = ?e, "Failed to get guild");
+            false
+        }
+    }
+}
+
+async fn run_voice_command(
+    data: &RwLock<TypeMap>,
+    songbird: &Songbird,
+    guild_id: GuildId,
+    command: VoiceCommand,
+) {
+    let handler_lock = match songbird.get(guild_id) {
+        Some(handler_lock) => handler_lock,
+        None => return,
+    };
+    match command {
+        VoiceCommand::Skip => {
+            let handler = handler_lock.lock().await;
+            if !handler.queue().is_empty() {
+                if let Err(e) = handler.queue().skip() {
+                    warn!(error = ?e, "Failed to skip track");
+                }
+            }
+        }
+        VoiceCommand::Stop => handler_lock.lock().await.queue().stop(),
+        VoiceCommand::Leave => leave_guild(data, songbird, guild_id).await,
+    }
+}
+
+/// Transcribes what people say in calls, running "hey dectalk" commands for guilds that turned
+/// on `voice_commands` and posting the rest to `transcript_channel` for guilds that turned on
+/// `transcribe_speech`.
+pub async fn transcribe_speech(
+    http: Arc<Http>,
+    data: Arc<RwLock<TypeMap>>,
+    songbird: Arc<Songbird>,
+    stt: SttConfig,
+) {
+    let state = BotState::get(&data).await;
+
+    let mut utterances = subscribe();
+    loop {
+        let utterance = match utterances.recv().await {
+            Ok(utterance) => utterance,
+            Err(RecvError::Lagged(skipped)) => {
+                warn!("Speech transcription missed {} utterances", skipped);
+                continue;
+            }
+            Err(RecvError::Closed) => return,
+        };
+        if shutdown::is_panicked() {
+            continue;
+        }
+
+        let config = state.guild_configs.get_config(utterance.guild_id).await;
+        let channel_id = config
+            .transcript_channel
+            .filter(|_| config.transcribe_speech);
+        if !config.enabled || (channel_id.is_none() && !config.voice_commands) {
+            continue;
+        }
+        if config.blacklist.contains(&utterance.user_id)
+            || state.blacklist.contains(utterance.user_id).await
+            || state
+                .transcription_opt_outs
+                .contains(utterance.user_id)
+                .await
+        {
+            continue;
+        }
+
+        let text = match transcribe(&stt, &utterance.samples).await {
+            Ok(text) if is_speech(&text) => text,
+            Ok(_) => continue,
+            Err(e) => {
+                error!(error = ?e, "Failed to transcribe speech");
+                continue;
+            }
+        };
+
+        let guild_id = GuildId::new(utterance.guild_id);
+        if config.voice_commands {
+            if let Some(command) = parse_voice_command(&text) {
+                let user_id = UserId::new(utterance.user_id);
+                if !can_control_playback(&http, &config, guild_id, user_id).await {
+                    debug!("Ignoring voice command from {}", user_id);
+                    continue;
+                }
+                info!(
+                    "Running voice command {:?} from {} in {}",
+                    command, utterance.user_id, guild_id
+                );
+                run_voice_command(&data, &songbird, guild_id, command).await;
+                continue;
+            }
+        }
+
+        let channel_id = match channel_id {
+            Some(channel_id) => ChannelId::new(channel_id),
+            None => continue,
+        };
+        let name = match http
+            .get_member(guild_id, UserId::new(utterance.user_id))
+            .await
+        {
+            Ok(member) => member.display_name().to_string(),
+            Err(_) => "Someone".to_string(),
+        };
+        let message = CreateMessage::new()
+            .content(format!(
+                "🎙 {}: {}",
+                name,
+                dectalk_bot_core::preprocess::truncate(&text, 1900)
+            ))
+            .allowed_mentions(CreateAllowedMentions::new());
+        if let Err(e) = channel_id.send_message(&http, message).await {
+            debug!(error = ?e, "Failed to post speech transcript");
+        }
+    }
+}
@@ -0,0 +1,21 @@
+#[cfg(unix)]
+use tracing::warn;
+
+/// Tells systemd the bot is connected, for `Type=notify` units. Does nothing outside systemd.
+pub fn notify_ready() {
+    #[cfg(unix)]
+    notify(&[sd_notify::NotifyState::Ready]);
+}
+
+/// Tells systemd the bot is draining and about to exit.
+pub fn notify_stopping() {
+    #[cfg(unix)]
+    notify(&[sd_notify::NotifyState::Stopping]);
+}
+
+#[cfg(unix)]
+fn notify(state: &[sd_notify::NotifyState]) {
+    if let Err(e) = sd_notify::notify(state) {
+        warn!(error = ?e, "Failed to notify systemd");
+    }
+}
@@ -0,0 +1,273 @@
+use std::{io::Cursor, sync::Arc, time::Duration};
+
+use anyhow::anyhow;
+use audiopus::{coder::Encoder, Application, Channels, SampleRate};
+use ogg::{PacketWriteEndInfo, PacketWriter};
+use reqwest::{multipart, Client, RequestBuilder};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::json;
+use serenity::prelude::{RwLock, TypeMap};
+use tracing::{debug, error, info};
+
+use dectalk_bot_core::dectalk::{DectalkVoice, Language};
+
+use crate::{
+    audio::synthesize,
+    config::{self, TelegramConfig},
+    guild_config::GuildConfig,
+    preprocess::{estimate_duration, process_message, ESTIMATE_MARGIN},
+    rate_limit::RateLimiter,
+    shutdown,
+    state::BotState,
+};
+
+const API_URL: &str = "https://api.telegram.org";
+const POLL_TIMEOUT: u64 = 30;
+const RETRY_DELAY: Duration = Duration::from_secs(30);
+const RATE_LIMIT: f64 = 5.0;
+const RATE_LIMIT_PERIOD: Duration = Duration::from_secs(30);
+const OPUS_RATE: u32 = 48000;
+// 20ms frames
+const OPUS_FRAME: usize = 960;
+const OGG_SERIAL: u32 = 1;
+
+#[derive(Deserialize)]
+struct Response<T> {
+    ok: bool,
+    result: Option<T>,
+    description: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Update {
+    update_id: i64,
+    message: Option<TelegramMessage>,
+}
+
+#[derive(Deserialize)]
+struct TelegramMessage {
+    message_id: i64,
+    chat: Chat,
+    from: Option<User>,
+    text: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Chat {
+    id: i64,
+}
+
+#[derive(Deserialize)]
+struct User {
+    id: u64,
+}
+
+/// Answers every text message sent to the Telegram bot with a voice note of it read aloud.
+pub async fn run_bot(telegram: TelegramConfig, data: Arc<RwLock<TypeMap>>) {
+    let client = Client::new();
+    let mut rate_limits = RateLimiter::new();
+    let mut offset = 0;
+    info!("Polling Telegram for messages");
+    while !shutdown::is_shutting_down() {
+        let updates = get_updates(&client, &telegram.token, offset)
+            .await
+            .inspect_err(|e| error!(error = ?e, "Failed to get Telegram updates"))
+            .ok();
+        let updates = match updates {
+            Some(updates) => updates,
+            None => {
+                tokio::time::sleep(RETRY_DELAY).await;
+                continue;
+            }
+        };
+
+        for update in updates {
+            offset = update.update_id + 1;
+            let message = match update.message {
+                Some(message) => message,
+                None => continue,
+            };
+            let (user, text) = match (&message.from, &message.text) {
+                (Some(user), Some(text)) => (user, text),
+                _ => continue,
+            };
+            if text.starts_with("/start") {
+                let reply = "Send me a message and I'll read it back in your own DECtalk voice.";
+                if let Err(e) = send_message(&client, &telegram.token, message.chat.id, reply).await
+                {
+                    error!(error = ?e, "Failed to send Telegram message");
+                }
+                continue;
+            }
+            if !rate_limits.try_take(message.chat.id, 1.0, RATE_LIMIT, RATE_LIMIT_PERIOD) {
+                debug!("Rate limited Telegram chat {}", message.chat.id);
+                continue;
+            }
+
+            let voice_note = match render(&data, user.id, text).await {
+                Ok(Some(voice_note)) => voice_note,
+                Ok(None) => continue,
+                Err(e) => {
+                    error!(error = ?e, "Failed to render Telegram voice note");
+                    continue;
+                }
+            };
+            if let Err(e) = send_voice(&client, &telegram.token, &message, voice_note).await {
+                error!(error = ?e, "Failed to send Telegram voice note");
+            }
+        }
+    }
+}
+
+/// Runs the text through the default server settings and returns it as OGG/Opus, or None if
+/// there's nothing left to read or it's over the default limits.
+async fn render(
+    data: &RwLock<TypeMap>,
+    user_id: u64,
+    text: &str,
+) -> anyhow::Result<Option<Vec<u8>>> {
+    let state = BotState::get(data).await;
+
+    let config = GuildConfig::default();
+    let limits = config::get().limits.default;
+    if text.len() > limits.max_message_length {
+        return Ok(None);
+    }
+    let content = match config.apply_profanity_filter(&process_message(text, &config)) {
+        Some(content) => state.pronunciations.apply(&content),
+        None => return Ok(None),
+    };
+    if content.is_empty() || estimate_duration(&content) > limits.max_duration * ESTIMATE_MARGIN {
+        return Ok(None);
+    }
+
+    let voice = DectalkVoice::generate(user_id, 0);
+    let wav = synthesize(&content, &voice, Language::English).await?;
+    Ok(Some(encode_ogg_opus(&wav)?))
+}
+
+async fn get_updates(client: &Client, token: &str, offset: i64) -> anyhow::Result<Vec<Update>> {
+    let request = client
+        .get(method_url(token, "getUpdates"))
+        .query(&[("offset", offset), ("timeout", POLL_TIMEOUT as i64)])
+        .timeout(Duration::from_secs(POLL_TIMEOUT + 10));
+    call(request).await
+}
+
+async fn send_message(
+    client: &Client,
+    token: &str,
+    chat_id: i64,
+    text: &str,
+) -> anyhow::Result<()> {
+    let request = client
+        .post(method_url(token, "sendMessage"))
+        .json(&json!({ "chat_id": chat_id, "text": text }));
+    call::<serde_json::Value>(request).await.map(|_| ())
+}
+
+async fn send_voice(
+    client: &Client,
+    token: &str,
+    message: &TelegramMessage,
+    voice_note: Vec<u8>,
+) -> anyhow::Result<()> {
+    let form = multipart::Form::new()
+        .text("chat_id", message.chat.id.to_string())
+        .text("reply_to_message_id", message.message_id.to_string())
+        .part(
+            "voice",
+            multipart::Part::bytes(voice_note)
+                .file_name("dectalk.ogg")
+                .mime_str("audio/ogg")?,
+        );
+    let request = client.post(method_url(token, "sendVoice")).multipart(form);
+    call::<serde_json::Value>(request).await.map(|_| ())
+}
+
+fn method_url(token: &str, method: &str) -> String {
+    format!("{}/bot{}/{}", API_URL, token, method)
+}
+
+/// The token is part of every URL, so it's stripped from errors before they can be logged and
+/// end up in the ops channel.
+async fn call<T: DeserializeOwned>(request: RequestBuilder) -> anyhow::Result<T> {
+    let response: Response<T> = request
+        .send()
+        .await
+        .map_err(reqwest::Error::without_url)?
+        .json()
+        .await
+        .map_err(reqwest::Error::without_url)?;
+    check(response)
+}
+
+fn check<T>(response: Response<T>) -> anyhow::Result<T> {
+    match response.result {
+        Some(result) if response.ok => Ok(result),
+        _ => Err(anyhow!(response
+            .description
+            .unwrap_or_else(|| "Telegram request failed".to_string()))),
+    }
+}
+
+/// Telegram only shows OGG/Opus as voice notes, so resample DECtalk's WAV to 48kHz and encode it.
+fn encode_ogg_opus(wav: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let mut reader = hound::WavReader::new(Cursor::new(wav))?;
+    let input_rate = reader.spec().sample_rate;
+    let samples: Vec<i16> = reader.samples::<i16>().map(|s| s.unwrap_or(0)).collect();
+    let samples = resample(&samples, input_rate, OPUS_RATE);
+
+    let encoder = Encoder::new(SampleRate::Hz48000, Channels::Mono, Application::Voip)?;
+    let pre_skip = encoder.lookahead()? as u16;
+
+    let mut head = b"OpusHead".to_vec();
+    head.push(1);
+    head.push(1);
+    head.extend_from_slice(&pre_skip.to_le_bytes());
+    head.extend_from_slice(&input_rate.to_le_bytes());
+    head.extend_from_slice(&0i16.to_le_bytes());
+    head.push(0);
+    let vendor = b"dectalk";
+    let mut tags = b"OpusTags".to_vec();
+    tags.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+    tags.extend_from_slice(vendor);
+    tags.extend_from_slice(&0u32.to_le_bytes());
+
+    let mut writer = PacketWriter::new(Vec::new());
+    writer.write_packet(head, OGG_SERIAL, PacketWriteEndInfo::EndPage, 0)?;
+    writer.write_packet(tags, OGG_SERIAL, PacketWriteEndInfo::EndPage, 0)?;
+
+    let frames = samples.chunks(OPUS_FRAME).collect::<Vec<_>>();
+    let mut packet = [0; 4000];
+    for (i, frame) in frames.iter().enumerate() {
+        let mut frame = frame.to_vec();
+        frame.resize(OPUS_FRAME, 0);
+        let length = encoder.encode(&frame, &mut packet)?;
+        let end = if i + 1 == frames.len() {
+            PacketWriteEndInfo::EndStream
+        } else {
+            PacketWriteEndInfo::NormalPacket
+        };
+        let granule = pre_skip as u64 + ((i + 1) * OPUS_FRAME) as u64;
+        writer.write_packet(packet[..length].to_vec(), OGG_SERIAL, end, granule)?;
+    }
+    Ok(writer.into_inner())
+}
+
+/// Linear interpolation, which is plenty for speech this lo-fi.
+fn resample(samples: &[i16], from: u32, to: u32) -> Vec<i16> {
+    if from == to || samples.is_empty() {
+        return samples.to_vec();
+    }
+    let length = samples.len() as u64 * to as u64 / from as u64;
+    (0..length)
+        .map(|i| {
+            let position = i as f64 * from as f64 / to as f64;
+            let index = position as usize;
+            let next = samples.get(index + 1).unwrap_or(&samples[index]);
+            let fraction = position - index as f64;
+            (samples[index] as f64 * (1.0 - fraction) + *next as f64 * fraction) as i16
+        })
+        .collect()
+}
@@ -0,0 +1,177 @@
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{Arc, LazyLock, Mutex},
+};
+
+use serde::Serialize;
+use serenity::{
+    all::{ChannelId, CreateAllowedMentions, CreateMessage},
+    async_trait,
+    http::Http,
+    prelude::{RwLock, TypeMap},
+};
+use songbird::{tracks::TrackHandle, Event, EventContext, EventHandler, TrackEvent};
+use tokio::{
+    fs,
+    sync::broadcast::{self, error::RecvError},
+};
+use tracing::{debug, warn};
+
+use crate::{config::CaptionsConfig, state::BotState};
+
+static EVENTS: LazyLock<broadcast::Sender<TranscriptEvent>> =
+    LazyLock::new(|| broadcast::channel(64).0);
+/// What each guild is hearing right now, for captions that poll instead of streaming.
+static CURRENT: LazyLock<Mutex<HashMap<u64, String>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// What the bot is saying, streamed to caption overlays.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TranscriptEvent {
+    Speaking {
+        guild_id: u64,
+        /// 0 when the speaker isn't a Discord user, e.g. someone on IRC.
+        user_id: u64,
+        user: String,
+        text: String,
+    },
+    Finished {
+        guild_id: u64,
+    },
+}
+
+impl TranscriptEvent {
+    pub fn guild_id(&self) -> u64 {
+        match self {
+            TranscriptEvent::Speaking { guild_id, .. } | TranscriptEvent::Finished { guild_id } => {
+                *guild_id
+            }
+        }
+    }
+}
+
+pub fn subscribe() -> broadcast::Receiver<TranscriptEvent> {
+    EVENTS.subscribe()
+}
+
+/// The text being spoken in the guild, or an empty string between messages.
+pub fn current(guild_id: u64) -> String {
+    CURRENT
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(&guild_id)
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Publishes `speaking` when the track actually starts, rather than when it's queued, so
+/// captions line up with the audio.
+pub fn follow_track(track: &TrackHandle, speaking: TranscriptEvent) {
+    let guild_id = speaking.guild_id();
+    if let Err(e) = track.add_event(Event::Track(TrackEvent::Play), Publish(speaking)) {
+        warn!(error = ?e, "Failed to follow track");
+    }
+    if let Err(e) = track.add_event(
+        Event::Track(TrackEvent::End),
+        Publish(TranscriptEvent::Finished { guild_id }),
+    ) {
+        warn!(error = ?e, "Failed to follow track");
+    }
+}
+
+struct Publish(TranscriptEvent);
+
+#[async_trait]
+impl EventHandler for Publish {
+    async fn act(&self, _ctx: &EventContext<'_>) -> Option<Event> {
+        {
+            let mut current = CURRENT.lock().unwrap_or_else(|e| e.into_inner());
+            match &self.0 {
+                TranscriptEvent::Speaking { guild_id, text, .. } => {
+                    current.insert(*guild_id, text.clone());
+                }
+                TranscriptEvent::Finished { guild_id } => {
+                    current.remove(guild_id);
+                }
+            }
+        }
+        // Sending only fails when nobody is listening
+        let _ = EVENTS.send(self.0.clone());
+        None
+    }
+}
+
+/// Posts everything the bot says to each guild's `transcript_channel`, for members who can't
+/// hear it or joined late.
+pub async fn post_to_channels(http: Arc<Http>, data: Arc<RwLock<TypeMap>>) {
+    let state = BotState::get(&data).await;
+
+    let mut events = subscribe();
+    loop {
+        let (guild_id, user, text) = match events.recv().await {
+            Ok(TranscriptEvent::Speaking {
+                guild_id,
+                user,
+                text,
+                ..
+            }) if !text.is_empty() => (guild_id, user, text),
+            Ok(_) => continue,
+            Err(RecvError::Lagged(skipped)) => {
+                warn!("Transcript channels missed {} messages", skipped);
+                continue;
+            }
+            Err(RecvError::Closed) => return,
+        };
+
+        let channel_id = match state
+            .guild_configs
+            .get_config(guild_id)
+            .await
+            .transcript_channel
+        {
+            Some(channel_id) => ChannelId::new(channel_id),
+            None => continue,
+        };
+        let message = CreateMessage::new()
+            .content(format!(
+                "🗣 {}: {}",
+                user,
+                dectalk_bot_core::preprocess::truncate(&text, 1900)
+            ))
+            .allowed_mentions(CreateAllowedMentions::new());
+        if let Err(e) = channel_id.send_message(&http, message).await {
+            debug!(error = ?e, "Failed to post transcript");
+        }
+    }
+}
+
+/// Keeps `captions.file` holding whatever the bot is saying, for OBS text sources set to read
+/// from a file. Writes go through a temporary file so OBS never sees half a caption.
+pub async fn write_captions(captions: CaptionsConfig) {
+    let temporary = captions.file.with_extension("tmp");
+    let mut events = subscribe();
+    loop {
+        let guild_id = match events.recv().await {
+            Ok(event) => event.guild_id(),
+            Err(RecvError::Lagged(skipped)) => {
+                debug!("Captions skipped {} events", skipped);
+                continue;
+            }
+            Err(RecvError::Closed) => return,
+        };
+        if captions.guild.is_some_and(|guild| guild != guild_id) {
+            continue;
+        }
+
+        if let Err(e) = write_caption(&captions.file, &temporary, &current(guild_id)).await {
+            warn!(error = ?e, "Failed to write captions");
+        }
+    }
+}
+
+async fn write_caption(path: &Path, temporary: &Path, text: &str) -> std::io::Result<()> {
+    fs::write(temporary, text).await?;
+    fs::rename(temporary, path).await
+}
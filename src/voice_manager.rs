@@ -1,11 +1,37 @@
-use std::{collections::HashMap, error::Error, sync::Arc};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tracing::{debug, error};
 
-use crate::dectalk::DectalkVoice;
-use tokio::{fs, sync::Mutex};
+use dectalk_bot_core::{dectalk::DectalkVoice, preprocess::BUILTIN_VOICES};
+
+use crate::{
+    config,
+    error::{self, BotError},
+    guild_config::{AssignedVoice, GuildConfig},
+};
+use tokio::{
+    sync::Mutex,
+    time::{self, Instant},
+};
+
+// Roll changes within this window share one write
+const SAVE_DELAY: Duration = Duration::from_secs(5);
+const REROLL_COOLDOWN: Duration = Duration::from_secs(10);
 
 pub struct VoiceManager {
-    pub voices: Arc<Mutex<HashMap<u64, DectalkVoice>>>,
+    /// Generated voices and when they were last used.
+    pub voices: Arc<Mutex<HashMap<u64, (DectalkVoice, Instant)>>>,
     pub rolls: Arc<Mutex<HashMap<u64, u64>>>,
+    /// Built-in DECtalk voices operators assigned with `/admin setvoice`, used instead of the roll.
+    pub presets: Arc<Mutex<HashMap<u64, String>>>,
+    rerolled: Mutex<HashMap<u64, Instant>>,
+    save_pending: Arc<AtomicBool>,
 }
 
 impl VoiceManager {
@@ -13,51 +39,172 @@ impl VoiceManager {
         VoiceManager {
             voices: Arc::new(Mutex::new(HashMap::new())),
             rolls: Arc::new(Mutex::new(HashMap::new())),
+            presets: Arc::new(Mutex::new(HashMap::new())),
+            rerolled: Mutex::new(HashMap::new()),
+            save_pending: Arc::new(AtomicBool::new(false)),
         }
     }
 
     pub async fn get_voice(&self, id: u64) -> DectalkVoice {
-        println!("Getting voice for {}", id);
+        debug!("Getting voice for {}", id);
         let mut voices = self.voices.lock().await;
-        if let Some(voice) = voices.get(&id) {
+        if let Some((voice, used)) = voices.get_mut(&id) {
+            *used = Instant::now();
             return voice.clone();
         }
 
-        println!("Generating voice for {}", id);
+        debug!("Generating voice for {}", id);
         let rolls = self.rolls.lock().await;
         let roll = rolls.get(&id).unwrap_or(&0);
 
         let voice = DectalkVoice::generate(id, *roll);
-        voices.insert(id, voice.clone());
+        voices.insert(id, (voice.clone(), Instant::now()));
         voice
     }
 
+    /// Generates voices ahead of time, e.g. for everyone in a call the bot just joined.
+    pub async fn prewarm(&self, ids: &[u64]) {
+        for id in ids {
+            self.get_voice(*id).await;
+        }
+    }
+
+    /// Drops voices nobody has used for `max_idle`, returning how many were dropped.
+    pub async fn evict_idle_voices(&self, max_idle: Duration) -> usize {
+        let mut voices = self.voices.lock().await;
+        let before = voices.len();
+        voices.retain(|_, (_, used)| used.elapsed() < max_idle);
+        before - voices.len()
+    }
+
     pub async fn clear_voice(&self, id: u64) {
-        println!("Clearing voice for {}", id);
+        debug!("Clearing voice for {}", id);
         self.voices.lock().await.remove(&id);
     }
 
-    pub async fn set_roll(&self, id: u64, roll: u64) -> Result<(), Box<dyn Error>> {
-        println!("Setting roll for {}: {}", id, roll);
-        self.rolls.lock().await.insert(id, roll);
+    pub async fn get_roll(&self, id: u64) -> u64 {
+        *self.rolls.lock().await.get(&id).unwrap_or(&0)
+    }
+
+    /// The user's assigned built-in voice, e.g. `betty`.
+    pub async fn get_preset(&self, id: u64) -> Option<&'static str> {
+        let presets = self.presets.lock().await;
+        let preset = presets.get(&id)?;
+        BUILTIN_VOICES
+            .iter()
+            .find(|(name, _)| name == preset)
+            .map(|(name, _)| *name)
+    }
+
+    /// Like `get_voice`, but a numbered voice the guild's staff assigned replaces the user's own.
+    pub async fn get_guild_voice(&self, id: u64, config: &GuildConfig) -> DectalkVoice {
+        match config.assigned_voices.get(&id) {
+            Some(AssignedVoice::Roll(roll)) => DectalkVoice::generate(id, *roll),
+            _ => self.get_voice(id).await,
+        }
+    }
+
+    /// Like `get_preset`, but any voice the guild's staff assigned replaces the user's own.
+    pub async fn get_guild_preset(&self, id: u64, config: &GuildConfig) -> Option<&'static str> {
+        match config.assigned_voices.get(&id) {
+            Some(assigned) => assigned.preset(),
+            None => self.get_preset(id).await,
+        }
+    }
+
+    /// Makes the user speak as a built-in voice until their roll is next changed.
+    pub async fn set_preset(&self, id: u64, preset: &str) {
+        debug!("Setting preset for {}: {}", id, preset);
+        self.presets.lock().await.insert(id, preset.to_string());
+        self.schedule_save();
+    }
+
+    /// Switches the user's voice, replacing any preset. The change is saved a few seconds later,
+    /// together with any other changes made in the meantime.
+    pub async fn set_roll(&self, id: u64, roll: u64) {
+        debug!("Setting roll for {}: {}", id, roll);
+        let had_preset = self.presets.lock().await.remove(&id).is_some();
+        if self.rolls.lock().await.insert(id, roll) == Some(roll) {
+            if had_preset {
+                self.schedule_save();
+            }
+            return;
+        }
         self.clear_voice(id).await;
-        self.save_rolls().await?;
-        Ok(())
+        self.schedule_save();
+    }
+
+    /// Like `set_roll`, but ignores users who changed their voice moments ago, so `[:roll]`
+    /// spam can't switch voices mid-conversation. Returns whether the roll was changed.
+    pub async fn reroll(&self, id: u64, roll: u64) -> bool {
+        if self.get_roll(id).await == roll {
+            return true;
+        }
+
+        let mut rerolled = self.rerolled.lock().await;
+        rerolled.retain(|_, at| at.elapsed() < REROLL_COOLDOWN);
+        if rerolled.contains_key(&id) {
+            debug!("Ignoring reroll from {}", id);
+            return false;
+        }
+        rerolled.insert(id, Instant::now());
+        drop(rerolled);
+
+        self.set_roll(id, roll).await;
+        true
+    }
+
+    fn schedule_save(&self) {
+        if self.save_pending.swap(true, Ordering::Relaxed) {
+            return;
+        }
+
+        let rolls = self.rolls.clone();
+        let presets = self.presets.clone();
+        let save_pending = self.save_pending.clone();
+        tokio::spawn(async move {
+            time::sleep(SAVE_DELAY).await;
+            save_pending.store(false, Ordering::Relaxed);
+            if let Err(e) = write_rolls(&rolls).await {
+                error!(error = ?e, "Failed to save rolls");
+            }
+            if let Err(e) = write_presets(&presets).await {
+                error!(error = ?e, "Failed to save presets");
+            }
+        });
     }
 
-    pub async fn load_rolls(&self) -> Result<(), Box<dyn Error>> {
-        println!("Loading rolls...");
-        let rolls_string = fs::read_to_string("data/rolls.json").await?;
-        let mut rolls = self.rolls.lock().await;
-        *rolls = serde_json::from_str(&rolls_string)?;
+    pub async fn load_rolls(&self) -> Result<(), BotError> {
+        debug!("Loading rolls...");
+        let rolls = error::read_json(config::get().data_path("rolls.json")).await?;
+        *self.rolls.lock().await = rolls;
         Ok(())
     }
 
-    pub async fn save_rolls(&self) -> Result<(), Box<dyn Error>> {
-        println!("Saving rolls...");
-        let rolls = self.rolls.lock().await;
-        let rolls_string = serde_json::to_string(&*rolls)?;
-        fs::write("data/rolls.json", rolls_string).await?;
+    pub async fn save_rolls(&self) -> Result<(), BotError> {
+        write_rolls(&self.rolls).await
+    }
+
+    pub async fn load_presets(&self) -> Result<(), BotError> {
+        debug!("Loading presets...");
+        let presets = error::read_json(config::get().data_path("presets.json")).await?;
+        *self.presets.lock().await = presets;
         Ok(())
     }
+
+    pub async fn save_presets(&self) -> Result<(), BotError> {
+        write_presets(&self.presets).await
+    }
+}
+
+async fn write_rolls(rolls: &Mutex<HashMap<u64, u64>>) -> Result<(), BotError> {
+    debug!("Saving rolls...");
+    let rolls = rolls.lock().await;
+    error::write_json(config::get().data_path("rolls.json"), &*rolls).await
+}
+
+async fn write_presets(presets: &Mutex<HashMap<u64, String>>) -> Result<(), BotError> {
+    debug!("Saving presets...");
+    let presets = presets.lock().await;
+    error::write_json(config::get().data_path("presets.json"), &*presets).await
 }